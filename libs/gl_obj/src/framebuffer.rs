@@ -0,0 +1,111 @@
+use std::{marker::PhantomData, rc::Rc};
+
+use super::*;
+use gl_safe::*;
+
+/// An off-screen render target.
+pub struct Framebuffer {
+	handle: GLuint,
+
+	// OpenGL functions may only be called from the thread that was used to initialize GL.
+	// Therefore, mark as !Send, !Sync to avoid accidental access from other threads
+	// (this would segfault).
+	not_send: PhantomData<Rc<()>>,
+}
+
+impl Framebuffer {
+	/// Create a framebuffer object.
+	/// http://docs.gl/gl4/glCreateFramebuffers
+	pub fn create() -> Self {
+		Self {
+			handle: glCreateFramebuffer(),
+			not_send: PhantomData,
+		}
+	}
+
+	/// Render depth values to `texture`.
+	/// The texture must have a depth internal format (e.g. `gl::DEPTH_COMPONENT24`).
+	pub fn depth_attachment(self, texture: &Texture) -> Self {
+		glNamedFramebufferTexture(self.handle, gl::DEPTH_ATTACHMENT, texture.handle(), 0);
+		self
+	}
+
+	/// Don't draw or read any colors (for depth-only rendering).
+	pub fn no_color_buffer(self) -> Self {
+		glNamedFramebufferDrawBuffer(self.handle, gl::NONE);
+		glNamedFramebufferReadBuffer(self.handle, gl::NONE);
+		self
+	}
+
+	/// Error unless the framebuffer can be rendered to
+	/// (e.g. the driver does not support the attached texture format).
+	/// http://docs.gl/gl4/glCheckFramebufferStatus
+	pub fn check_complete(self) -> Result<Self, String> {
+		let status = glCheckNamedFramebufferStatus(self.handle, gl::FRAMEBUFFER);
+		if status != gl::FRAMEBUFFER_COMPLETE {
+			return Err(format!("framebuffer {} incomplete: status 0x{:x}", self.handle, status));
+		}
+		Ok(self)
+	}
+
+	/// Render to this framebuffer from now on.
+	pub fn bind(&self) {
+		glBindFramebuffer(gl::FRAMEBUFFER, self.handle)
+	}
+
+	/// Render to the window from now on.
+	pub fn bind_default() {
+		glBindFramebuffer(gl::FRAMEBUFFER, 0)
+	}
+}
+
+impl Drop for Framebuffer {
+	fn drop(&mut self) {
+		glDeleteFramebuffer(self.handle);
+	}
+}
+
+/// A framebuffer that only records depth, into a texture that can be sampled afterwards
+/// with a `sampler2DShadow` (e.g. for shadow mapping).
+pub struct DepthTarget {
+	framebuffer: Framebuffer,
+	texture: Texture,
+}
+
+impl DepthTarget {
+	pub fn new(size: uvec2) -> Result<Self, String> {
+		let texture = Texture::new2d(gl::DEPTH_COMPONENT24, 1, size) //
+			.filter_linear()
+			.clamp_to_edge()
+			.parameteri(gl::TEXTURE_COMPARE_MODE, gl::COMPARE_REF_TO_TEXTURE as i32)
+			.parameteri(gl::TEXTURE_COMPARE_FUNC, gl::LEQUAL as i32);
+		let framebuffer = Framebuffer::create() //
+			.depth_attachment(&texture)
+			.no_color_buffer()
+			.check_complete()?;
+		Ok(Self { framebuffer, texture })
+	}
+
+	/// Render to this target (and set the viewport to cover it) from now on.
+	/// Call `Framebuffer::bind_default` to go back to rendering to the window.
+	pub fn bind(&self) {
+		self.framebuffer.bind();
+		let size = self.size();
+		glViewport(0, 0, size.x() as i32, size.y() as i32);
+	}
+
+	/// Reset all depth values to the far plane.
+	/// Must be bound.
+	pub fn clear(&self) {
+		glClear(gl::DEPTH_BUFFER_BIT)
+	}
+
+	pub fn texture(&self) -> &Texture {
+		&self.texture
+	}
+
+	pub fn size(&self) -> uvec2 {
+		let size = self.texture.size();
+		uvec2(size.x(), size.y())
+	}
+}
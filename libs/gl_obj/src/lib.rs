@@ -2,6 +2,7 @@ pub use vector::*;
 
 mod buffer;
 mod consts;
+mod framebuffer;
 mod program;
 mod shader;
 mod texture;
@@ -10,6 +11,7 @@ mod vertexarray;
 
 pub use buffer::*;
 pub use consts::*;
+pub use framebuffer::*;
 pub use program::*;
 pub use shader::*;
 pub use texture::*;
@@ -89,6 +89,10 @@ impl Texture {
 		self.size
 	}
 
+	pub fn handle(&self) -> GLuint {
+		self.handle
+	}
+
 	pub fn bind_image_unit(&self, unit: u32, access: GLenum) {
 		glBindImageTexture(unit, self.handle, 0, false, 0, access, self.internalformat());
 	}
@@ -89,6 +89,14 @@ pub fn glBlendFunc(sfactor: GLenum, dfactor: GLenum) {
 	check::gl_error()
 }
 
+/// Enable or disable writing into the depth buffer.
+/// http://docs.gl/gl4/glDepthMask
+#[allow(non_snake_case)]
+pub fn glDepthMask(flag: bool) {
+	unsafe { gl::DepthMask(flag as GLboolean) };
+	check::gl_error()
+}
+
 /// Disable server-side GL capabilities
 /// http://docs.gl/gl4/glEnable
 #[allow(non_snake_case)]
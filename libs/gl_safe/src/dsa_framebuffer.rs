@@ -0,0 +1,61 @@
+use super::check;
+use super::*;
+
+/// Create a framebuffer object.
+/// http://docs.gl/gl4/glCreateFramebuffers
+#[allow(non_snake_case)]
+pub fn glCreateFramebuffer() -> GLuint {
+	let mut result = 0;
+	unsafe { gl::CreateFramebuffers(1, &mut result) };
+	check::gl_error();
+	result
+}
+
+/// Delete a framebuffer object.
+/// http://docs.gl/gl4/glDeleteFramebuffers
+#[allow(non_snake_case)]
+pub fn glDeleteFramebuffer(framebuffer: GLuint) {
+	unsafe { gl::DeleteFramebuffers(1, &framebuffer) };
+	check::gl_error()
+}
+
+/// Bind a framebuffer to a framebuffer target (0: the default framebuffer, i.e. the window).
+/// http://docs.gl/gl4/glBindFramebuffer
+#[allow(non_snake_case)]
+pub fn glBindFramebuffer(target: GLenum, framebuffer: GLuint) {
+	unsafe { gl::BindFramebuffer(target, framebuffer) };
+	check::gl_error()
+}
+
+/// Attach a level of a texture object as a logical buffer of a framebuffer object.
+/// http://docs.gl/gl4/glFramebufferTexture
+#[allow(non_snake_case)]
+pub fn glNamedFramebufferTexture(framebuffer: GLuint, attachment: GLenum, texture: GLuint, level: i32) {
+	unsafe { gl::NamedFramebufferTexture(framebuffer, attachment, texture, level) };
+	check::gl_error()
+}
+
+/// Specify which color buffer is to be drawn into (gl::NONE: none).
+/// http://docs.gl/gl4/glDrawBuffer
+#[allow(non_snake_case)]
+pub fn glNamedFramebufferDrawBuffer(framebuffer: GLuint, buf: GLenum) {
+	unsafe { gl::NamedFramebufferDrawBuffer(framebuffer, buf) };
+	check::gl_error()
+}
+
+/// Select a color buffer source for pixels (gl::NONE: none).
+/// http://docs.gl/gl4/glReadBuffer
+#[allow(non_snake_case)]
+pub fn glNamedFramebufferReadBuffer(framebuffer: GLuint, src: GLenum) {
+	unsafe { gl::NamedFramebufferReadBuffer(framebuffer, src) };
+	check::gl_error()
+}
+
+/// Check the completeness status of a framebuffer.
+/// http://docs.gl/gl4/glCheckFramebufferStatus
+#[allow(non_snake_case)]
+pub fn glCheckNamedFramebufferStatus(framebuffer: GLuint, target: GLenum) -> GLenum {
+	let result = unsafe { gl::CheckNamedFramebufferStatus(framebuffer, target) };
+	check::gl_error();
+	result
+}
@@ -1,6 +1,7 @@
 mod check;
 mod draw;
 mod dsa_buffer;
+mod dsa_framebuffer;
 mod dsa_program;
 mod dsa_shader;
 mod dsa_texture;
@@ -10,6 +11,7 @@ mod safewrappers;
 
 pub use draw::*;
 pub use dsa_buffer::*;
+pub use dsa_framebuffer::*;
 pub use dsa_program::*;
 pub use dsa_shader::*;
 pub use dsa_texture::*;
@@ -3,6 +3,7 @@ use super::internal::*;
 pub const RED: vec3 = vec3(1.0, 0.0, 0.0);
 pub const GREEN: vec3 = vec3(0.0, 1.0, 0.0);
 pub const BLUE: vec3 = vec3(0.0, 0.0, 1.0);
+pub const LIGHT_BLUE: vec3 = vec3(0.5, 0.75, 1.0);
 
 pub const YELLOW: vec3 = vec3(1.0, 1.0, 0.0);
 
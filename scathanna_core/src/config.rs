@@ -12,7 +12,8 @@ pub struct Config {
 	/// Player nickname, e.g. "Bob".
 	pub name: String,
 
-	/// Preferred team: red|blu
+	/// Preferred team: red|blu|green.
+	/// The server only honors it if that does not unbalance teams.
 	#[serde(default)]
 	pub team: String,
 
@@ -48,6 +49,20 @@ pub struct Config {
 	#[serde(default = "f_wasd")]
 	pub movement_keys: String,
 
+	/// Don't draw the world further away than this.
+	/// (Default: 512, smaller is faster on slow hardware.)
+	#[serde(default)]
+	pub draw_distance: Option<f32>,
+
+	/// Don't draw parts of the map hidden behind walls.
+	/// Faster, but approximate: may hide what is seen through small openings.
+	#[serde(default)]
+	pub occlusion_culling: bool,
+
+	/// Let players and entities cast shadows from the sun onto the map.
+	#[serde(default)]
+	pub shadows: bool,
+
 	/// Enable sounds.
 	#[serde(default = "f_true")]
 	pub sound: bool,
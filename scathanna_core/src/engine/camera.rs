@@ -25,6 +25,11 @@ impl Camera {
 		Ray64::new(self.position.into(), self.orientation.look_dir().into())
 	}
 
+	/// What the camera sees (up to `view_distance`), for a window of size `viewport`.
+	pub fn frustum(&self, viewport: (u32, u32), view_distance: f32) -> Frustum {
+		Frustum::from_matrix(&self.matrix(viewport)).with_far_plane(self.position, self.orientation.look_dir(), view_distance)
+	}
+
 	pub fn can_see(&self, pos: vec3) -> bool {
 		// TODO: this is a crude approximation that yields many false positives,
 		// use precise frustum instead.
@@ -7,10 +7,11 @@ pub struct Engine {
 	viewport: Cell<(u32, u32)>,
 	shaders: Shaders,
 	//	cam_pos: Cell<vec3>,
-
-	//view_distance: f32,
-	sun_direction: Cell<vec3>, // unit vector towards sun
-	ambient: f32,
+	view_distance: f32,            // the world is not drawn beyond this distance.
+	occlusion_culling: bool,       // skip drawing what is hidden behind walls (see VoxelModels).
+	sun_direction: Cell<vec3>,     // unit vector towards sun
+	light: Cell<LightProbe>,       // light falling on the dynamic models drawn next (see `set_light`).
+	shadow_map: Option<ShadowMap>, // None if shadows are disabled.
 
 	tex_cache: RefCell<HashMap<String, Rc<Texture>>>,
 	obj_cache: RefCell<HashMap<String, Rc<VertexArray>>>,
@@ -37,10 +38,13 @@ impl Engine {
 		Self {
 			shaders: Shaders::new(),
 			viewport: Cell::new((0, 0)),
+			view_distance: config.draw_distance.unwrap_or(Self::DEFAULT_VIEW_DISTANCE),
+			occlusion_culling: config.occlusion_culling,
 			tex_cache: default(),
 			obj_cache: default(),
 			sun_direction: Cell::new(vec3(0.0, 1.0, 0.0)),
-			ambient: 0.2,
+			light: Cell::new(LightProbe::DEFAULT),
+			shadow_map: if config.shadows { Self::new_shadow_map() } else { None },
 			font_char_vao: Self::build_vao_(&Self::char_rect()),
 			texture_binds: Counter::new(),
 			draw_calls: Counter::new(),
@@ -51,12 +55,62 @@ impl Engine {
 		}
 	}
 
+	// Shadows are optional: without driver support, play without them.
+	fn new_shadow_map() -> Option<ShadowMap> {
+		match ShadowMap::new(ShadowMap::DEFAULT_SIZE) {
+			Ok(shadow_map) => Some(shadow_map),
+			Err(e) => {
+				eprintln!("ERROR initializing shadows: {} (continuing without shadows)", e);
+				None
+			}
+		}
+	}
+
 	// for access to counters
 	pub fn shaders(&self) -> &Shaders {
 		&self.shaders
 	}
 }
 
+// Culling
+impl Engine {
+	pub const DEFAULT_VIEW_DISTANCE: f32 = 512.0;
+
+	pub fn view_distance(&self) -> f32 {
+		self.view_distance
+	}
+
+	pub fn occlusion_culling(&self) -> bool {
+		self.occlusion_culling
+	}
+
+	/// What `camera` sees, up to the view distance, in the current viewport (see `set_camera`).
+	pub fn frustum(&self, camera: &Camera) -> Frustum {
+		camera.frustum(self.viewport(), self.view_distance)
+	}
+}
+
+// Shadows
+impl Engine {
+	/// Start rendering shadow casters (players, entities) around `center`,
+	/// if shadows are enabled (returns false otherwise, so that casters need not be drawn).
+	/// Must be followed by `end_shadow_pass`, and then `set_camera` before drawing anything else.
+	pub fn begin_shadow_pass(&self, center: vec3) -> bool {
+		match &self.shadow_map {
+			None => false,
+			Some(shadow_map) => {
+				let matrix = shadow_map.begin(self.sun_direction(), center);
+				self.shaders.set_shadow_projection(&matrix);
+				true
+			}
+		}
+	}
+
+	pub fn end_shadow_pass(&self) {
+		Framebuffer::bind_default()
+	}
+}
+
 // Sound
 
 impl Engine {
@@ -218,8 +272,14 @@ impl Engine {
 		self.sun_direction.get()
 	}
 
-	pub fn set_ambient(&mut self, ambient: f32) {
-		self.ambient = ambient
+	/// Light `MatteTexture` and `Glossy` models with `light` from now on
+	/// (e.g. sampled from the map's light probes at the position of the next model to draw).
+	pub fn set_light(&self, light: LightProbe) {
+		self.light.set(light)
+	}
+
+	pub fn light(&self) -> LightProbe {
+		self.light.get()
 	}
 
 	/// Draw model without any transforms.
@@ -245,6 +305,10 @@ impl Engine {
 	}
 
 	pub fn draw_boundingbox(&self, bounds: BoundingBox<f32>) {
+		self.draw_boundingbox_with_color(bounds, WHITE)
+	}
+
+	pub fn draw_boundingbox_with_color(&self, bounds: BoundingBox<f32>, color: vec3) {
 		self.enable_line_offset();
 
 		let BoundingBox { min, max } = bounds;
@@ -281,14 +345,38 @@ impl Engine {
 		.collect();
 
 		let buf = MeshBuffer::from_positions(pos);
-		let model = Model::new(self.build_vao(&buf), Material::UniformColor(WHITE)).with_lines();
+		let model = Model::new(self.build_vao(&buf), Material::UniformColor(color)).with_lines();
 		self.draw_model_at(&model, min);
 	}
 
+	/// Draw the faces of `bounds`, tinted with `color` (seen from inside as well as outside).
+	/// Translucent: draw after all opaque models.
+	pub fn draw_tinted_box(&self, bounds: BoundingBox<f32>, color: vec3, alpha: f32) {
+		let BoundingBox { min, max } = bounds;
+		let (x, y, z) = (max - min).into();
+		let o = 0.0;
+		let corners = [
+			[(o, o, o), (x, o, o), (x, y, o), (o, y, o)], // -z
+			[(o, o, z), (x, o, z), (x, y, z), (o, y, z)], // +z
+			[(o, o, o), (o, y, o), (o, y, z), (o, o, z)], // -x
+			[(x, o, o), (x, y, o), (x, y, z), (x, o, z)], // +x
+			[(o, o, o), (x, o, o), (x, o, z), (o, o, z)], // -y
+			[(o, y, o), (x, y, o), (x, y, z), (o, y, z)], // +y
+		];
+		let pos = corners.into_iter().flat_map(|[a, b, c, d]| [a, b, c, a, c, d]).map(vec3::from).collect();
+
+		let buf = MeshBuffer::from_positions(pos);
+		let model = Model::new(self.build_vao(&buf), Material::TranslucentColor(color, alpha)).double_sided();
+		self.set_translucent(true);
+		self.draw_model_at(&model, min);
+		self.set_translucent(false);
+	}
+
 	pub fn use_shader(&self, material: &Material, transf: &mat4) {
 		use Material::*;
 		match material {
-			UniformColor(col) => self.shaders.use_uniform_color(*col, transf),
+			UniformColor(col) => self.shaders.use_uniform_color(*col, 1.0 /*alpha*/, transf),
+			TranslucentColor(col, alpha) => self.shaders.use_uniform_color(*col, *alpha, transf),
 			VertexColor => self.shaders.use_vertex_color(transf),
 			FlatTexture(tex) => {
 				self.bind_texture(&tex, 0 /*unit*/);
@@ -296,16 +384,26 @@ impl Engine {
 			}
 			MatteTexture(tex) => {
 				self.bind_texture(&tex, 0 /*unit*/);
-				self.shaders.use_matte_texture(self.sun_direction.get(), self.ambient, transf)
+				self.shaders.use_matte_texture(self.sun_direction.get(), &self.light.get(), transf)
 			}
 			Glossy(tex) => {
 				self.bind_texture(&tex, 0 /*unit*/);
-				self.shaders.use_glossy(self.sun_direction.get(), 1.0 /*sun_intens*/, self.ambient, transf)
+				self.shaders.use_glossy(self.sun_direction.get(), &self.light.get(), transf)
 			}
-			Lightmap { texture, lightmap } => {
-				self.bind_texture(&texture, 0 /*unit*/);
-				self.bind_texture(&lightmap, 1 /*unit*/);
-				self.shaders.use_lightmap(transf)
+			Lightmap { texture, lightmap } => self.use_lightmap(texture, lightmap, 1.0 /*alpha*/, transf),
+			TranslucentLightmap { texture, lightmap, alpha } => self.use_lightmap(texture, lightmap, *alpha, transf),
+		}
+	}
+
+	// Lightmap shader, with dynamic shadows if enabled.
+	fn use_lightmap(&self, texture: &Texture, lightmap: &Texture, alpha: f32, transf: &mat4) {
+		self.bind_texture(texture, 0 /*unit*/);
+		self.bind_texture(lightmap, 1 /*unit*/);
+		match &self.shadow_map {
+			None => self.shaders.use_lightmap(alpha, transf),
+			Some(shadow_map) => {
+				self.bind_texture(shadow_map.texture(), 2 /*unit*/);
+				self.shaders.use_lightmap_shadow(alpha, self.sun_direction(), &shadow_map.matrix(), transf)
 			}
 		}
 	}
@@ -362,6 +460,19 @@ impl Engine {
 			glDisable(gl::DEPTH_TEST)
 		}
 	}
+
+	/// Blend translucent models (e.g. water) over what was drawn before.
+	/// They don't write depth, so they don't hide each other: draw them last, back to front.
+	pub fn set_translucent(&self, enable: bool) {
+		if enable {
+			glEnable(gl::BLEND);
+			glBlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+			glDepthMask(false);
+		} else {
+			glDisable(gl::BLEND);
+			glDepthMask(true);
+		}
+	}
 }
 
 // Texture management
@@ -462,12 +573,13 @@ impl Engine {
 		self.wavefront_obj_scaled(base, 1.0)
 	}
 
-	// TODO: this assumes only one scaled instance, cache collides otherwise
 	pub fn wavefront_obj_scaled(&self, base: &str, scale: f32) -> Result<Rc<VertexArray>> {
-		if !self.obj_cache.borrow().contains_key(base) {
-			self.obj_cache.borrow_mut().insert(base.to_owned(), Rc::new(self.wavefront_obj_uncached(base, scale)?));
+		// the same obj may be loaded at different scales (e.g. a gun held by players and lying around as a pickup).
+		let key = format!("{}@{}", base, scale);
+		if !self.obj_cache.borrow().contains_key(&key) {
+			self.obj_cache.borrow_mut().insert(key.clone(), Rc::new(self.wavefront_obj_uncached(base, scale)?));
 		}
-		Ok(Rc::clone(self.obj_cache.borrow().get(base).unwrap()))
+		Ok(Rc::clone(self.obj_cache.borrow().get(&key).unwrap()))
 	}
 
 	// TODO: transform func instead of scale
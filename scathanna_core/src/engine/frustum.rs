@@ -0,0 +1,86 @@
+use super::internal::*;
+
+/// The part of space that a camera can see: a truncated pyramid bounded by 6 planes.
+/// Used to skip drawing what is off-screen.
+pub struct Frustum {
+	// Left, right, bottom, top, near, far.
+	// A point `p` is on the inside of plane `(n, d)` if `n.dot(p) + d >= 0`.
+	planes: [(vec3, f32); 6],
+}
+
+impl Frustum {
+	/// The frustum of a view-projection matrix (e.g. `Camera::matrix`).
+	pub fn from_matrix(matrix: &mat4) -> Self {
+		// Gribb & Hartmann: each plane is the last row of the matrix plus or minus one of the others.
+		let m = matrix.as_array(); // column-major
+		let row = |r: usize| [m[r], m[4 + r], m[8 + r], m[12 + r]];
+		let plane = |a: [f32; 4], b: [f32; 4], sign: f32| {
+			let n = vec3(a[0] + sign * b[0], a[1] + sign * b[1], a[2] + sign * b[2]);
+			let d = a[3] + sign * b[3];
+			let len = n.len();
+			(n / len, d / len)
+		};
+		let w = row(3);
+		Self {
+			planes: [
+				plane(w, row(0), 1.0),
+				plane(w, row(0), -1.0),
+				plane(w, row(1), 1.0),
+				plane(w, row(1), -1.0),
+				plane(w, row(2), 1.0),
+				plane(w, row(2), -1.0),
+			],
+		}
+	}
+
+	/// Move the far plane to `distance` in front of `position`, looking in direction `look_dir`.
+	pub fn with_far_plane(mut self, position: vec3, look_dir: vec3, distance: f32) -> Self {
+		self.planes[5] = (-look_dir, look_dir.dot(position) + distance);
+		self
+	}
+
+	/// Is (part of) the box inside the frustum?
+	/// Conservative: may return true for boxes just outside a corner of the frustum.
+	pub fn intersects(&self, bounds: &BoundingBox<f32>) -> bool {
+		self.planes.iter().all(|&(n, d)| {
+			// the corner furthest along the plane's normal is inside, or none are.
+			let corner = vec3(
+				if n.x() >= 0.0 { bounds.max.x() } else { bounds.min.x() },
+				if n.y() >= 0.0 { bounds.max.y() } else { bounds.min.y() },
+				if n.z() >= 0.0 { bounds.max.z() } else { bounds.min.z() },
+			);
+			n.dot(corner) + d >= 0.0
+		})
+	}
+
+	pub fn contains(&self, point: vec3) -> bool {
+		self.planes.iter().all(|&(n, d)| n.dot(point) + d >= 0.0)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn camera_frustum() {
+		let camera = Camera {
+			position: vec3(10.0, 20.0, 30.0),
+			orientation: Orientation { yaw: 1.0, pitch: -0.3 },
+		};
+		let look = camera.orientation.look_dir();
+		let frustum = camera.frustum((800, 600), 100.0);
+
+		assert!(frustum.contains(camera.position + 5.0 * look));
+		assert!(frustum.contains(camera.position + 99.0 * look));
+		assert!(!frustum.contains(camera.position - 5.0 * look)); // behind
+		assert!(!frustum.contains(camera.position + 101.0 * look)); // beyond the draw distance
+
+		// a box surrounding the camera is visible, a box behind it is not.
+		let around = BoundingBox::new(camera.position - vec3(1.0, 1.0, 1.0), camera.position + vec3(1.0, 1.0, 1.0));
+		assert!(frustum.intersects(&around));
+		let behind = camera.position - 20.0 * look;
+		let behind = BoundingBox::new(behind - vec3(1.0, 1.0, 1.0), behind + vec3(1.0, 1.0, 1.0));
+		assert!(!frustum.intersects(&behind));
+	}
+}
@@ -0,0 +1,70 @@
+use super::internal::*;
+
+/// Light arriving at a point in space, used to shade dynamic models (players, entities)
+/// so that they are dark in dungeons and bright in the open (see `LightProbes`).
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct LightProbe {
+	/// "Ambient cube": indirect light (sky, bounced sunlight, glowing voxels)
+	/// received by surfaces facing -X, +X, -Y, +Y, -Z, +Z (same order as `Direction`).
+	pub ambient: [vec3; 6],
+
+	/// Fraction of the sun that is visible (0: in the shade, 1: in full sunlight).
+	pub sun: f32,
+}
+
+impl LightProbe {
+	/// Light used when nothing better is known: full sun and the same ambient from all sides.
+	pub const DEFAULT: Self = Self::uniform(0.2);
+
+	/// Full sun and `ambient` from all sides.
+	pub const fn uniform(ambient: f32) -> Self {
+		let ambient = vec3(ambient, ambient, ambient);
+		Self { ambient: [ambient; 6], sun: 1.0 }
+	}
+
+	/// Ambient light received by a surface facing `normal` (unit vector).
+	/// Must match `ambient_light` in the shaders.
+	pub fn ambient_at(&self, normal: vec3) -> vec3 {
+		let side = |axis: usize| if normal[axis] < 0.0 { 2 * axis } else { 2 * axis + 1 };
+		let n2 = normal.mul3(normal);
+		n2.x() * self.ambient[side(0)] + n2.y() * self.ambient[side(1)] + n2.z() * self.ambient[side(2)]
+	}
+
+	/// Weighted sum, for interpolating between probes.
+	pub fn weighted_sum(probes: &[(f32, LightProbe)]) -> Self {
+		let mut sum = Self { ambient: [vec3::ZERO; 6], sun: 0.0 };
+		for (w, probe) in probes {
+			for i in 0..6 {
+				sum.ambient[i] += *w * probe.ambient[i];
+			}
+			sum.sun += *w * probe.sun;
+		}
+		sum
+	}
+}
+
+impl Default for LightProbe {
+	fn default() -> Self {
+		Self::DEFAULT
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn ambient_cube() {
+		let mut probe = LightProbe::uniform(0.0);
+		probe.ambient[3] = vec3(1.0, 1.0, 1.0); // +Y: sky above
+		assert_eq!(probe.ambient_at(vec3::EY), vec3(1.0, 1.0, 1.0));
+		assert_eq!(probe.ambient_at(-vec3::EY), vec3::ZERO);
+		assert_eq!(probe.ambient_at(vec3::EX), vec3::ZERO);
+		let tilted = probe.ambient_at(vec3(1.0, 1.0, 0.0).normalized());
+		assert!((tilted.x() - 0.5).abs() < 1e-6);
+
+		let half = LightProbe::weighted_sum(&[(0.5, probe), (0.5, LightProbe::uniform(0.0))]);
+		assert_eq!(half.ambient_at(vec3::EY), vec3(0.5, 0.5, 0.5));
+		assert_eq!(half.sun, 1.0);
+	}
+}
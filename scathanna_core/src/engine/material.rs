@@ -3,9 +3,18 @@ use super::internal::*;
 #[derive(Clone)]
 pub enum Material {
 	UniformColor(vec3),
+	TranslucentColor(vec3, f32),
 	VertexColor,
 	FlatTexture(Rc<Texture>),
 	MatteTexture(Rc<Texture>),
 	Glossy(Rc<Texture>), // TODO: sun_intensity,...
 	Lightmap { texture: Rc<Texture>, lightmap: Rc<Texture> },
+	TranslucentLightmap { texture: Rc<Texture>, lightmap: Rc<Texture>, alpha: f32 },
+}
+
+impl Material {
+	/// Must be drawn after all opaque models (e.g. water).
+	pub fn is_translucent(&self) -> bool {
+		matches!(self, Material::TranslucentColor(..) | Material::TranslucentLightmap { .. })
+	}
 }
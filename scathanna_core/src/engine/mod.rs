@@ -3,12 +3,15 @@ mod internal;
 mod camera;
 mod counter;
 mod engine;
+mod frustum;
+mod light_probe;
 mod material;
 mod meshbuffer;
 mod model;
 mod modelbuffer;
 mod orientation;
 mod shaders;
+mod shadow_map;
 mod sound_pack;
 mod texture_management;
 mod util;
@@ -16,18 +19,23 @@ mod util;
 pub use camera::*;
 pub use counter::*;
 pub use engine::*;
+pub use frustum::*;
+pub use light_probe::*;
 pub use material::*;
 pub use meshbuffer::*;
 pub use model::*;
 pub use modelbuffer::*;
 pub use orientation::*;
 pub use shaders::*;
+pub use shadow_map::*;
 pub use sound_pack::*;
 pub use util::*;
 
 pub use std::rc::Rc;
 
 pub type Texture = gl_obj::Texture;
+pub type DepthTarget = gl_obj::DepthTarget;
+pub type Framebuffer = gl_obj::Framebuffer;
 pub type Program = gl_obj::Program;
 pub type VertexArray = gl_obj::VertexArray;
 pub type UniformLocation = u32;
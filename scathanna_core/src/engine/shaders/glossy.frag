@@ -9,9 +9,17 @@ out vec4 out_col;
 uniform sampler2D usampler;
 uniform vec3 sun_dir;
 uniform float sun_intens;
-uniform float ambient;
+uniform vec3 ambient_cube[6]; // -X, +X, -Y, +Y, -Z, +Z (see LightProbe)
 uniform vec3 cam_pos;
 
+// Ambient light received by a surface facing `normal` (see LightProbe::ambient_at).
+vec3 ambient_light(vec3 normal) {
+  vec3 n2 = normal * normal;
+  return n2.x * ambient_cube[normal.x < 0.0 ? 0 : 1] +
+         n2.y * ambient_cube[normal.y < 0.0 ? 2 : 3] +
+         n2.z * ambient_cube[normal.z < 0.0 ? 4 : 5];
+}
+
 void main() {
 
   vec3 normal = normalize(f_normal);
@@ -19,9 +27,7 @@ void main() {
   float costheta = dot(sun_dir, normal);
   float direct = sun_intens * max(0.0, costheta);
 
-  float dir_ambient = ambient * (0.5 * (costheta + 2.0)); // TODO
-
-  float diffuse = direct + dir_ambient;
+  vec3 diffuse = direct + ambient_light(normal);
 
   vec3 view_dir = normalize(cam_pos - f_pos);
   vec3 refl_dir = reflect(normalize(-sun_dir), normal);
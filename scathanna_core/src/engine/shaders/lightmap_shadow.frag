@@ -0,0 +1,46 @@
+#version 410 core
+
+precision highp float;
+
+in vec3 f_normal;
+in highp vec2 f_tex;
+in highp vec2 f_lightmap;
+in highp vec3 f_shadow;
+out vec4 out_col;
+uniform sampler2D texture_unit;
+uniform sampler2D lightmap_unit;
+uniform sampler2DShadow shadow_unit;
+uniform vec3 sun_dir;
+uniform float alpha;
+
+// How much of the (baked) light is taken away by a dynamic object's shadow.
+const float shadow_strength = 0.5;
+
+// Fraction of the sun blocked by shadow casters (0: not in shadow, 1: fully).
+float shadow(vec3 normal) {
+  // Outside the shadow map: no casters.
+  if (any(lessThan(f_shadow, vec3(0.0))) || any(greaterThan(f_shadow, vec3(1.0)))) {
+    return 0.0;
+  }
+  // Facing away from the sun: already in shadow in the lightmap.
+  float costheta = dot(normal, sun_dir);
+  if (costheta <= 0.0) {
+    return 0.0;
+  }
+  // Average a few samples for soft edges.
+  vec2 texel = 1.0 / vec2(textureSize(shadow_unit, 0));
+  float lit = 0.0;
+  for (int x = -1; x <= 1; x += 2) {
+    for (int y = -1; y <= 1; y += 2) {
+      lit += texture(shadow_unit, vec3(f_shadow.xy + 0.5 * vec2(x, y) * texel, f_shadow.z));
+    }
+  }
+  return min(1.0, 2.0 * costheta) * (1.0 - 0.25 * lit);
+}
+
+void main() {
+  vec4 light = texture(lightmap_unit, f_lightmap);
+  vec4 tex = texture(texture_unit, f_tex);
+  float shade = 1.0 - shadow_strength * shadow(normalize(f_normal));
+  out_col = vec4(tex.rgb * light.rgb * shade, alpha);
+}
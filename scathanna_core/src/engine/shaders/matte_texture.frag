@@ -7,10 +7,20 @@ in highp vec2 f_tex;
 out vec4 out_col;
 uniform sampler2D usampler;
 uniform vec3 sun_dir;
-uniform float ambient;
+uniform float sun_intens;
+uniform vec3 ambient_cube[6]; // -X, +X, -Y, +Y, -Z, +Z (see LightProbe)
+
+// Ambient light received by a surface facing `normal` (see LightProbe::ambient_at).
+vec3 ambient_light(vec3 normal) {
+  vec3 n2 = normal * normal;
+  return n2.x * ambient_cube[normal.x < 0.0 ? 0 : 1] +
+         n2.y * ambient_cube[normal.y < 0.0 ? 2 : 3] +
+         n2.z * ambient_cube[normal.z < 0.0 ? 4 : 5];
+}
 
 void main() {
-  float light = max(ambient, dot(f_normal, sun_dir));
+  vec3 normal = normalize(f_normal);
+  vec3 light = max(ambient_light(normal), vec3(sun_intens * dot(normal, sun_dir)));
   vec4 tex = texture(usampler, f_tex);
   out_col = vec4(tex.rgb * light, 1.0);
 }
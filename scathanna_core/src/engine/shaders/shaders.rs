@@ -31,6 +31,7 @@ pub struct Shaders {
 	matte_texture: MatteTexture,
 	glossy: Glossy,
 	lightmap: Lightmap,
+	lightmap_shadow: LightmapShadow,
 	particles: Particles,
 	font: Font,
 
@@ -59,6 +60,7 @@ impl Shaders {
 			matte_texture: MatteTexture::new(),
 			glossy: Glossy::new(),
 			lightmap: Lightmap::new(),
+			lightmap_shadow: LightmapShadow::new(),
 			particles: Particles::new(),
 			font: Font::new(),
 			projection_matrix: Cell::new(mat4::UNIT),
@@ -81,11 +83,18 @@ impl Shaders {
 		self.isometric_matrix.set(isometric_matrix((width, height)));
 	}
 
+	/// Render from the sun's point of view, with projection `matrix`, until the next `set_camera`.
+	/// (see `Engine::begin_shadow_pass`).
+	pub fn set_shadow_projection(&self, matrix: &mat4) {
+		self.projection_matrix.set(*matrix);
+	}
+
 	/// Use `uniform_color.{vert, frag}` with a uniform fragment color.
-	pub fn use_uniform_color(&self, color: vec3, transf: &mat4) {
+	pub fn use_uniform_color(&self, color: vec3, alpha: f32, transf: &mat4) {
 		let prog = &self.uniform_color.base.prog;
 		self.lazy_switch(prog);
 		prog.uniform3f(self.uniform_color.uniform_color, color.x(), color.y(), color.z());
+		prog.uniform1f(self.uniform_color.alpha, alpha);
 		prog.uniform_matrix4f(self.uniform_color.base.model, false, transf.as_array());
 		prog.uniform_matrix4f(self.uniform_color.base.proj, false, self.projection_matrix.get().as_array());
 	}
@@ -107,39 +116,57 @@ impl Shaders {
 	}
 
 	/// Use `matte_texture.{vert, frag}`.
-	pub fn use_matte_texture(&self, sun_dir: vec3, ambient: f32, transf: &mat4) {
+	pub fn use_matte_texture(&self, sun_dir: vec3, light: &LightProbe, transf: &mat4) {
 		let prog = &self.matte_texture.base.prog;
 		self.lazy_switch(prog);
 		prog.uniform3f(self.matte_texture.sun_dir, sun_dir.x(), sun_dir.y(), sun_dir.z());
-		prog.uniform1f(self.matte_texture.ambient, ambient);
+		prog.uniform1f(self.matte_texture.sun_intens, light.sun);
+		set_ambient_cube(prog, &self.matte_texture.ambient_cube, light);
 		prog.uniform_matrix4f(self.matte_texture.base.model, false, transf.as_array());
 		prog.uniform_matrix4f(self.matte_texture.base.proj, false, self.projection_matrix.get().as_array());
 	}
 
 	/// Use `glossy.{vert, frag}`.
-	pub fn use_glossy(&self, sun_dir: vec3, sun_intens: f32, ambient: f32, transf: &mat4) {
+	pub fn use_glossy(&self, sun_dir: vec3, light: &LightProbe, transf: &mat4) {
 		let shader = &self.glossy;
 		let prog = &shader.base.prog;
 		self.lazy_switch(prog);
 		let cam_pos = self.cam_pos.get();
 		prog.uniform3f(shader.sun_dir, sun_dir.x(), sun_dir.y(), sun_dir.z());
-		prog.uniform1f(shader.sun_intens, sun_intens);
-		prog.uniform1f(shader.ambient, ambient);
+		prog.uniform1f(shader.sun_intens, light.sun);
+		set_ambient_cube(prog, &shader.ambient_cube, light);
 		prog.uniform3f(shader.cam_pos, cam_pos.x(), cam_pos.y(), cam_pos.z());
 		prog.uniform_matrix4f(shader.base.model, false, transf.as_array());
 		prog.uniform_matrix4f(shader.base.proj, false, self.projection_matrix.get().as_array());
 	}
 
 	/// Use `lightmap.{vert, frag}`.
-	pub fn use_lightmap(&self, transf: &mat4) {
+	pub fn use_lightmap(&self, alpha: f32, transf: &mat4) {
 		let prog = &self.lightmap.base.prog;
 		self.lazy_switch(prog);
 		prog.uniform1i(self.lightmap.texture_unit, 0);
 		prog.uniform1i(self.lightmap.lightmap_unit, 1);
+		prog.uniform1f(self.lightmap.alpha, alpha);
 		prog.uniform_matrix4f(self.lightmap.base.model, false, transf.as_array());
 		prog.uniform_matrix4f(self.lightmap.base.proj, false, self.projection_matrix.get().as_array());
 	}
 
+	/// Use `lightmap_shadow.{vert, frag}`: `lightmap` darkened by dynamic shadows.
+	/// The shadow map (see `ShadowMap`) must be bound to texture unit 2.
+	pub fn use_lightmap_shadow(&self, alpha: f32, sun_dir: vec3, shadow_proj: &mat4, transf: &mat4) {
+		let shader = &self.lightmap_shadow;
+		let prog = &shader.base.prog;
+		self.lazy_switch(prog);
+		prog.uniform1i(shader.texture_unit, 0);
+		prog.uniform1i(shader.lightmap_unit, 1);
+		prog.uniform1i(shader.shadow_unit, 2);
+		prog.uniform3f(shader.sun_dir, sun_dir.x(), sun_dir.y(), sun_dir.z());
+		prog.uniform1f(shader.alpha, alpha);
+		prog.uniform_matrix4f(shader.shadow_proj, false, shadow_proj.as_array());
+		prog.uniform_matrix4f(shader.base.model, false, transf.as_array());
+		prog.uniform_matrix4f(shader.base.proj, false, self.projection_matrix.get().as_array());
+	}
+
 	/// Use `particles.{vert, frag}`.
 	pub fn use_particles(&self, color: vec3, alpha: f32, gravity: f32, time: f32, transf: &mat4) {
 		let prog = &self.particles.base.prog;
@@ -220,6 +247,7 @@ impl Particles {
 struct UniformColor {
 	base: SimpleShader,
 	uniform_color: UniformLocation,
+	alpha: UniformLocation,
 }
 
 impl UniformColor {
@@ -227,6 +255,7 @@ impl UniformColor {
 		let base = SimpleShader::new(include_str!("uniform_color.vert"), include_str!("uniform_color.frag"));
 		Self {
 			uniform_color: base.prog.uniform_location("color"),
+			alpha: base.prog.uniform_location("alpha"),
 			base,
 		}
 	}
@@ -236,7 +265,8 @@ impl UniformColor {
 struct MatteTexture {
 	base: SimpleShader,
 	sun_dir: UniformLocation,
-	ambient: UniformLocation,
+	sun_intens: UniformLocation,
+	ambient_cube: [UniformLocation; 6],
 }
 
 impl MatteTexture {
@@ -244,7 +274,8 @@ impl MatteTexture {
 		let base = SimpleShader::new(include_str!("matte_texture.vert"), include_str!("matte_texture.frag"));
 		Self {
 			sun_dir: base.prog.uniform_location("sun_dir"),
-			ambient: base.prog.uniform_location("ambient"),
+			sun_intens: base.prog.uniform_location("sun_intens"),
+			ambient_cube: ambient_cube_locations(&base.prog),
 			base,
 		}
 	}
@@ -255,7 +286,7 @@ struct Glossy {
 	base: SimpleShader,
 	sun_dir: UniformLocation,
 	sun_intens: UniformLocation,
-	ambient: UniformLocation,
+	ambient_cube: [UniformLocation; 6],
 	cam_pos: UniformLocation,
 }
 
@@ -265,7 +296,7 @@ impl Glossy {
 		Self {
 			sun_dir: base.prog.uniform_location("sun_dir"),
 			sun_intens: base.prog.uniform_location("sun_intens"),
-			ambient: base.prog.uniform_location("ambient"),
+			ambient_cube: ambient_cube_locations(&base.prog),
 			cam_pos: base.prog.uniform_location("cam_pos"),
 			base,
 		}
@@ -277,6 +308,7 @@ struct Lightmap {
 	base: SimpleShader,
 	texture_unit: UniformLocation,
 	lightmap_unit: UniformLocation,
+	alpha: UniformLocation,
 }
 
 impl Lightmap {
@@ -285,6 +317,33 @@ impl Lightmap {
 		Self {
 			texture_unit: base.prog.uniform_location("texture_unit"),
 			lightmap_unit: base.prog.uniform_location("lightmap_unit"),
+			alpha: base.prog.uniform_location("alpha"),
+			base,
+		}
+	}
+}
+
+/// Wraps lightmap_shadow.{vert,frag}.
+struct LightmapShadow {
+	base: SimpleShader,
+	texture_unit: UniformLocation,
+	lightmap_unit: UniformLocation,
+	shadow_unit: UniformLocation,
+	shadow_proj: UniformLocation,
+	sun_dir: UniformLocation,
+	alpha: UniformLocation,
+}
+
+impl LightmapShadow {
+	fn new() -> Self {
+		let base = SimpleShader::new(include_str!("lightmap_shadow.vert"), include_str!("lightmap_shadow.frag"));
+		Self {
+			texture_unit: base.prog.uniform_location("texture_unit"),
+			lightmap_unit: base.prog.uniform_location("lightmap_unit"),
+			shadow_unit: base.prog.uniform_location("shadow_unit"),
+			shadow_proj: base.prog.uniform_location("shadow_proj"),
+			sun_dir: base.prog.uniform_location("sun_dir"),
+			alpha: base.prog.uniform_location("alpha"),
 			base,
 		}
 	}
@@ -312,6 +371,17 @@ impl Font {
 	}
 }
 
+// Locations of `uniform vec3 ambient_cube[6]` (see LightProbe).
+fn ambient_cube_locations(prog: &Program) -> [UniformLocation; 6] {
+	[0, 1, 2, 3, 4, 5].map(|i| prog.uniform_location(&format!("ambient_cube[{}]", i)))
+}
+
+fn set_ambient_cube(prog: &Program, locations: &[UniformLocation; 6], light: &LightProbe) {
+	for (&location, color) in locations.iter().zip(light.ambient) {
+		prog.uniform3f(location, color.x(), color.y(), color.z());
+	}
+}
+
 fn compile_program(vertex_src: &str, fragment_src: &str) -> Program {
 	let vertex_shader = gl_obj::Shader::new_vertex(vertex_src);
 	let texture_shader = gl_obj::Shader::new_fragment(fragment_src);
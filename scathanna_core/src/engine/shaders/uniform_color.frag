@@ -3,6 +3,7 @@
 precision highp float;
 
 in vec3 f_col;
+uniform float alpha;
 out vec4 out_col;
 
-void main() { out_col = vec4(f_col, 1.0); }
\ No newline at end of file
+void main() { out_col = vec4(f_col, alpha); }
\ No newline at end of file
@@ -0,0 +1,107 @@
+use super::internal::*;
+
+/// Real-time sun shadows cast by dynamic objects (players, entities) onto the voxel world,
+/// which is lit by static lightmaps otherwise.
+///
+/// Shadow casters are rendered from the sun's point of view into a depth texture
+/// (see `Engine::begin_shadow_pass`), which the lightmap shader then samples
+/// to darken what lies behind them.
+pub struct ShadowMap {
+	target: DepthTarget,
+	matrix: Cell<mat4>,
+}
+
+impl ShadowMap {
+	/// Default resolution of the depth texture.
+	pub const DEFAULT_SIZE: u32 = 2048;
+
+	/// Shadows are only cast within this distance from the camera.
+	pub const RADIUS: f32 = 64.0;
+
+	/// Shadow map with a `size` x `size` depth texture.
+	/// Fails if the graphics driver cannot render to it.
+	pub fn new(size: u32) -> Result<Self> {
+		let target = DepthTarget::new(uvec2(size, size)).map_err(|e| anyhow!("shadow map: {}", e))?;
+		// Start without shadows, in case nothing is ever rendered.
+		target.bind();
+		target.clear();
+		Framebuffer::bind_default();
+		Ok(Self {
+			target,
+			matrix: Cell::new(mat4::UNIT),
+		})
+	}
+
+	/// Render shadow casters around `center` from now on.
+	/// Returns the matrix to be used as projection while rendering casters.
+	pub fn begin(&self, sun_dir: vec3, center: vec3) -> mat4 {
+		self.matrix.set(Self::sun_matrix(sun_dir, center, self.target.size().x()));
+		self.target.bind();
+		self.target.clear();
+		self.matrix.get()
+	}
+
+	/// Projection from world to shadow map coordinates, as used by the last `begin`.
+	pub fn matrix(&self) -> mat4 {
+		self.matrix.get()
+	}
+
+	pub fn texture(&self) -> &Texture {
+		self.target.texture()
+	}
+
+	/// Orthographic projection looking along the sun's rays,
+	/// covering a sphere of size `RADIUS` around `center`, for a shadow map of `size` x `size` texels.
+	pub fn sun_matrix(sun_dir: vec3, center: vec3, size: u32) -> mat4 {
+		let forward = -sun_dir.normalized();
+		let up = if forward.y().abs() > 0.99 { vec3::EX } else { vec3::EY };
+		let right = forward.cross(up).normalized();
+		let up = right.cross(forward);
+
+		// Move the center in steps of whole texels, so that shadow edges
+		// don't shimmer while the camera moves.
+		let texel = 2.0 * Self::RADIUS / (size as f32);
+		let snap = |axis: vec3| (axis.dot(center) / texel).round() * texel;
+		let (x, y, z) = (snap(right), snap(up), forward.dot(center));
+
+		let r = Self::RADIUS;
+		mat4::transpose([
+			[right.x() / r, right.y() / r, right.z() / r, -x / r],
+			[up.x() / r, up.y() / r, up.z() / r, -y / r],
+			[forward.x() / r, forward.y() / r, forward.z() / r, -z / r],
+			[0.0, 0.0, 0.0, 1.0],
+		])
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	// Apply an affine transform to a point.
+	fn project(m: &mat4, p: vec3) -> vec3 {
+		let t = m.as_array();
+		m.transform_point_ignore_w(p) + vec3(t[12], t[13], t[14])
+	}
+
+	#[test]
+	fn sun_matrix() {
+		let sun_dir = vec3(0.3, 0.6, 0.7).normalized();
+		let center = vec3(100.0, 20.0, 50.0);
+		let m = ShadowMap::sun_matrix(sun_dir, center, ShadowMap::DEFAULT_SIZE);
+
+		// the center maps to the middle of the shadow map (up to texel snapping).
+		assert!(project(&m, center).len() < 0.01);
+
+		// points closer to the sun have smaller depth, so they cast shadows on what is behind them.
+		let above = project(&m, center + 10.0 * sun_dir);
+		let below = project(&m, center - 10.0 * sun_dir);
+		assert!(above.z() < below.z());
+		assert!((above.x() - below.x()).abs() < 1e-5);
+		assert!((above.y() - below.y()).abs() < 1e-5);
+
+		// the whole radius fits in the shadow map.
+		let edge = project(&m, center + ShadowMap::RADIUS * 0.99 * vec3::EY.cross(sun_dir).normalized());
+		assert!(edge.x().abs() <= 1.0 && edge.y().abs() <= 1.0 && edge.z().abs() <= 1.0);
+	}
+}
@@ -1,15 +1,23 @@
 use super::internal::*;
+use spatial_sound::spatial_filter::low_pass;
 use spatial_sound::*;
 
 pub struct SoundPack {
 	mixer: Option<Mixer>,
 	clips: RefCell<HashMap<String, Rc<Clip>>>,
+	muffled: Cell<bool>,
 }
 
 type Clip = Vec<f32>;
 
 const AUDIO_PATH: &str = "assets/audio/";
 
+// Clips are decoded at 44.1 kHz (see `decode_44khz_mono_f32`).
+const SAMPLING_RATE: f32 = 44100.0;
+
+// Low-pass filter applied to all sounds while muffled (e.g. under water).
+const MUFFLED_CUTOFF_FREQ: f32 = 600.0;
+
 impl SoundPack {
 	pub fn new(config: &Config) -> Self {
 		let mixer = match config.sound {
@@ -22,7 +30,16 @@ impl SoundPack {
 			},
 			false => None,
 		};
-		Self { mixer, clips: default() }
+		Self {
+			mixer,
+			clips: default(),
+			muffled: Cell::new(false),
+		}
+	}
+
+	/// Muffle all sounds from now on (e.g. while the listener is under water), or stop doing so.
+	pub fn set_muffled(&self, muffled: bool) {
+		self.muffled.set(muffled)
 	}
 
 	/// Start playing an audio clip (e.g. "fight") without spatial effects.
@@ -36,7 +53,11 @@ impl SoundPack {
 	pub fn play_raw_volume(&self, clip_name: &str, volume: f32) {
 		if let Some(mixer) = &self.mixer {
 			if let Some(clip) = self.clip(clip_name) {
-				mixer.play_raw_mono(clip.iter().copied().map(|v| v * volume))
+				let src = clip.iter().copied().map(move |v| v * volume);
+				match self.muffled.get() {
+					true => mixer.play_raw_mono(low_pass(src, SAMPLING_RATE, MUFFLED_CUTOFF_FREQ)),
+					false => mixer.play_raw_mono(src),
+				}
 			}
 		}
 	}
@@ -45,7 +66,11 @@ impl SoundPack {
 	pub fn play_spatial(&self, clip_name: &str, azimuth: f32, volume: f32) {
 		if let Some(mixer) = &self.mixer {
 			if let Some(clip) = self.clip(clip_name) {
-				mixer.play_spatial(azimuth, volume, clip.iter().copied())
+				let src = clip.iter().copied();
+				match self.muffled.get() {
+					true => mixer.play_spatial(azimuth, volume, low_pass(src, SAMPLING_RATE, MUFFLED_CUTOFF_FREQ)),
+					false => mixer.play_spatial(azimuth, volume, src),
+				}
 			}
 		}
 	}
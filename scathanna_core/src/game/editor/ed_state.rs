@@ -51,6 +51,7 @@ impl EdState {
 
 	/// Mostly empty EdState to start building a new map from.
 	pub fn create_new(dir: &Path) -> Result<Self> {
+		Palette::init()?;
 		fs::create_dir_all(dir)?;
 		let mut voxels = Voxels::new();
 		voxels.set_range(&Cuboid::cube(ivec3(0, 0, 0), Voxels::CELL_SIZE), VoxelType(1));
@@ -62,6 +63,7 @@ impl EdState {
 
 	/// Load EdState from a `my_map.sc/` directory.
 	pub fn load(dir: &Path) -> Result<Self> {
+		Palette::init()?;
 		let engine = Rc::new(Engine::new(&Config::default()));
 		let voxel_world = VoxelWorld::load(&engine, dir)?;
 		let metadata = Metadata::load(&dir.join(MapData::METADATA_FILE)).unwrap_or_default();
@@ -107,6 +109,9 @@ impl EdState {
 		self.engine.draw_crosshair();
 		self.draw_spawn_points();
 		self.draw_pickup_points();
+		self.draw_flag_bases();
+		self.draw_control_zones();
+		self.draw_triggers();
 		self.draw_cursor();
 	}
 
@@ -134,28 +139,134 @@ impl EdState {
 	}
 
 	fn handle_command(&mut self, cmd: &str) {
+		if let Err(e) = self.handle_command_with_result(cmd) {
+			println!("{}", e)
+		}
+	}
+
+	fn handle_command_with_result(&mut self, cmd: &str) -> Result<()> {
+		let split = cmd.split_ascii_whitespace().collect::<Vec<_>>();
+		if split.is_empty() {
+			return Ok(()); // empty command
+		}
+		let cmd = split[0];
+		let args = &split[1..];
 		match cmd {
 			"pos" => println!("cursor position: {}", self.cursor_range().map(|c| c.min).unwrap_or_default()),
 			"spawn" => {
+				// optional team tag: `spawn red`.
+				let team = match args {
+					[] => None,
+					_ => Some(one_arg(args)?.parse()?),
+				};
 				if let Some(pos) = self.cursor_position() {
-					self.add_spawn_point(pos)
+					self.add_spawn_point(pos, team)
 				}
 			}
 			"pickup" => {
+				// optional item table: `pickup party_hat laser_rifle`.
+				let items = args.iter().map(|&item| PickupItem { item: item.into(), weight: 1.0 }).collect();
 				if let Some(pos) = self.cursor_position() {
-					self.add_pickup_point(pos)
+					self.add_pickup_point(pos, items)?
 				}
 			}
-			unknown => println!("unknown command: {}", unknown),
+			"zone" => {
+				if let Some(range) = self.cursor_range() {
+					self.add_control_zone(range)
+				}
+			}
+			"jumppad" => {
+				// launch speed, in the direction the camera is looking.
+				let speed: f32 = one_arg(args)?.parse()?;
+				if let Some(range) = self.cursor_range() {
+					self.add_jump_pad(range, speed * self.camera.orientation.look_dir())
+				}
+			}
+			"teleport" => {
+				if let Some(range) = self.cursor_range() {
+					self.add_teleporter(range)
+				}
+			}
+			"destination" => {
+				if let Some(pos) = self.cursor_position() {
+					self.set_teleport_destination(pos)?
+				}
+			}
+			"flag" => {
+				let team = one_arg(args)?.parse()?;
+				if let Some(pos) = self.cursor_position() {
+					self.add_flag_base(pos, team)
+				}
+			}
+			unknown => return Err(anyhow!("unknown command: {}", unknown)),
 		}
+		Ok(())
 	}
 
-	fn add_spawn_point(&mut self, pos: ivec3) {
-		self.metadata.spawn_points.push(SpawnPoint { pos });
+	// Add a spawn point where players face the same way as the editor camera.
+	fn add_spawn_point(&mut self, pos: ivec3, team: Option<Team>) {
+		let orientation = self.camera.orientation;
+		self.metadata.spawn_points.push(SpawnPoint { pos, team, orientation });
 	}
 
-	fn add_pickup_point(&mut self, pos: ivec3) {
-		self.metadata.pickup_points.push(PickupPoint { pos, taken: true });
+	fn add_pickup_point(&mut self, pos: ivec3, items: Vec<PickupItem>) -> Result<()> {
+		let pickup_point = PickupPoint::new(pos, items);
+		pickup_point.validate()?;
+		self.metadata.pickup_points.push(pickup_point);
+		Ok(())
+	}
+
+	// Set a team's flag base (capture-the-flag),
+	// replacing the previous one as there is only one flag per team.
+	fn add_flag_base(&mut self, pos: ivec3, team: Team) {
+		self.metadata.flag_bases.retain(|b| b.team != team);
+		self.metadata.flag_bases.push(FlagBase { pos, team });
+	}
+
+	fn add_jump_pad(&mut self, range: Cuboid, velocity: vec3) {
+		let action = TriggerAction::JumpPad { velocity };
+		self.metadata.triggers.push(Trigger {
+			min: range.min,
+			max: range.max,
+			action,
+		});
+	}
+
+	// Add a teleporter covering the cursor range.
+	// Its destination is set by a subsequent `destination` command.
+	fn add_teleporter(&mut self, range: Cuboid) {
+		let destination = range.min.to_f32();
+		let action = TriggerAction::Teleport { destination, orientation: default() };
+		self.metadata.triggers.push(Trigger {
+			min: range.min,
+			max: range.max,
+			action,
+		});
+		self.log("teleporter added, use `destination` to set where it goes".into());
+	}
+
+	// Set the most recently added teleporter's destination,
+	// where players face the same way as the editor camera.
+	fn set_teleport_destination(&mut self, pos: ivec3) -> Result<()> {
+		let orientation = self.camera.orientation;
+		let action = self
+			.metadata
+			.triggers
+			.iter_mut()
+			.rev()
+			.map(|t| &mut t.action)
+			.find(|a| matches!(a, TriggerAction::Teleport { .. }))
+			.ok_or(anyhow!("no teleporter yet, add one with `teleport`"))?;
+		*action = TriggerAction::Teleport {
+			destination: pos.to_f32(),
+			orientation,
+		};
+		Ok(())
+	}
+
+	// Add a control zone (king-of-the-hill) covering the cursor range.
+	fn add_control_zone(&mut self, range: Cuboid) {
+		self.metadata.control_zones.push(ControlZone { min: range.min, max: range.max });
 	}
 
 	fn handle_save(&self) {
@@ -280,6 +391,29 @@ impl EdState {
 		}
 	}
 
+	fn draw_flag_bases(&self) {
+		for base in &self.metadata.flag_bases {
+			let model = self.models.entity_model(EKind::Flag { team: base.team });
+			self.engine.draw_model_at(model, base.position());
+		}
+	}
+
+	// Outline jump pads and teleporters, and draw teleporter destinations like spawn points.
+	fn draw_triggers(&self) {
+		for trigger in &self.metadata.triggers {
+			self.engine.draw_boundingbox_with_color(trigger.bounds(), trigger.color());
+			if let TriggerAction::Teleport { destination, .. } = trigger.action {
+				self.engine.draw_model_at(&self.spawn_point_model, destination);
+			}
+		}
+	}
+
+	fn draw_control_zones(&self) {
+		for zone in &self.metadata.control_zones {
+			self.engine.draw_boundingbox_with_color(zone.bounds(), YELLOW);
+		}
+	}
+
 	fn cursor_range(&self) -> Option<Cuboid> {
 		self.crosshair_hitpoint(-0.1).map(|hitpoint| self.aligned_cursor_range(hitpoint))
 	}
@@ -10,7 +10,12 @@ pub struct ClientState {
 	player_id: ID,
 	world: World,
 	hud: HUD,
+	zones: Vec<ZoneOwner>,
+	spectate_target: Option<ID>,
 	pending_diffs: Vec<ClientMsg>,
+
+	// Voxel ranges changed by the server, to be re-meshed by the renderer.
+	dirty_voxels: Vec<Cuboid>,
 }
 
 impl ClientState {
@@ -19,8 +24,11 @@ impl ClientState {
 			player_id,
 			world,
 			hud: default(),
+			zones: default(),
+			spectate_target: None,
 			engine,
 			pending_diffs: default(),
+			dirty_voxels: default(),
 		}
 	}
 
@@ -33,7 +41,10 @@ impl ClientState {
 			AddPlayer(player) => self.handle_add_player(player),
 			MovePlayer(player_id, frame) => self.handle_move_player(player_id, frame),
 			UpdatePlayer(player) => self.handle_update_player(player),
-			ForceMovePlayer(position) => self.handle_force_move_player(position),
+			ForceMovePlayer(position, orientation) => self.handle_force_move_player(position, orientation),
+			CorrectMove(frame, steps) => self.handle_correct_move(frame, steps),
+			Knockback(impulse) => self.handle_knockback(impulse),
+			SetRules(rules) => self.handle_set_rules(rules),
 			UpdateEntity(entity) => self.handle_update_entity(entity),
 			RemoveEntity(entity_id) => self.handle_remove_entity(entity_id),
 			DropPlayer(player_id) => self.handle_drop_player(player_id),
@@ -41,7 +52,11 @@ impl ClientState {
 			PlaySound(sound_effect) => self.handle_play_sound(&sound_effect),
 			RequestRespawn(spawn_point) => self.handle_request_respawn(spawn_point),
 			UpdateHUD(update) => self.handle_update_hud(update),
+			UpdateZones(zones) => self.handle_update_zones(zones),
+			GiveWeapon(weapon) => self.handle_give_weapon(weapon),
+			SetVoxels { range, value } => self.handle_set_voxels(range, value),
 			SwitchMap { .. } => panic!("BUG: SwitchMap must be handled by NetClient"),
+			JoinRefused(_) => panic!("BUG: JoinRefused must be handled by NetClient"),
 		}
 	}
 
@@ -66,11 +81,31 @@ impl ClientState {
 		let mut new = new;
 		new.local = old.local.clone();
 		new.skeleton.set_frame(old.skeleton.frame());
+		if new.id == self.player_id {
+			// weapon is controlled locally (synced to server).
+			new.weapon = old.weapon;
+		}
 		*old = new;
 	}
 
-	fn handle_force_move_player(&mut self, position: vec3) {
+	fn handle_force_move_player(&mut self, position: vec3, orientation: Orientation) {
 		self.local_player_mut().skeleton.position = position;
+		self.local_player_mut().skeleton.orientation = orientation;
+		self.local_player_mut().local.reorient = Some(orientation);
+	}
+
+	fn handle_correct_move(&mut self, frame: Frame, steps: u64) {
+		let mut player = self.local_player().clone();
+		player.reconcile(frame, steps, &self.world);
+		*self.local_player_mut() = player;
+	}
+
+	fn handle_knockback(&mut self, impulse: vec3) {
+		self.local_player_mut().local.knockback.push_back(impulse);
+	}
+
+	fn handle_set_rules(&mut self, rules: GameRules) {
+		self.world.rules = rules;
 	}
 
 	fn handle_update_entity(&mut self, entity: Entity) {
@@ -87,8 +122,13 @@ impl ClientState {
 
 	fn handle_request_respawn(&mut self, spawn_point: SpawnPoint) {
 		self.local_player_mut().next_spawn_point = spawn_point.position();
+		self.local_player_mut().next_spawn_orientation = spawn_point.orientation();
 		self.local_player_mut().skeleton.velocity = vec3::ZERO;
-		self.local_player_mut().skeleton.orientation.pitch = 0.0;
+
+		// weapons are lost on death.
+		self.local_player_mut().local.ammo = default();
+		self.local_player_mut().weapon = default();
+		self.pending_diffs.push(ClientMsg::SwitchWeapon(default()));
 	}
 
 	fn handle_add_effect(&mut self, effect: Effect) {
@@ -99,6 +139,45 @@ impl ClientState {
 		self.hud.update(upd)
 	}
 
+	fn handle_update_zones(&mut self, zones: Vec<ZoneOwner>) {
+		let status = zones
+			.iter()
+			.enumerate()
+			.map(|(i, &owner)| (owner.color(self.player_id), format!("zone {}: {}", i + 1, self.describe_owner(owner))))
+			.collect();
+		self.hud.set_zones(status);
+		self.zones = zones;
+	}
+
+	// E.g. "Red team", "you", "alice".
+	fn describe_owner(&self, owner: ZoneOwner) -> String {
+		match owner {
+			ZoneOwner::Nobody => "neutral".into(),
+			ZoneOwner::Team(team) => format!("{} team", team),
+			ZoneOwner::Player(player_id) if player_id == self.player_id => "you".into(),
+			ZoneOwner::Player(player_id) => self.world.players.get(player_id).map(|p| p.name.clone()).unwrap_or_default(),
+			ZoneOwner::Contested => "contested".into(),
+		}
+	}
+
+	// Picked up a weapon: add its ammo and take it in hand.
+	fn handle_give_weapon(&mut self, weapon: Weapon) {
+		let mut player = self.local_player().clone();
+		player.local.ammo[weapon.index()] += weapon.ammo().unwrap_or_default();
+		player.switch_weapon(&mut self.pending_diffs, weapon);
+		*self.local_player_mut() = player;
+	}
+
+	fn handle_set_voxels(&mut self, range: Cuboid, value: VoxelType) {
+		self.world.map.voxels.set_range(&range, value);
+		self.dirty_voxels.push(range);
+	}
+
+	/// Voxel ranges changed since the last call, whose models need to be re-built.
+	pub fn take_dirty_voxels(&mut self) -> Vec<Cuboid> {
+		mem::take(&mut self.dirty_voxels)
+	}
+
 	// __________________________________________________________ sound
 
 	fn handle_play_sound(&self, sound: &SoundEffect) {
@@ -133,26 +212,32 @@ impl ClientState {
 			let distance2 = (ear_pos - sound_pos).len2();
 			let falloff_volume = (volume * (UNIT_DIST * UNIT_DIST) / distance2).clamp(0.0, 1.0);
 			// muffle sound when obstructed by a wall
-			let obstructed_volume = if self.is_obstructed(ear_pos, sound_pos) { 0.3 * falloff_volume } else { falloff_volume };
+			let obstructed_volume = if self.world.is_obstructed(ear_pos, sound_pos) { 0.3 * falloff_volume } else { falloff_volume };
 			self.engine.sound().play_spatial(clip_name, azimuth, obstructed_volume)
 		}
 	}
 
-	// does a wall obstruct the line of sight between two positions?
-	fn is_obstructed(&self, pos1: vec3, pos2: vec3) -> bool {
-		let dir = (pos2 - pos1).normalized();
-		let len = (pos2 - pos1).len();
-		let ray = Ray64::new(pos1.into(), dir.into());
-		let t = self.world.map.intersect(&ray).unwrap_or(f64::INFINITY) as f32;
-		t < len
+	// Is the camera under water? (Muffles all sounds).
+	fn is_under_water(&self) -> bool {
+		self.world.map.voxels.at_pos(self.camera_player().camera().position).is_transparent()
 	}
 
 	// __________________________________________________________ local control
 
-	pub fn tick(&mut self, input_state: &InputState, dt: f32) -> ClientMsgs {
+	pub fn tick(&mut self, input_state: &mut InputState, dt: f32) -> ClientMsgs {
+		// just (re)spawned or teleported: look the way the spawn point faces,
+		// rather than the way the mouse was pointing.
+		if let Some(orientation) = self.local_player_mut().local.reorient.take() {
+			input_state.set_mouse(orientation.yaw, orientation.pitch);
+		}
+
 		self.control_player(input_state, dt);
+		self.control_spectating(input_state);
+		self.engine.sound().set_muffled(self.is_under_water());
 
 		self.extrapolate_other_players(dt);
+		self.extrapolate_projectiles(dt);
+		self.tick_powerup_countdown(dt);
 		self.animate_footsteps(dt);
 		self.tick_effects(dt);
 		self.hud.tick(dt);
@@ -178,6 +263,9 @@ impl ClientState {
 				AddEffect(effect) => self.handle_add_effect(effect.clone()),
 				PlaySound(sound) => self.handle_play_sound(sound),
 				HitPlayer { .. } => (/* handled by server*/),
+				HitVoxel(_) => (/*handled by server*/),
+				SwitchWeapon(_) => (/*already applied locally by control*/),
+				Fire(_) => (/*handled by server*/),
 				ReadyToSpawn => (/*handled by server*/),
 				Command(_) => (/*handled by server*/),
 			}
@@ -191,6 +279,26 @@ impl ClientState {
 		*self.local_player_mut() = clone;
 	}
 
+	/// While eliminated (waiting for the next round), look through the eyes of another player.
+	/// Clicking switches to the next player still in the game.
+	fn control_spectating(&mut self, input_state: &InputState) {
+		if !self.local_player().spectating {
+			self.spectate_target = None;
+			return;
+		}
+
+		let mut alive = self.world.players.iter().filter(|(_, p)| p.spawned).map(|(id, _)| id).collect::<SmallVec<_>>();
+		alive.sort();
+
+		let current = self.spectate_target.filter(|id| alive.contains(id));
+		if current.is_none() || input_state.is_pressed(Key::Mouse1) {
+			self.spectate_target = match current {
+				None => alive.first().copied(),
+				Some(current) => alive.iter().copied().find(|&id| id > current).or(alive.first().copied()),
+			}
+		}
+	}
+
 	/// Extrapolate other player's positions based on their last know velocity.
 	/// This greatly reduces positional stutter in the face of network latency.
 	fn extrapolate_other_players(&mut self, dt: f32) {
@@ -201,6 +309,23 @@ impl ClientState {
 		}
 	}
 
+	/// Count down the local player's timed powerup for display on the HUD.
+	/// (The server does the same, and is the one to decide when the powerup expires.)
+	fn tick_powerup_countdown(&mut self, dt: f32) {
+		if let Some(ttl) = &mut self.local_player_mut().powerup_ttl {
+			*ttl = (*ttl - dt).max(0.0);
+		}
+	}
+
+	/// Move projectiles along their trajectory in between server updates.
+	fn extrapolate_projectiles(&mut self, dt: f32) {
+		for entity in self.world.entities.values_mut() {
+			if entity.kind.is_projectile() {
+				entity.extrapolate(dt);
+			}
+		}
+	}
+
 	/// Animate the players feet if they are moving.
 	/// This is done locally by each client (do not send feet position over the network all the time).
 	/// Also generate footstep, jump,... sounds locally (do not send these sound effects over the network).
@@ -223,11 +348,11 @@ impl ClientState {
 				// make one's own footsteps less loud
 				// (quite distracting otherwise)
 				let volume = if player_id == self.player_id { 0.05 } else { 0.3 };
+				let footsteps = &self.world.players[player_id].skeleton.ground_voxel(&self.world).def().footsteps;
 				self.play_sound_spatial(
-					if self.world.players[player_id].is_on_lava(&self.world) {
-						"lava"
-					} else {
-						Self::random_footstep_clip()
+					match footsteps.is_empty() {
+						true => Self::random_footstep_clip(),
+						false => &footsteps[rand::thread_rng().gen_range(0..footsteps.len())],
 					},
 					volume,
 					&Spatial {
@@ -297,9 +422,20 @@ impl ClientState {
 		self.player_id
 	}
 
+	/// The player whose eyes we look through:
+	/// normally the local player, but another player while spectating.
+	pub fn camera_player(&self) -> &Player {
+		self.spectate_target.and_then(|id| self.world.players.get(id)).unwrap_or(self.local_player())
+	}
+
 	pub fn hud(&self) -> &HUD {
 		&self.hud
 	}
+
+	/// Current owner of each of the map's control zones (king-of-the-hill).
+	pub fn zones(&self) -> &[ZoneOwner] {
+		&self.zones
+	}
 }
 
 fn azimuth(frame: &Frame, sound_pos: vec3) -> f32 {
@@ -0,0 +1,56 @@
+use super::internal::*;
+
+/// A region of the map that teams or players score by holding (king-of-the-hill).
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct ControlZone {
+	pub min: ivec3,
+	pub max: ivec3,
+}
+
+impl ControlZone {
+	pub fn bounds(&self) -> BoundingBox<f32> {
+		BoundingBox::new(self.min.to_f32(), self.max.to_f32())
+	}
+}
+
+/// Who holds a control zone.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ZoneOwner {
+	#[default]
+	Nobody,
+	Team(Team),
+	Player(ID),
+	// Players of more than one side inside: nobody scores.
+	Contested,
+}
+
+impl ZoneOwner {
+	/// Owner of a zone occupied by the given sides (one per player inside).
+	pub fn from_occupants(sides: impl Iterator<Item = Side>) -> Self {
+		let mut owner = ZoneOwner::Nobody;
+		for side in sides {
+			let side = match side {
+				Side::Team(team) => ZoneOwner::Team(team),
+				Side::Player(player_id) => ZoneOwner::Player(player_id),
+			};
+			owner = match owner {
+				ZoneOwner::Nobody => side,
+				owner if owner == side => owner,
+				_ => return ZoneOwner::Contested,
+			}
+		}
+		owner
+	}
+
+	/// Color to draw the zone with, as seen by player `viewer`:
+	/// team color, green if held by the viewer, red if held by another player.
+	pub fn color(self, viewer: ID) -> vec3 {
+		match self {
+			ZoneOwner::Nobody => GREY,
+			ZoneOwner::Team(team) => team.color_filter(),
+			ZoneOwner::Player(player_id) if player_id == viewer => GREEN,
+			ZoneOwner::Player(_) => RED,
+			ZoneOwner::Contested => WHITE,
+		}
+	}
+}
@@ -53,9 +53,7 @@ impl Effect {
 		}
 	}
 
-	pub fn ricochet(start: vec3, color_filter: vec3) -> Self {
-		println!("ricochet");
-		let mut rng = rand::thread_rng();
+	pub fn ricochet(rng: &mut impl Rng, start: vec3, color_filter: vec3) -> Self {
 		let len = 50.0;
 		let orientation = Orientation {
 			pitch: rng.gen_range(20.0 * DEG..90.0 * DEG),
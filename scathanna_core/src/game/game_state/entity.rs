@@ -1,27 +1,31 @@
 use super::internal::*;
-use std::sync::atomic::AtomicU64;
-use std::sync::atomic::Ordering;
+use rand::distributions::{Distribution, WeightedIndex};
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Entity {
 	id: EID,
 	pub position: vec3,
+	pub velocity: vec3, // only projectiles move (see projectile.rs).
 	pub kind: EKind,
 }
 
 pub type Entities = HashMap<EID, Entity>;
 
+/// Key for identifying entities (handed out by the server, see `ServerState::new_entity`).
 pub type EID = u64;
-static NEXT_ENTITY_ID: AtomicU64 = AtomicU64::new(1);
-
-// A fresh, unique entity number.
-fn new_entity_id() -> EID {
-	NEXT_ENTITY_ID.fetch_add(1, Ordering::SeqCst)
-}
 
 impl Entity {
-	pub fn new(position: vec3, kind: EKind) -> Self {
-		Self { id: new_entity_id(), position, kind }
+	pub fn new(id: EID, position: vec3, kind: EKind) -> Self {
+		Self {
+			id,
+			position,
+			velocity: vec3::ZERO,
+			kind,
+		}
+	}
+
+	pub fn with_velocity(self, velocity: vec3) -> Self {
+		Self { velocity, ..self }
 	}
 
 	pub fn id(&self) -> EID {
@@ -42,39 +46,52 @@ impl Entity {
 #[derive(Serialize, Deserialize, Clone, Debug, Copy, PartialEq, Eq)]
 pub enum EKind {
 	GiftBox { pickup_point_id: Option<usize> },
-	CowboyHat,
-	BerserkerHelmet,
-	PartyHat,
-	XMasHat,
+	Powerup(PowerupID), // defined in assets/powerups.json
+	Flag { team: Team },
+	Gun(Weapon),
+	Rocket { owner: ID },
+	Grenade { owner: ID },
 }
 
 use EKind::*;
 
 impl EKind {
-	pub const ALL_POWERUPS: [EKind; 5] = [
-		GiftBox { pickup_point_id: None },
-		CowboyHat, //
-		BerserkerHelmet,
-		PartyHat,
-		XMasHat,
-	];
+	// Gift boxes turn into one of these (weapons included).
+	pub fn all_powerups() -> impl Iterator<Item = EKind> {
+		let builtin = [
+			GiftBox { pickup_point_id: None },
+			Gun(Weapon::ConfettiBlaster),
+			Gun(Weapon::LaserRifle),
+			Gun(Weapon::CarrotLauncher),
+			Gun(Weapon::ConfettiGrenade),
+		];
+		PowerupTable::get().ids().map(Powerup).chain(builtin)
+	}
 
-	pub const ALL_EKINDS: [EKind; 5] = [
-		GiftBox { pickup_point_id: None },
-		CowboyHat, //
-		BerserkerHelmet,
-		PartyHat,
-		XMasHat,
+	pub fn all_ekinds() -> impl Iterator<Item = EKind> {
+		Self::all_powerups().chain(Self::OTHER_EKINDS)
+	}
+
+	// All kinds that are not in `all_powerups`.
+	const OTHER_EKINDS: [EKind; 5] = [
+		Flag { team: Team::Red },
+		Flag { team: Team::Blue },
+		Flag { team: Team::Green },
+		Rocket { owner: 0 },
+		Grenade { owner: 0 },
 	];
 
 	pub fn as_str(self) -> &'static str {
 		use EKind::*;
 		match self {
 			GiftBox { .. } => "gift_box",
-			CowboyHat => "cowboy_hat",
-			BerserkerHelmet => "berserker_helmet",
-			PartyHat => "party_hat",
-			XMasHat => "xmas_hat",
+			Powerup(id) => &PowerupTable::get()[id].name,
+			Flag { team: Team::Red } => "red_flag",
+			Flag { team: Team::Blue } => "blue_flag",
+			Flag { team: Team::Green } => "green_flag",
+			Gun(weapon) => weapon.as_str(),
+			Rocket { .. } => "rocket",
+			Grenade { .. } => "grenade",
 			//_ => "thingamabob",
 		}
 	}
@@ -82,20 +99,86 @@ impl EKind {
 	pub fn description(self) -> &'static str {
 		use EKind::*;
 		match self {
-			CowboyHat => "Shoot as fast as you can pull the trigger",
-			BerserkerHelmet => "It's self-explanatory",
-			PartyHat => "One extra life, love lava",
-			XMasHat => "Unlimited jumping",
+			Powerup(id) => &PowerupTable::get()[id].description,
 			GiftBox { .. } => "There's a prize on your head",
+			Flag { .. } => "Bring it home",
+			Gun(weapon) => weapon.description(),
+			Rocket { .. } => "Incoming!",
+			Grenade { .. } => "Duck and cover",
+		}
+	}
+
+	/// Name of the wavefront file used to draw this kind of entity.
+	/// Usually the same as `as_str`, except for kinds that only differ in texture.
+	pub fn obj_name(self) -> &'static str {
+		match self {
+			Flag { .. } => "flag",
+			Powerup(id) => PowerupTable::get()[id].model(),
+			Gun(weapon) => weapon.obj_name(),
+			Rocket { .. } => "carrot",
+			Grenade { .. } => "box",
+			other => other.as_str(),
+		}
+	}
+
+	/// Color to draw with in case no texture file is found.
+	pub fn fallback_color(self) -> vec3 {
+		match self {
+			Flag { team } => team.color_filter(),
+			Gun(weapon) => weapon.fallback_color(),
+			Rocket { .. } => ORANGE,
+			Grenade { .. } => MAGENTA,
+			_ => GREY,
+		}
+	}
+
+	/// Sound played when picked up.
+	pub fn sound(self) -> &'static str {
+		match self {
+			Powerup(id) => PowerupTable::get()[id].sound(),
+			other => other.as_str(),
+		}
+	}
+
+	/// Seconds before this powerup wears off (`None`: lasts until death).
+	pub fn duration(self) -> Option<f32> {
+		match self {
+			Powerup(id) => PowerupTable::get()[id].duration,
+			_ => None,
+		}
+	}
+
+	/// What this kind of entity does when worn as a powerup.
+	pub fn effects(self) -> &'static PowerupEffects {
+		match self {
+			Powerup(id) => &PowerupTable::get()[id].effects,
+			_ => &NO_EFFECTS,
+		}
+	}
+
+	/// Relative odds of a gift box turning into this powerup.
+	fn spawn_weight(self) -> f32 {
+		match self {
+			Powerup(id) => PowerupTable::get()[id].spawn_weight,
+			_ => 1.0,
+		}
+	}
+
+	/// Size at which to draw this kind of entity.
+	pub fn model_scale(self) -> f32 {
+		match self {
+			Rocket { .. } => 1.5,
+			Grenade { .. } => 1.0,
+			_ => 3.0,
 		}
 	}
 
 	// pick a random powerup, except the one that the player already has.
-	pub fn random_powerup_except(current: Option<EKind>) -> EKind {
+	pub fn random_powerup_except(rng: &mut impl Rng, current: Option<EKind>) -> EKind {
 		match current {
-			None => Self::random_powerup(),
+			None => Self::random_powerup(rng),
 			Some(current) => loop {
-				let rand = Self::random_powerup();
+				let rand = Self::random_powerup(rng);
 				if rand != current {
 					return rand;
 				}
@@ -103,9 +186,11 @@ impl EKind {
 		}
 	}
 
-	// pick a random powerup.
-	pub fn random_powerup() -> EKind {
-		Self::ALL_POWERUPS[rand::thread_rng().gen_range(0..Self::ALL_POWERUPS.len())]
+	// pick a random powerup, according to spawn weights.
+	pub fn random_powerup(rng: &mut impl Rng) -> EKind {
+		let powerups = Self::all_powerups().collect::<SmallVec<_>>();
+		let weights = WeightedIndex::new(powerups.iter().map(|p| p.spawn_weight())).expect("powerup spawn weights");
+		powerups[weights.sample(rng)]
 	}
 
 	/// Check if `self` and `other` have the same enum discriminant.
@@ -119,8 +204,9 @@ impl FromStr for EKind {
 	type Err = Error;
 
 	fn from_str(s: &str) -> Result<Self> {
-		Self::ALL_EKINDS //
-			.into_iter()
+		Self::all_ekinds() //
+			.filter(|k| !matches!(k, Flag { .. })) // flags are managed by capture-the-flag, not summoned.
+			.filter(|k| !k.is_projectile()) // projectiles are fired, not summoned.
 			.filter(|k| k.as_str() == s)
 			.next()
 			.ok_or(anyhow!("unknown entity: {}", s))
@@ -0,0 +1,15 @@
+use super::internal::*;
+
+/// Where a team's flag sits when not taken (capture-the-flag).
+/// Carrying the enemy flag to your own base scores a capture.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct FlagBase {
+	pub pos: ivec3,
+	pub team: Team,
+}
+
+impl FlagBase {
+	pub fn position(&self) -> vec3 {
+		self.pos.to_f32()
+	}
+}
@@ -3,6 +3,9 @@ use super::internal::*;
 pub enum GameType {
 	DeadMatch(DeadMatch),
 	TeamMatch(TeamMatch),
+	CaptureTheFlag(CaptureTheFlag),
+	KingOfTheHill(KingOfTheHill),
+	Elimination(Elimination),
 }
 
 impl GameType {
@@ -11,10 +14,66 @@ impl GameType {
 		match self {
 			DeadMatch(_) => false,
 			TeamMatch(_) => true,
+			CaptureTheFlag(_) => true,
+			KingOfTheHill(koth) => koth.teams,
+			Elimination(el) => el.teams,
+		}
+	}
+
+	/// Per-team scores, if this is a team game.
+	pub fn team_score(&self) -> Option<&[i32; NUM_TEAMS]> {
+		use GameType::*;
+		match self {
+			DeadMatch(_) => None,
+			TeamMatch(tm) => Some(&tm.team_score),
+			CaptureTheFlag(ctf) => Some(&ctf.team_score),
+			KingOfTheHill(koth) => koth.teams.then_some(&koth.team_score),
+			Elimination(el) => el.teams.then_some(&el.team_score),
+		}
+	}
+
+	/// Rules that differ per game type unless overridden on the command line (see ServerOpts).
+	pub fn default_rules(&self) -> GameRules {
+		use GameType::*;
+		match self {
+			DeadMatch(_) | TeamMatch(_) => GameRules {
+				player_collision: false,
+				knockback: true,
+			},
+			CaptureTheFlag(_) | KingOfTheHill(_) => GameRules {
+				player_collision: true,
+				knockback: true,
+			},
+			Elimination(_) => GameRules {
+				player_collision: true,
+				knockback: false,
+			},
+		}
+	}
+
+	pub fn team_score_mut(&mut self) -> Option<&mut [i32; NUM_TEAMS]> {
+		use GameType::*;
+		match self {
+			DeadMatch(_) => None,
+			TeamMatch(tm) => Some(&mut tm.team_score),
+			CaptureTheFlag(ctf) => Some(&mut ctf.team_score),
+			KingOfTheHill(koth) => koth.teams.then_some(&mut koth.team_score),
+			Elimination(el) => el.teams.then_some(&mut el.team_score),
 		}
 	}
 }
 
+/// Gameplay rules that clients need to know about too (sent with ServerMsg::SetRules),
+/// because they affect movement, which is simulated client-side.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct GameRules {
+	/// Players block each other (otherwise they walk right through).
+	pub player_collision: bool,
+
+	/// Shots and explosions push players away.
+	pub knockback: bool,
+}
+
 impl FromStr for GameType {
 	type Err = Error;
 
@@ -22,7 +81,17 @@ impl FromStr for GameType {
 		Ok(match s {
 			"dm" | "deathmatch" => GameType::DeadMatch(default()),
 			"tm" | "team" | "teammatch" => GameType::TeamMatch(default()),
-			bad => return Err(anyhow!("unknown game type `{}`, options: `deathmatch`, `teammatch`", bad)),
+			"ctf" | "capturetheflag" => GameType::CaptureTheFlag(default()),
+			"koth" | "kingofthehill" => GameType::KingOfTheHill(KingOfTheHill::new(false)),
+			"tkoth" | "teamkingofthehill" => GameType::KingOfTheHill(KingOfTheHill::new(true)),
+			"elim" | "elimination" => GameType::Elimination(Elimination::new(false)),
+			"telim" | "teamelimination" => GameType::Elimination(Elimination::new(true)),
+			bad => {
+				return Err(anyhow!(
+					"unknown game type `{}`, options: `deathmatch`, `teammatch`, `capturetheflag`, `kingofthehill`, `teamkingofthehill`, `elimination`, `teamelimination`",
+					bad
+				))
+			}
 		})
 	}
 }
@@ -33,5 +102,106 @@ pub struct DeadMatch;
 #[derive(Default)]
 pub struct TeamMatch {
 	pub team_score: [i32; NUM_TEAMS],
-	// TODO: store player teams here
+}
+
+#[derive(Default)]
+pub struct CaptureTheFlag {
+	pub team_score: [i32; NUM_TEAMS],
+
+	// Where each team's flag is.
+	// `None` for teams without a flag base on the current map.
+	pub flags: [Option<FlagState>; NUM_TEAMS],
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FlagState {
+	// Sitting at the team's flag base (entity ID).
+	AtBase(EID),
+	// Carried by an enemy player.
+	Carried(ID),
+	// Lying where its carrier died (entity ID, seconds until it returns to base by itself).
+	Dropped(EID, f32),
+}
+
+/// Score by holding control zones: one point per zone held, every few seconds.
+pub struct KingOfTheHill {
+	// Zones are held by teams (true) or by single players (false).
+	pub teams: bool,
+	pub team_score: [i32; NUM_TEAMS],
+
+	// Current owner of each of the map's control zones.
+	pub zones: Vec<ZoneOwner>,
+
+	// Seconds since zone holders last scored.
+	pub score_timer: f32,
+}
+
+impl KingOfTheHill {
+	pub fn new(teams: bool) -> Self {
+		Self {
+			teams,
+			team_score: default(),
+			zones: default(),
+			score_timer: 0.0,
+		}
+	}
+}
+
+/// Round-based: dead players spectate until one team (or player) is left standing.
+pub struct Elimination {
+	// Last team standing (true) or last player standing (false).
+	pub teams: bool,
+	pub team_score: [i32; NUM_TEAMS], // from kills, like in a team match.
+
+	pub round: u32,
+
+	// Rounds won by each team or player so far this match (kept apart from kills).
+	pub team_wins: [u32; NUM_TEAMS],
+	pub player_wins: HashMap<ID, u32>,
+
+	// A round is in progress once at least two opponents are alive at the same time.
+	// Before that (e.g. while waiting for players to join), dead players may respawn.
+	pub in_progress: bool,
+
+	// Seconds until the next round starts, after a round has been won.
+	pub next_round_ttl: Option<f32>,
+}
+
+impl Elimination {
+	pub fn new(teams: bool) -> Self {
+		Self {
+			teams,
+			team_score: default(),
+			round: 1,
+			team_wins: default(),
+			player_wins: default(),
+			in_progress: false,
+			next_round_ttl: None,
+		}
+	}
+
+	/// Rounds won by a side so far this match.
+	pub fn wins(&self, side: Side) -> u32 {
+		match side {
+			Side::Team(team) => self.team_wins[team as usize],
+			Side::Player(player_id) => self.player_wins.get(&player_id).copied().unwrap_or(0),
+		}
+	}
+
+	/// Count a round won by `side`, return their number of wins.
+	pub fn add_win(&mut self, side: Side) -> u32 {
+		let wins = match side {
+			Side::Team(team) => &mut self.team_wins[team as usize],
+			Side::Player(player_id) => self.player_wins.entry(player_id).or_default(),
+		};
+		*wins += 1;
+		*wins
+	}
+}
+
+/// One side in an elimination round or a zone fight: a whole team or a single player.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Side {
+	Team(Team),
+	Player(ID),
 }
@@ -10,6 +10,9 @@ pub struct HUD {
 
 	log_msg: Vec<String>,
 	log_ttl: f32,
+
+	// Owner of each control zone (king-of-the-hill), e.g. "zone 1: Red team".
+	zones: Vec<(vec3, String)>,
 }
 
 // Time-to-live for "you killed..." message shown above crosshair.
@@ -34,6 +37,10 @@ impl HUD {
 		self.message_ttl = MSG_TTL;
 	}
 
+	pub fn set_zones(&mut self, zones: Vec<(vec3, String)>) {
+		self.zones = zones;
+	}
+
 	pub fn log(&mut self, message: String) {
 		self.log_msg.push(message);
 		if self.log_msg.len() > MAX_LOG_MSG {
@@ -66,7 +73,26 @@ impl HUD {
 			msg => engine.print_center(WHITE, msg),
 		}
 
-		if !player.spawned {
+		// bottom right: player status, from top to bottom.
+		let mut status = self.zones.clone();
+		if let Some(team) = player.flag {
+			status.push((team.color_filter(), format!("You have the {} flag", team)));
+		}
+		if player.spawned {
+			if let Some(powerup) = powerup_status(player) {
+				status.push(powerup);
+			}
+			status.push((WHITE, weapon_status(player)));
+			if player.stamina() < 1.0 {
+				status.push((stamina_color(player.stamina()), stamina_bar(player.stamina())));
+			}
+			status.push((health_color(player.health), health_bar(player.health)));
+		}
+		print_bottom_right_lines(engine, &status);
+
+		if player.spectating {
+			engine.print_top_center(WHITE, "Eliminated until the next round\nClick to follow another player")
+		} else if !player.spawned {
 			engine.print_top_center(WHITE, "Click to respawn")
 		}
 
@@ -74,6 +100,63 @@ impl HUD {
 	}
 }
 
+// Print lines in the bottom-right corner, each in its own color.
+fn print_bottom_right_lines(engine: &Engine, lines: &[(vec3, String)]) {
+	for (i, (color, line)) in lines.iter().enumerate() {
+		// trailing blank lines leave room for the lines below.
+		let padding = "\n ".repeat(lines.len() - 1 - i);
+		engine.print_bottom_right(*color, &format!("{}{}", line, padding));
+	}
+}
+
+// Current powerup and time left, e.g. "cowboy hat: 12s".
+// Turns yellow when about to wear off.
+fn powerup_status(player: &Player) -> Option<(vec3, String)> {
+	let name = player.powerup?.as_str().replace("_", " ");
+	Some(match player.powerup_ttl {
+		None => (WHITE, name),
+		Some(ttl) => (if ttl < 5.0 { YELLOW } else { WHITE }, format!("{}: {:.0}s", name, ttl.ceil())),
+	})
+}
+
+// Current weapon and ammo, e.g. "laser rifle: 4".
+fn weapon_status(player: &Player) -> String {
+	let name = player.weapon.as_str().replace("_", " ");
+	match player.ammo() {
+		None => name,
+		Some(ammo) => format!("{}: {}", name, ammo),
+	}
+}
+
+// Text health bar, e.g. "[||||||||    ] 75".
+fn health_bar(health: i32) -> String {
+	const WIDTH: i32 = 20;
+	let full = clamp(health * WIDTH / Player::MAX_HEALTH, 0, WIDTH) as usize;
+	format!("[{}{}] {:3}", "|".repeat(full), " ".repeat(WIDTH as usize - full), health)
+}
+
+// Text stamina bar, e.g. "sprint [||||||    ]".
+fn stamina_bar(stamina: f32) -> String {
+	const WIDTH: usize = 10;
+	let full = ((stamina * WIDTH as f32).ceil() as usize).min(WIDTH);
+	format!("sprint [{}{}]", "|".repeat(full), " ".repeat(WIDTH - full))
+}
+
+fn stamina_color(stamina: f32) -> vec3 {
+	match stamina {
+		s if s > 0.0 => WHITE,
+		_ => RED,
+	}
+}
+
+fn health_color(health: i32) -> vec3 {
+	match health {
+		h if h > Player::MAX_HEALTH / 2 => GREEN,
+		h if h > Player::MAX_HEALTH / 4 => YELLOW,
+		_ => RED,
+	}
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub enum HUDUpdate {
 	Message(String),
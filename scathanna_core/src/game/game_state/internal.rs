@@ -1,4 +1,2 @@
 pub use super::super::internal::*;
 pub use super::*;
-
-pub use super::commands::*;
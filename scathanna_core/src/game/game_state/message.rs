@@ -23,14 +23,24 @@ pub type ServerMsgs = Vec<Envelope<ServerMsg>>;
 pub struct JoinMsg {
 	pub name: String, // Player's nickname
 	pub avatar_id: u8,
-	pub team: Team,
+	pub team: Option<Team>, // Preferred team. The server decides so that teams stay balanced.
+	pub assets_hash: u64,   // Must match the server's (see `JoinMsg::assets_hash`).
+}
+
+impl JoinMsg {
+	/// Hash of the asset files that server and clients must agree on
+	/// because their contents are referred to by index over the network.
+	pub fn assets_hash() -> u64 {
+		PowerupTable::get().hash() ^ Palette::get().hash().rotate_left(1)
+	}
 }
 
 /// Subsequent messages sent by Client after the initial JoinMsg.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum ClientMsg {
-	/// This is my location, orientation, ... (sent continuously).
-	MovePlayer(Frame),
+	/// This is my location, orientation, ... (sent continuously),
+	/// and the input for each physics step taken since the previous MovePlayer (the server re-plays them).
+	MovePlayer(Frame, Vec<MoveInput>),
 
 	/// I'm ready to (re-)spawn
 	ReadyToSpawn,
@@ -41,8 +51,19 @@ pub enum ClientMsg {
 	/// Start a sound effect.
 	PlaySound(SoundEffect),
 
-	/// I have shot player with ID `victim`.
-	HitPlayer(ID),
+	/// I have shot player with ID `victim`, at the given location.
+	HitPlayer(ID, HitLocation),
+
+	/// I have shot the voxel at this position (it may be destructible).
+	HitVoxel(ivec3),
+
+	/// I have fired the given weapon (sent before the hits of this shot, if any).
+	/// Projectiles (rockets, grenades) are launched by the server from my gun,
+	/// in the direction I'm looking, and taken from there.
+	Fire(Weapon),
+
+	/// I have switched to another weapon.
+	SwitchWeapon(Weapon),
 
 	/// Send a CLI command to the server.
 	Command(String),
@@ -54,7 +75,15 @@ pub enum ServerMsg {
 	AddPlayer(Player),
 	DropPlayer(ID),
 	SwitchMap { map_name: String, players: Players, player_id: ID, entities: Entities },
-	ForceMovePlayer(vec3),
+	// Sent instead of the first SwitchMap if the JoinMsg was not accepted.
+	JoinRefused(String),
+	ForceMovePlayer(vec3, Orientation),
+	// Your movement did not add up: you should be here after this many physics steps (see Player::reconcile).
+	CorrectMove(Frame, u64),
+	// You got hit: add this to your velocity (only the owning client moves a player, see MoveInput::knockback).
+	Knockback(vec3),
+	// Rules of the current game, sent after SwitchMap.
+	SetRules(GameRules),
 	RequestRespawn(SpawnPoint),
 	// Update a player's position, orientation, velocity (source of truth = client).
 	MovePlayer(ID, Frame),
@@ -65,6 +94,12 @@ pub enum ServerMsg {
 	AddEffect(Effect),
 	PlaySound(SoundEffect),
 	UpdateHUD(HUDUpdate),
+	// You picked up a weapon (adds its ammo to the local player's inventory).
+	GiveWeapon(Weapon),
+	// Current owner of each of the map's control zones (king-of-the-hill).
+	UpdateZones(Vec<ZoneOwner>),
+	// Voxels were destroyed (or otherwise changed) since the map was loaded.
+	SetVoxels { range: Cuboid, value: VoxelType },
 }
 
 impl ServerMsg {
@@ -84,3 +119,24 @@ impl ServerMsg {
 		Envelope { to, msg: self }
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn load_assets() {
+		// the shipped files must parse, and keep the classic lava and water.
+		let palette = Palette::get();
+		assert_eq!(palette[VoxelType::LAVA].damage, Player::MAX_HEALTH);
+		assert!(palette[VoxelType::LAVA].emissive.is_some());
+		assert!(!palette[VoxelType::WATER].solid);
+		assert!(PowerupTable::get().by_name("party_hat").is_some());
+
+		// a different file of either kind must change the hash checked on join.
+		assert_ne!(palette.hash(), 0);
+		assert_ne!(PowerupTable::get().hash(), 0);
+		assert_ne!(JoinMsg::assets_hash(), PowerupTable::get().hash());
+		assert_ne!(JoinMsg::assets_hash(), palette.hash());
+	}
+}
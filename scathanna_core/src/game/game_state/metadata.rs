@@ -8,6 +8,15 @@ pub struct Metadata {
 	#[serde(default)]
 	pub pickup_points: Vec<PickupPoint>,
 
+	#[serde(default)]
+	pub flag_bases: Vec<FlagBase>,
+
+	#[serde(default)]
+	pub control_zones: Vec<ControlZone>,
+
+	#[serde(default)]
+	pub triggers: Vec<Trigger>,
+
 	#[serde(default = "default_sun_dir")]
 	pub sun_direction: vec3,
 }
@@ -2,37 +2,52 @@ mod internal;
 
 mod client_state;
 mod commands;
+mod control_zone;
 mod effect;
 mod entity;
+mod flag_base;
 mod gametype;
 mod hud;
 mod map_data;
 mod message;
 mod metadata;
+mod move_input;
 mod pickup_point;
 mod player;
 mod players;
+mod powerup;
+mod projectile;
 mod server_opts;
 mod server_state;
 mod sound_effect;
 mod spawn_point;
 mod team;
+mod trigger;
+mod weapon;
 mod world;
 
 pub use client_state::*;
+pub use commands::*;
+pub use control_zone::*;
 pub use effect::*;
 pub use entity::*;
+pub use flag_base::*;
 pub use gametype::*;
 pub use hud::*;
 pub use map_data::*;
 pub use message::*;
 pub use metadata::*;
+pub use move_input::*;
 pub use pickup_point::*;
 pub use player::*;
 pub use players::*;
+pub use powerup::*;
+pub use projectile::*;
 pub use server_opts::*;
 pub use server_state::*;
 pub use sound_effect::*;
 pub use spawn_point::*;
 pub use team::*;
+pub use trigger::*;
+pub use weapon::*;
 pub use world::*;
@@ -0,0 +1,96 @@
+use super::internal::*;
+
+/// Everything that steers a player's movement during one physics step (see `Player::step`).
+/// Sent to the server together with the resulting Frame, so that it can re-play the movement.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct MoveInput {
+	pub orientation: Orientation,
+	pub walk_dir: vec3,     // horizontal, length 0 or 1
+	pub jump: bool,         // jump key held down
+	pub jump_pressed: bool, // jump key pressed since the previous step (for mid-air jumps)
+	pub crouch: bool,
+	pub sprint: bool,
+	pub knockback: vec3, // impulse granted by the server (see ServerMsg::Knockback), added to the velocity
+}
+
+impl MoveInput {
+	/// Input from the currently pressed keys and mouse look.
+	pub fn from_keys(input_state: &InputState) -> Self {
+		let orientation = Orientation {
+			yaw: input_state.mouse_yaw(),
+			pitch: input_state.mouse_pitch(),
+		};
+		Self {
+			orientation,
+			walk_dir: walk_dir(orientation.yaw, input_state),
+			jump: input_state.is_down(Key::Jump),
+			jump_pressed: input_state.is_pressed(Key::Jump),
+			crouch: input_state.is_down(Key::Crouch),
+			sprint: input_state.is_down(Key::Sprint),
+			knockback: vec3::ZERO,
+		}
+	}
+
+	/// Input received over the network: make sure it cannot move faster than the keyboard can.
+	/// Input with NaN or infinite numbers (which would poison positions and sorts) does not move at all.
+	pub fn sanitized(self) -> Self {
+		if !self.is_finite() {
+			return Self::default();
+		}
+		let walk_dir = vec3(self.walk_dir.x(), 0.0, self.walk_dir.z());
+		Self {
+			walk_dir: if walk_dir.len() > 1.0 { walk_dir.safe_normalized() } else { walk_dir },
+			..self
+		}
+	}
+
+	fn is_finite(&self) -> bool {
+		self.orientation.yaw.is_finite() && self.orientation.pitch.is_finite() && self.walk_dir.is_finite() && self.knockback.is_finite()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn sanitized() {
+		let fast = MoveInput {
+			walk_dir: vec3(3.0, 1.0, 4.0),
+			..default()
+		};
+		assert_eq!(fast.sanitized().walk_dir, vec3(0.6, 0.0, 0.8));
+
+		let slow = MoveInput {
+			walk_dir: vec3(0.3, 0.0, 0.4),
+			sprint: true,
+			..default()
+		};
+		assert_eq!(slow.sanitized(), slow);
+
+		for bad in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+			let inputs = [
+				MoveInput {
+					walk_dir: vec3(bad, 0.0, 0.0),
+					..default()
+				},
+				MoveInput {
+					knockback: vec3(0.0, bad, 0.0),
+					..default()
+				},
+				MoveInput {
+					orientation: Orientation { yaw: bad, pitch: 0.0 },
+					..default()
+				},
+				MoveInput {
+					orientation: Orientation { yaw: 0.0, pitch: bad },
+					jump: true,
+					..default()
+				},
+			];
+			for input in inputs {
+				assert_eq!(input.sanitized(), MoveInput::default());
+			}
+		}
+	}
+}
@@ -1,15 +1,126 @@
 use super::internal::*;
+use rand::distributions::{Distribution, WeightedIndex};
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PickupPoint {
 	pub pos: ivec3,
 
 	#[serde(default)]
 	pub taken: bool,
+
+	/// Seconds after being picked up before a new item appears here.
+	#[serde(default = "default_respawn_delay")]
+	pub respawn_delay: f32,
+
+	/// Items that appear here, with their relative odds.
+	/// Empty means a gift box (which turns into a random powerup when picked up).
+	#[serde(default)]
+	pub items: Vec<PickupItem>,
+
+	/// Announce to all players when a new item is about to appear here.
+	#[serde(default)]
+	pub announce: bool,
+}
+
+/// An entry in a pickup point's item table.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PickupItem {
+	/// E.g. "party_hat", "laser_rifle", "gift_box".
+	pub item: String,
+
+	#[serde(default = "one")]
+	pub weight: f32,
+}
+
+const DEFAULT_RESPAWN_DELAY: f32 = 60.0;
+
+fn default_respawn_delay() -> f32 {
+	DEFAULT_RESPAWN_DELAY
+}
+
+fn one() -> f32 {
+	1.0
 }
 
 impl PickupPoint {
+	pub fn new(pos: ivec3, items: Vec<PickupItem>) -> Self {
+		Self {
+			pos,
+			taken: true,
+			respawn_delay: DEFAULT_RESPAWN_DELAY,
+			items,
+			announce: false,
+		}
+	}
+
 	pub fn position(&self) -> vec3 {
 		self.pos.to_f32()
 	}
+
+	/// Check that the item table only refers to existing items, with valid weights.
+	pub fn validate(&self) -> Result<()> {
+		if self.respawn_delay.is_nan() || self.respawn_delay < 0.0 {
+			return Err(anyhow!("pickup point {}: respawn_delay must be >= 0, got {}", self.pos, self.respawn_delay));
+		}
+		self.table().map(|_| ()).map_err(|err| anyhow!("pickup point {}: {}", self.pos, err))
+	}
+
+	/// Pick the next item to appear at pickup point number `pickup_point_id` (this point).
+	pub fn random_item(&self, rng: &mut impl Rng, pickup_point_id: usize) -> EKind {
+		let item = match self.table() {
+			Ok(Some((kinds, weights))) => kinds[weights.sample(rng)],
+			_ => EKind::GiftBox { pickup_point_id: None },
+		};
+		match item {
+			EKind::GiftBox { .. } => EKind::GiftBox {
+				pickup_point_id: Some(pickup_point_id),
+			},
+			other => other,
+		}
+	}
+
+	// Parsed item table, `None` if empty.
+	fn table(&self) -> Result<Option<(Vec<EKind>, WeightedIndex<f32>)>> {
+		if self.items.is_empty() {
+			return Ok(None);
+		}
+		let kinds = self.items.iter().map(|i| i.item.parse()).collect::<Result<Vec<EKind>>>()?;
+		let weights = WeightedIndex::new(self.items.iter().map(|i| i.weight)).map_err(|err| anyhow!("item weights: {}", err))?;
+		Ok(Some((kinds, weights)))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn parse_defaults() {
+		let pp: PickupPoint = serde_json::from_str(r#"{"pos": [1, 2, 3]}"#).unwrap();
+		assert_eq!(pp.respawn_delay, DEFAULT_RESPAWN_DELAY);
+		assert!(pp.items.is_empty());
+		assert!(!pp.announce);
+
+		let mut rng = StdRng::seed_from_u64(1);
+		assert_eq!(pp.random_item(&mut rng, 7), EKind::GiftBox { pickup_point_id: Some(7) });
+	}
+
+	#[test]
+	fn item_table() {
+		let pp: PickupPoint = serde_json::from_str(r#"{"pos": [0, 0, 0], "items": [{"item": "laser_rifle"}, {"item": "gift_box", "weight": 0}]}"#).unwrap();
+		pp.validate().unwrap();
+		let mut rng = StdRng::seed_from_u64(1);
+		for _ in 0..20 {
+			assert_eq!(pp.random_item(&mut rng, 0), EKind::Gun(Weapon::LaserRifle));
+		}
+	}
+
+	#[test]
+	fn reject_bad_items() {
+		let unknown: PickupPoint = serde_json::from_str(r#"{"pos": [0, 0, 0], "items": [{"item": "no_such_thing"}]}"#).unwrap();
+		assert!(unknown.validate().is_err());
+
+		let zero: PickupPoint = serde_json::from_str(r#"{"pos": [0, 0, 0], "items": [{"item": "gift_box", "weight": 0}]}"#).unwrap();
+		assert!(zero.validate().is_err());
+	}
 }
@@ -1,4 +1,5 @@
 use super::internal::*;
+use std::collections::VecDeque;
 use ClientMsg::*;
 
 /// Player data.
@@ -11,14 +12,21 @@ pub struct Player {
 	pub name: String,  // nickname
 	pub avatar_id: u8, // determines which avatar model is drawn (gl_client.rs).
 	pub team: Team,
-	pub health: i32,
-	pub spawned: bool, // playing or waiting for respawn?
+	pub health: i32,      // 0..=MAX_HEALTH, a player dies when it drops to 0.
+	pub regen_ttl: f32,   // seconds until health regenerates (reset when hit).
+	pub spawned: bool,    // playing or waiting for respawn?
+	pub spectating: bool, // eliminated, waiting for the next round (elimination game type).
 	pub next_spawn_point: vec3,
+	pub next_spawn_orientation: Orientation,
 	pub powerup: Option<EKind>,
+	pub extra_lives: u32,                 // hits survived before losing the powerup (see PowerupEffects).
+	pub powerup_ttl: Option<f32>,         // seconds before the powerup wears off (see PowerupDef::duration).
+	pub flag: Option<Team>,               // carrying this team's flag (capture-the-flag)
 	pub invulnerability_ttl: Option<f32>, // seconds of invulnerability left
 
 	// controlled locally, synced to server:
 	pub skeleton: Skeleton, // fully determines player position
+	pub weapon: Weapon,     // currently held weapon
 
 	// controlled locally, not synced:
 	pub local: LocalState,
@@ -27,9 +35,21 @@ pub struct Player {
 /// Controlled by the local client, never overwritten by the server.
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct LocalState {
-	pub gun_cooldown: f32, // Time until next shot allowed.
-	pub feet_phase: f32,   // used for avatar animation (gl_client.rs).
+	pub gun_cooldown: f32,        // Time until next shot allowed.
+	pub ammo: [u32; NUM_WEAPONS], // Shots left per weapon (ignored for weapons with unlimited ammo).
+	pub jumps: u32,               // Jumps since last standing on the ground.
+	pub feet_phase: f32,          // used for avatar animation (gl_client.rs).
 	pub feet_pitch: f32,
+	pub reorient: Option<Orientation>, // Turn the mouse look this way (after respawn or teleport, see ClientState::tick).
+	pub trigger: Option<usize>,        // Trigger (jump pad, teleporter) we're standing in. They only fire on entering.
+	pub stamina_used: f32,             // Seconds of sprinting since last fully rested (see MAX_STAMINA).
+	pub in_water: bool,                // Swimming (to splash when entering or leaving the water).
+	pub timestep: FixedTimestep,       // Frame time not yet simulated (see Player::step).
+	pub steps: u64,                    // Physics steps taken. Counted by the server as well (see ServerMsg::CorrectMove).
+	pub jump_queued: bool,             // Jump pressed, not yet seen by a physics step.
+	pub knockback: VecDeque<vec3>,     // Impulses received from the server, not yet seen by a physics step (one per step, see ServerMsg::Knockback).
+	#[serde(skip)]
+	pub history: VecDeque<MoveInput>, // Latest steps, re-played after a correction by the server.
 }
 
 /// Key for identifying players
@@ -37,14 +57,32 @@ pub type ID = u32;
 
 const FEET_ANIM_SPEED: f32 = 12.0;
 const FEET_ANIM_DAMP: f32 = 6.0;
-const DEFAULT_GUN_COOLDOWN: f32 = 0.7; // seconds
+
+// Seconds of sprinting before running out of breath,
+// and seconds of not sprinting recovered per second.
+const MAX_STAMINA: f32 = 4.0;
+const STAMINA_RECOVERY: f32 = 0.5;
+
+// Physics steps kept for re-playing after a correction by the server (i.e. a round trip of up to 2 seconds).
+const MAX_HISTORY: usize = (2.0 / TIMESTEP) as usize;
 
 impl Player {
 	pub const HSIZE: f32 = 3.8;
 	pub const VSIZE: f32 = 5.8;
 	pub const CAM_HEIGHT: f32 = 5.4;
+	pub const CROUCH_VSIZE: f32 = 4.2;
 	pub const WALK_SPEED: f32 = 24.0;
-	const JUMP_SPEED: f32 = 24.0;
+	const CROUCH_SPEED: f32 = 0.5; // walk speed multiplier
+	const SPRINT_SPEED: f32 = 1.6; // walk speed multiplier
+	pub const JUMP_SPEED: f32 = 24.0;
+	const SWIM_UP_SPEED: f32 = 12.0;
+	pub const MAX_HEALTH: i32 = 100;
+
+	// Avatar geometry, shared with player_model.rs and used to tell head shots from body shots:
+	// feet reach up to HEAD_HEIGHT (when standing), above that is the (large) head.
+	// Only its top part, above the gun (at GUN_HEIGHT times the player's height), counts as a head shot.
+	pub const HEAD_HEIGHT: f32 = 1.5;
+	const GUN_HEIGHT: f32 = 0.66;
 
 	pub fn new(id: ID, position: vec3, orientation: Orientation, name: String, avatar_id: u8, team: Team) -> Self {
 		Self {
@@ -52,13 +90,20 @@ impl Player {
 			name,
 			avatar_id,
 			spawned: false,
-			health: 100,
+			spectating: false,
+			health: Self::MAX_HEALTH,
+			regen_ttl: 0.0,
 			next_spawn_point: position,
+			next_spawn_orientation: orientation,
 			powerup: None,
+			extra_lives: 0,
+			powerup_ttl: None,
+			flag: None,
 			team,
 			invulnerability_ttl: None,
 
 			skeleton: Skeleton::new(position, orientation, Self::HSIZE, Self::VSIZE),
+			weapon: default(),
 			local: default(),
 		}
 	}
@@ -68,17 +113,69 @@ impl Player {
 	/// Record a diff for controlling this player with keyboard/mouse input.
 	/// Called on a clone of the World's player (!so need to be careful for self-interaction!).
 	pub fn control(&mut self, upd: &mut ClientMsgs, input_state: &InputState, world: &World, dt: f32) {
+		let mut inputs = vec![];
 		if self.spawned {
-			self.tick_movement(upd, input_state, world, dt);
+			inputs = self.tick_movement(upd, input_state, world, dt);
+			self.control_weapon_switch(upd, input_state);
 			self.control_shooting(upd, input_state, world, dt);
 		} else {
 			self.set_orientation(input_state);
-			if input_state.is_pressed(Key::Mouse1) {
+			// spectators click to follow another player instead (see ClientState).
+			if input_state.is_pressed(Key::Mouse1) && !self.spectating {
 				self.skeleton.position = self.next_spawn_point;
+				self.skeleton.orientation = self.next_spawn_orientation;
+				self.local.reorient = Some(self.next_spawn_orientation);
 				upd.push(ReadyToSpawn);
 			}
 		}
-		upd.push(MovePlayer(self.skeleton.frame()));
+		upd.push(MovePlayer(self.skeleton.frame(), inputs));
+	}
+
+	// __________________ weapons
+
+	fn control_weapon_switch(&mut self, upd: &mut ClientMsgs, input_state: &InputState) {
+		for (key, weapon) in Key::NUMERIC_KEYS.iter().copied().zip(Weapon::ALL) {
+			if input_state.is_pressed(key) {
+				self.switch_weapon(upd, weapon);
+			}
+		}
+
+		if input_state.is_pressed(Key::ScrollNext) {
+			self.switch_weapon(upd, self.next_owned_weapon(1));
+		}
+		if input_state.is_pressed(Key::ScrollPrev) {
+			self.switch_weapon(upd, self.next_owned_weapon(NUM_WEAPONS - 1));
+		}
+	}
+
+	/// Take `weapon` in hand, if we have it.
+	pub fn switch_weapon(&mut self, upd: &mut ClientMsgs, weapon: Weapon) {
+		if weapon != self.weapon && self.owns(weapon) {
+			self.weapon = weapon;
+			self.local.gun_cooldown = 0.0;
+			upd.push(SwitchWeapon(weapon));
+		}
+	}
+
+	// The next weapon we own, `step` places further in `Weapon::ALL` (wrapping around).
+	fn next_owned_weapon(&self, step: usize) -> Weapon {
+		let mut i = self.weapon.index();
+		loop {
+			i = (i + step) % NUM_WEAPONS;
+			if self.owns(Weapon::ALL[i]) {
+				return Weapon::ALL[i];
+			}
+		}
+	}
+
+	/// Do we have this weapon, and ammo to fire it?
+	pub fn owns(&self, weapon: Weapon) -> bool {
+		weapon.ammo().is_none() || self.local.ammo[weapon.index()] > 0
+	}
+
+	/// Shots left in the current weapon. `None`: unlimited.
+	pub fn ammo(&self) -> Option<u32> {
+		self.weapon.ammo().map(|_| self.local.ammo[self.weapon.index()])
 	}
 
 	// __________________ shooting
@@ -98,82 +195,261 @@ impl Player {
 	}
 
 	fn can_shoot_berserk(&self, world: &World) -> bool {
-		use EKind::*;
-		match self.powerup {
-			Some(BerserkerHelmet) => true,
-			Some(PartyHat) => self.is_on_lava(world),
-			_ => false,
+		self.powerup_effects(world).auto_fire
+	}
+
+	/// What our powerup (if any) currently does for us.
+	pub fn powerup_effects(&self, world: &World) -> &'static PowerupEffects {
+		let effects = self.powerup.map(EKind::effects).unwrap_or(&NO_EFFECTS);
+		match &effects.on_lava {
+			Some(on_lava) if self.is_on_lava(world) => on_lava,
+			_ => effects,
 		}
 	}
 
+	/// Standing on lava, or any other voxel that does damage (see `Palette`).
 	pub fn is_on_lava(&self, world: &World) -> bool {
-		let probe = self.position() - 0.2 * vec3::EY;
-		world.map.voxels.at(probe.floor()) == VoxelType::LAVA
-	}
-
-	fn gun_cooldown(&self, world: &World) -> f32 {
-		use EKind::*;
-		const FAST: f32 = 0.05;
-		match self.powerup {
-			Some(CowboyHat) => FAST,
-			Some(BerserkerHelmet) => FAST,
-			Some(PartyHat) => {
-				if self.is_on_lava(world) {
-					FAST
-				} else {
-					DEFAULT_GUN_COOLDOWN
-				}
-			}
-			_ => DEFAULT_GUN_COOLDOWN,
-		}
+		self.skeleton.ground_voxel(world).def().damage > 0
+	}
+
+	/// Seconds between shots with the current weapon.
+	pub fn gun_cooldown(&self, world: &World) -> f32 {
+		self.weapon.cooldown() / self.powerup_effects(world).fire_rate
 	}
 
 	fn shoot(&mut self, upd: &mut ClientMsgs, world: &World, _dt: f32) {
 		// shooting, so gun will need to cool down before next shot is allowed.
 		self.local.gun_cooldown = self.gun_cooldown(world);
 
+		let weapon = self.weapon;
+		upd.push(ClientMsg::PlaySound(SoundEffect::spatial(pick_random(weapon.shoot_sounds()), self.position(), weapon.shoot_volume())));
+
 		let line_of_fire = self.line_of_fire(world);
-		let start = line_of_fire.start.to_f32();
-		let end = self.shoot_at(world);
+		upd.push(Fire(weapon));
+
+		let mut rng = rand::thread_rng();
+		for i in 0..weapon.pellets() {
+			let (ray, orientation) = match weapon.spread() {
+				spread if spread > 0.0 => {
+					let orientation = Orientation {
+						yaw: self.orientation().yaw + rng.gen_range(-spread..spread),
+						pitch: self.orientation().pitch + rng.gen_range(-spread..spread),
+					};
+					(Ray64::new(line_of_fire.start, orientation.look_dir().into()), orientation)
+				}
+				_ => (line_of_fire.clone(), self.orientation()),
+			};
+			let end = self.shoot_ray(upd, world, &ray, orientation);
+
+			// one ricochet sound per shot is plenty.
+			if i == 0 {
+				upd.push(ClientMsg::PlaySound(SoundEffect::spatial(pick_random(&["ricochet1", "ricochet2", "ricochet3", "ricochet4"]), end, 1.0)));
+			}
+		}
+
+		// out of ammo: fall back to the default weapon.
+		if let Some(ammo) = self.ammo() {
+			self.local.ammo[weapon.index()] = ammo.saturating_sub(1);
+			if !self.owns(weapon) {
+				self.switch_weapon(upd, default());
+			}
+		}
+	}
+
+	// Fire a single bullet along `ray`, return where it ends.
+	fn shoot_ray(&self, upd: &mut ClientMsgs, world: &World, ray: &Ray64, orientation: Orientation) -> vec3 {
+		let range = self.weapon.range() as f64;
+		let hit = world.intersect_except(self.id, ray).filter(|(t, _)| *t < range);
+		let end = ray.at(hit.map(|(t, _)| t + 0.01).unwrap_or(range)).to_f32();
+
+		let start = ray.start.to_f32();
 		let len = (end - start).len();
-		upd.push(ClientMsg::AddEffect(Effect::particle_beam(start, self.orientation(), len, self.team.color_filter())));
-		upd.push(ClientMsg::PlaySound(SoundEffect::spatial(pick_random(&["bang1", "bang2", "bang3", "bang4"]), self.position(), 30.0)));
-		upd.push(ClientMsg::PlaySound(SoundEffect::spatial(pick_random(&["ricochet1", "ricochet2", "ricochet3", "ricochet4"]), end, 1.0)));
+		upd.push(ClientMsg::AddEffect(self.weapon.beam_effect(start, orientation, len, self.team.color_filter())));
 
-		if let Some((_, Some(victim_id))) = world.intersect_except(self.id, &line_of_fire) {
-			upd.push(HitPlayer(victim_id));
+		match hit {
+			Some((t, Some(victim_id))) => {
+				let hit_pos = ray.at(t).to_f32();
+				upd.push(HitPlayer(victim_id, world.players[victim_id].hit_location(hit_pos)));
+			}
+			// the server decides if the voxel is destructible.
+			Some((_, None)) => upd.push(HitVoxel(end.map(|v| v.floor() as i32))),
+			None => (),
 		}
 
-		// effect when shooting lava
-		if world.map.voxels.at_pos(end) == VoxelType::LAVA {
-			upd.push(AddEffect(Effect::particle_explosion(end, RED)));
+		// effect when shooting lava (or any other damaging voxel)
+		let def = world.map.voxels.at_pos(end).def();
+		if def.damage > 0 {
+			upd.push(AddEffect(Effect::particle_explosion(end, def.emissive.unwrap_or(def.color))));
 			upd.push(PlaySound(SoundEffect::spatial("lava", end, 1.0)))
 		}
+
+		end
 	}
 
 	// __________________ movement
 
-	fn tick_movement(&mut self, upd: &mut ClientMsgs, input_state: &InputState, world: &World, dt: f32) {
+	// Mouse look follows the frame rate, but movement is simulated in fixed physics steps.
+	// Returns the input for each step taken.
+	fn tick_movement(&mut self, upd: &mut ClientMsgs, input_state: &InputState, world: &World, dt: f32) -> Vec<MoveInput> {
 		self.set_orientation(input_state);
-		self.tick_walk(upd, input_state, world, dt);
-		self.tick_jump(upd, input_state, world, dt);
-		self.skeleton.tick(upd, world, dt);
+		let input = MoveInput::from_keys(input_state);
+		self.local.jump_queued |= input.jump_pressed;
+
+		let steps = self.local.timestep.advance(dt);
+		let mut inputs = Vec::with_capacity(steps as usize);
+		for _ in 0..steps {
+			let input = MoveInput {
+				jump_pressed: mem::take(&mut self.local.jump_queued),
+				knockback: self.local.knockback.pop_front().unwrap_or(vec3::ZERO),
+				..input
+			};
+			self.step(upd, &input, world);
+			inputs.push(input);
+			self.local.history.push_back(input);
+			if self.local.history.len() > MAX_HISTORY {
+				self.local.history.pop_front();
+			}
+		}
+		inputs
+	}
+
+	/// Advance movement by one physics step (TIMESTEP seconds).
+	/// Deterministic, so that the server can re-play the client's steps to validate its movement.
+	pub fn step(&mut self, upd: &mut ClientMsgs, input: &MoveInput, world: &World) {
+		self.skeleton.orientation = input.orientation;
+		self.tick_crouch(input, world);
+		self.tick_walk(input, world);
+		self.tick_jump(upd, input, world);
+		self.skeleton.velocity += input.knockback;
+		self.skeleton.tick(upd, world, &self.obstacles(world), TIMESTEP);
+		self.tick_water(upd, world);
+		self.tick_triggers(upd, world);
+		self.local.steps += 1;
+	}
+
+	/// The server disagreed with our movement: it had us at `frame` after `steps` physics steps.
+	/// Re-play the steps it has not seen yet on top of that.
+	pub fn reconcile(&mut self, frame: Frame, steps: u64, world: &World) {
+		let unseen = self.local.steps.saturating_sub(steps) as usize;
+		let replay = self.local.history.iter().skip(self.local.history.len().saturating_sub(unseen)).copied().collect::<Vec<_>>();
+		self.skeleton.set_frame(frame);
+		self.local.steps = steps;
+		let mut upd = vec![]; // sounds and effects were already played the first time around.
+		for input in &replay {
+			self.step(&mut upd, input, world);
+		}
+	}
+
+	// Other players' hitboxes, if they block our way (see GameRules).
+	fn obstacles(&self, world: &World) -> Vec<BoundingBox<f32>> {
+		match world.rules.player_collision {
+			false => vec![],
+			true => world
+				.players
+				.iter()
+				.filter(|(id, p)| *id != self.id && p.spawned && !p.spectating)
+				.map(|(_, p)| p.skeleton.bounds())
+				.collect(),
+		}
 	}
 
-	fn tick_walk(&mut self, _upd: &mut ClientMsgs, input_state: &InputState, world: &World, dt: f32) {
-		let walk_speed = Self::WALK_SPEED * walk_dir(self.orientation().yaw, input_state);
-		self.skeleton.try_walk(dt, world, walk_speed);
+	// Splash when jumping into or climbing out of the water.
+	fn tick_water(&mut self, upd: &mut ClientMsgs, world: &World) {
+		let in_water = self.skeleton.is_in_water(world);
+		if in_water != self.local.in_water {
+			self.local.in_water = in_water;
+			upd.push(ClientMsg::AddEffect(Effect::particle_explosion(self.position(), LIGHT_BLUE)));
+			upd.push(ClientMsg::PlaySound(SoundEffect::spatial("splash", self.position(), 0.5)));
+		}
 	}
 
-	fn tick_jump(&mut self, upd: &mut ClientMsgs, input_state: &InputState, world: &World, _dt: f32) {
-		if input_state.is_down(Key::Jump) {
+	// Jump pads and teleporters (see trigger.rs).
+	// Applied locally for responsiveness, the server checks teleports.
+	fn tick_triggers(&mut self, upd: &mut ClientMsgs, world: &World) {
+		let triggers = &world.map.metadata.triggers;
+		let entered = triggers.iter().position(|t| t.contains(self.position()));
+		if entered == self.local.trigger {
+			return;
+		}
+		self.local.trigger = entered;
+
+		match entered.map(|i| triggers[i].action) {
+			None => (),
+			Some(TriggerAction::JumpPad { velocity }) => {
+				self.skeleton.velocity = velocity;
+				self.local.jumps = 1;
+				upd.push(ClientMsg::PlaySound(SoundEffect::spatial("fly", self.position(), 1.0)))
+			}
+			Some(TriggerAction::Teleport { destination, orientation }) => {
+				upd.push(ClientMsg::AddEffect(Effect::respawn(self.position())));
+				self.skeleton.position = destination;
+				self.skeleton.velocity = vec3::ZERO;
+				self.skeleton.orientation = orientation;
+				self.local.reorient = Some(orientation);
+				upd.push(ClientMsg::PlaySound(SoundEffect::spatial("respawn", destination, 1.0)))
+			}
+		}
+	}
+
+	// Crouch while the crouch key is held down, but don't stand up under a ceiling.
+	fn tick_crouch(&mut self, input: &MoveInput, world: &World) {
+		let vsize = match input.crouch {
+			true => Self::CROUCH_VSIZE,
+			false => Self::VSIZE,
+		};
+		let resized = Skeleton { vsize, ..self.skeleton.clone() };
+		if vsize < self.skeleton.vsize || resized.pos_ok(world, self.position()) {
+			self.skeleton = resized;
+		}
+	}
+
+	fn tick_walk(&mut self, input: &MoveInput, world: &World) {
+		let dir = input.walk_dir;
+		let speed = Self::WALK_SPEED * self.powerup_effects(world).speed * self.tick_sprint(input, dir != vec3::ZERO, TIMESTEP);
+		self.skeleton.try_walk(TIMESTEP, world, speed * dir);
+	}
+
+	// Walk speed multiplier for sprinting or crouching.
+	// Sprinting uses up stamina, which slowly recovers when not sprinting.
+	fn tick_sprint(&mut self, input: &MoveInput, walking: bool, dt: f32) -> f32 {
+		if self.is_crouching() {
+			self.local.stamina_used = f32::max(0.0, self.local.stamina_used - STAMINA_RECOVERY * dt);
+			return Self::CROUCH_SPEED;
+		}
+		if walking && input.sprint && self.local.stamina_used < MAX_STAMINA {
+			self.local.stamina_used += dt;
+			Self::SPRINT_SPEED
+		} else {
+			self.local.stamina_used = f32::max(0.0, self.local.stamina_used - STAMINA_RECOVERY * dt);
+			1.0
+		}
+	}
+
+	fn tick_jump(&mut self, upd: &mut ClientMsgs, input: &MoveInput, world: &World) {
+		// swim up while holding jump.
+		if self.skeleton.is_in_water(world) {
+			self.local.jumps = 0;
+			if input.jump {
+				self.skeleton.velocity[Y] = Self::SWIM_UP_SPEED;
+			}
+			return;
+		}
+
+		// falling off a ledge counts as a jump.
+		let on_ground = self.skeleton.on_ground(world);
+		self.local.jumps = if on_ground { 0 } else { self.local.jumps.max(1) };
+
+		if input.jump {
 			if self.skeleton.try_jump(world, Self::JUMP_SPEED) {
+				self.local.jumps = 1;
 				upd.push(ClientMsg::PlaySound(SoundEffect::spatial("jump", self.position(), 0.3)))
 			}
 		}
 
-		if self.powerup == Some(EKind::XMasHat) && input_state.is_pressed(Key::Jump) {
+		// some powerups allow for jumping in mid-air.
+		if !on_ground && input.jump_pressed && self.local.jumps < self.powerup_effects(world).jump_count {
 			self.skeleton.unconditional_jump(Self::JUMP_SPEED);
+			self.local.jumps += 1;
 			upd.push(ClientMsg::PlaySound(SoundEffect::spatial("fly", self.position(), 0.3)))
 		}
 	}
@@ -232,9 +508,23 @@ impl Player {
 		self.skeleton.orientation
 	}
 
+	pub fn is_crouching(&self) -> bool {
+		self.skeleton.vsize < Self::VSIZE
+	}
+
+	/// How much lower than normal the head is (0 when standing).
+	pub fn crouch_depth(&self) -> f32 {
+		Self::VSIZE - self.skeleton.vsize
+	}
+
+	/// Fraction of stamina left for sprinting (1: fully rested).
+	pub fn stamina(&self) -> f32 {
+		1.0 - self.local.stamina_used / MAX_STAMINA
+	}
+
 	pub fn camera(&self) -> Camera {
 		Camera {
-			position: self.position() + vec3(0.0, Self::CAM_HEIGHT, 0.0),
+			position: self.position() + vec3(0.0, Self::CAM_HEIGHT - self.crouch_depth(), 0.0),
 			orientation: self.orientation(),
 		}
 	}
@@ -295,8 +585,21 @@ impl Player {
 		line_of_fire.at(world.intersect_except(self.id, &line_of_fire).map(|(t, _)| t + 0.01).unwrap_or(10000.0)).into()
 	}
 
+	/// Which part of this player's avatar was hit by a bullet hitting at absolute position `hit_pos`.
+	pub fn hit_location(&self, hit_pos: vec3) -> HitLocation {
+		// relative to the player's height, so that all zones shrink alike when crouching.
+		let height = (hit_pos.y() - self.position().y()) / self.skeleton.vsize;
+		if height < Self::HEAD_HEIGHT / Self::VSIZE {
+			HitLocation::Feet
+		} else if height < Self::GUN_HEIGHT {
+			HitLocation::Body
+		} else {
+			HitLocation::Head
+		}
+	}
+
 	pub fn gun_pos_internal(&self) -> vec3 {
-		vec3(0.5 * self.skeleton.hsize + 0.4, 0.66 * self.skeleton.vsize, 0.0)
+		vec3(0.5 * self.skeleton.hsize + 0.4, Self::GUN_HEIGHT * self.skeleton.vsize, 0.0)
 	}
 
 	/// Intersect ray with player hitbox.
@@ -308,3 +611,229 @@ impl Player {
 		}
 	}
 }
+
+/// Where a player got hit. Determines the damage dealt.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HitLocation {
+	Head,
+	Body,
+	Feet,
+}
+
+impl HitLocation {
+	/// Health lost by a hit at this location.
+	pub fn damage(self) -> i32 {
+		use HitLocation::*;
+		match self {
+			Head => Player::MAX_HEALTH,
+			Body => 50,
+			Feet => 34,
+		}
+	}
+
+	pub fn as_str(self) -> &'static str {
+		use HitLocation::*;
+		match self {
+			Head => "head",
+			Body => "body",
+			Feet => "feet",
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	// A flat floor at y=4 with a low ceiling above x < 64.
+	fn test_world() -> World {
+		let mut world = World::from_map(concat!(env!("CARGO_MANIFEST_DIR"), "/../assets/maps/bacon"), default(), default()).unwrap();
+		world.map.voxels = Voxels::new();
+		world.map.voxels.set_range(&Cuboid::new(ivec3(0, 0, 0), ivec3(128, 4, 128)), VoxelType(5));
+		world.map.voxels.set_range(&Cuboid::new(ivec3(0, 9, 0), ivec3(64, 12, 128)), VoxelType(5));
+		world
+	}
+
+	#[test]
+	fn hit_location() {
+		let mut player = Player::new(1, vec3(20.0, 4.0, 20.5), default(), "alice".into(), 0, Team::Red);
+		let at = |player: &Player, height: f32| player.hit_location(player.position() + height * vec3::EY);
+
+		assert_eq!(at(&player, 1.0), HitLocation::Feet);
+		assert_eq!(at(&player, 3.0), HitLocation::Body);
+		assert_eq!(at(&player, 5.0), HitLocation::Head);
+
+		// crouching: zones shrink along with the player.
+		player.skeleton.vsize = Player::CROUCH_VSIZE;
+		assert_eq!(at(&player, 1.0), HitLocation::Feet);
+		assert_eq!(at(&player, 2.5), HitLocation::Body);
+		assert_eq!(at(&player, 3.0), HitLocation::Head);
+	}
+
+	#[test]
+	fn crouch_under_ceiling() {
+		let world = test_world();
+		let mut player = Player::new(1, vec3(20.0, 4.0, 20.5), default(), "alice".into(), 0, Team::Red);
+		let mut input = InputState::new();
+
+		input.record_key(Key::Crouch, true);
+		player.tick_crouch(&MoveInput::from_keys(&input), &world);
+		assert!(player.is_crouching());
+		assert!(player.camera().position.y() < 4.0 + Player::CAM_HEIGHT);
+
+		// no room to stand up.
+		input.record_key(Key::Crouch, false);
+		input.clear();
+		player.tick_crouch(&MoveInput::from_keys(&input), &world);
+		assert!(player.is_crouching());
+
+		// out in the open.
+		player.set_position(vec3(100.0, 4.0, 20.5));
+		player.tick_crouch(&MoveInput::from_keys(&input), &world);
+		assert!(!player.is_crouching());
+	}
+
+	#[test]
+	fn step_up() {
+		let mut world = test_world();
+		// a step 2 high, next to a wall 3 high.
+		world.map.voxels.set_range(&Cuboid::new(ivec3(106, 4, 0), ivec3(128, 6, 40)), VoxelType(5));
+		world.map.voxels.set_range(&Cuboid::new(ivec3(106, 4, 40), ivec3(128, 7, 80)), VoxelType(5));
+		let mut upd = vec![];
+
+		let mut walk = |position: vec3| {
+			let mut skeleton = Skeleton::new(position, default(), Player::HSIZE, Player::VSIZE);
+			for _ in 0..10 {
+				skeleton.velocity = vec3(Player::WALK_SPEED, 0.0, 0.0);
+				skeleton.tick(&mut upd, &world, &[], 0.05);
+			}
+			skeleton.position
+		};
+
+		let on_step = walk(vec3(100.0, 4.0, 20.5));
+		assert!(on_step.x() > 106.0);
+		assert!((6.0..6.01).contains(&on_step.y()), "{}", on_step);
+
+		let at_wall = walk(vec3(100.0, 4.0, 60.5));
+		assert!(at_wall.x() <= 106.0 - Player::HSIZE / 2.0);
+		assert!(at_wall.y() < 4.01);
+	}
+
+	#[test]
+	fn swimming() {
+		let mut world = test_world();
+		world.map.voxels.set_range(&Cuboid::new(ivec3(64, 4, 0), ivec3(128, 40, 128)), VoxelType::WATER);
+		let mut player = Player::new(1, vec3(100.0, 30.0, 20.5), default(), "alice".into(), 0, Team::Red);
+		player.spawned = true;
+		let mut input = InputState::new();
+		let mut upd = vec![];
+
+		// sink slowly.
+		for _ in 0..10 {
+			player.control(&mut upd, &input, &world, 0.1);
+		}
+		assert!(player.skeleton.velocity.y() < 0.0);
+		assert!(player.skeleton.velocity.y() >= -6.0);
+
+		// swim up.
+		input.record_key(Key::Jump, true);
+		let y = player.position().y();
+		player.control(&mut upd, &input, &world, 0.1);
+		assert!(player.position().y() > y);
+	}
+
+	#[test]
+	fn frame_rate_independent() {
+		// walk and jump for a while at different frame rates.
+		let world = test_world();
+		let mut input = InputState::new();
+		input.record_key(Key::Right, true);
+		input.record_key(Key::Jump, true);
+		let run = |fps: f32, steps: u64| {
+			let mut player = Player::new(1, vec3(70.0, 4.0, 20.5), default(), "alice".into(), 0, Team::Red);
+			player.spawned = true;
+			let mut upd = vec![];
+			while player.local.steps < steps {
+				player.control(&mut upd, &input, &world, 1.0 / fps);
+			}
+			player
+		};
+
+		// ends up in exactly the same place after the same number of physics steps.
+		let slow = run(30.0, 50);
+		let fast = run(144.0, slow.local.steps);
+		assert_eq!(slow.local.steps, fast.local.steps);
+		assert_eq!(slow.skeleton, fast.skeleton);
+		assert!(slow.position().y() > 4.0);
+	}
+
+	#[test]
+	fn reconcile() {
+		let world = test_world();
+		let mut player = Player::new(1, vec3(70.0, 4.0, 20.5), default(), "alice".into(), 0, Team::Red);
+		player.spawned = true;
+		let mut input = InputState::new();
+		input.record_key(Key::Right, true);
+		let mut upd = vec![];
+		for _ in 0..10 {
+			player.control(&mut upd, &input, &world, 0.05);
+		}
+
+		// the server had us somewhere else 20 steps ago: re-play the last 20 steps from there.
+		let steps = player.local.steps - 20;
+		let corrected = Frame {
+			position: vec3(75.0, 4.0, 20.5),
+			..player.skeleton.frame()
+		};
+		player.reconcile(corrected, steps, &world);
+		assert_eq!(player.local.steps, steps + 20);
+		let expected = vec3(75.0 + 20.0 * TIMESTEP * Player::WALK_SPEED, 4.0, 20.5);
+		assert!((player.position() - expected).len() < 0.01, "{} != {}", player.position(), expected);
+	}
+
+	#[test]
+	fn player_collision() {
+		let mut world = test_world();
+		let mut bob = Player::new(2, vec3(80.0, 4.0, 20.5), default(), "bob".into(), 0, Team::Blue);
+		bob.spawned = true;
+		world.players.insert(2, bob);
+
+		// alice walks towards bob for 1 second.
+		let walk = |world: &World| {
+			let mut alice = Player::new(1, vec3(70.0, 4.0, 20.5), default(), "alice".into(), 0, Team::Red);
+			alice.spawned = true;
+			let mut upd = vec![];
+			for _ in 0..(1.0 / TIMESTEP) as usize {
+				alice.step(&mut upd, &MoveInput { walk_dir: vec3::EX, ..default() }, world);
+			}
+			alice.position()
+		};
+
+		// by default, players walk right through each other.
+		assert!(walk(&world).x() > 90.0);
+
+		world.rules.player_collision = true;
+		let blocked = walk(&world);
+		assert!(blocked.x() <= 80.0 - Player::HSIZE && blocked.x() > 80.0 - Player::HSIZE - 0.01, "{}", blocked);
+	}
+
+	#[test]
+	fn sprint_stamina() {
+		let mut player = Player::new(1, vec3::ZERO, default(), "alice".into(), 0, Team::Red);
+		let mut input = InputState::new();
+		input.record_key(Key::Sprint, true);
+
+		let dt = 0.1;
+		assert_eq!(player.tick_sprint(&MoveInput::from_keys(&input), true, dt), Player::SPRINT_SPEED);
+		while player.stamina() > 0.0 {
+			player.tick_sprint(&MoveInput::from_keys(&input), true, dt);
+		}
+		assert_eq!(player.tick_sprint(&MoveInput::from_keys(&input), true, dt), 1.0);
+
+		// rest to recover.
+		input.record_key(Key::Sprint, false);
+		input.clear();
+		player.tick_sprint(&MoveInput::from_keys(&input), true, dt);
+		assert!(player.stamina() > 0.0);
+	}
+}
@@ -0,0 +1,221 @@
+use super::internal::*;
+use std::ops::Index;
+use std::sync::OnceLock;
+
+/// Powerup definitions, loaded from `assets/powerups.json`
+/// so that powerups can be added and balanced without recompiling.
+///
+/// Read by both server and clients.
+/// Powerups are referred to by index over the network,
+/// so the server and its clients must use the same file
+/// (checked on join, see `JoinMsg::assets_hash`).
+pub struct PowerupTable {
+	defs: Vec<PowerupDef>,
+	hash: u64, // of the file contents
+}
+
+/// Index into the PowerupTable.
+pub type PowerupID = u8;
+
+/// A single powerup, as defined in `assets/powerups.json`.
+#[derive(Deserialize, Clone, Debug)]
+pub struct PowerupDef {
+	/// E.g. "cowboy_hat". Also the default model, texture and sound name.
+	pub name: String,
+
+	/// Shown when picked up.
+	pub description: String,
+
+	/// Wavefront file used to draw the powerup (defaults to `name`).
+	#[serde(default)]
+	pub model: Option<String>,
+
+	/// Sound played when picked up (defaults to `name`).
+	#[serde(default)]
+	pub sound: Option<String>,
+
+	/// Seconds before the powerup wears off (lasts until death if absent).
+	#[serde(default)]
+	pub duration: Option<f32>,
+
+	/// Sound played when the powerup wears off (defaults to "protect").
+	#[serde(default)]
+	pub expire_sound: Option<String>,
+
+	/// Relative odds of a gift box turning into this powerup
+	/// (gift boxes and weapons have weight 1).
+	#[serde(default = "one")]
+	pub spawn_weight: f32,
+
+	#[serde(default)]
+	pub effects: PowerupEffects,
+}
+
+/// What a powerup does to the player wearing it.
+/// Absent fields leave the player unchanged.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct PowerupEffects {
+	/// Multiplies the weapon's fire rate.
+	pub fire_rate: f32,
+
+	/// Keep firing while the trigger is held down.
+	pub auto_fire: bool,
+
+	/// Number of jumps before having to land again (normally 1).
+	pub jump_count: u32,
+
+	/// Walking on lava does no harm.
+	pub lava_immunity: bool,
+
+	/// Number of otherwise deadly hits survived. The powerup is lost after the last one.
+	pub extra_lives: u32,
+
+	/// Multiplies walking speed.
+	pub speed: f32,
+
+	/// Effects that apply instead while standing on lava.
+	pub on_lava: Option<Box<PowerupEffects>>,
+}
+
+/// Effects of not having a powerup.
+pub static NO_EFFECTS: PowerupEffects = PowerupEffects {
+	fire_rate: 1.0,
+	auto_fire: false,
+	jump_count: 1,
+	lava_immunity: false,
+	extra_lives: 0,
+	speed: 1.0,
+	on_lava: None,
+};
+
+impl Default for PowerupEffects {
+	fn default() -> Self {
+		NO_EFFECTS.clone()
+	}
+}
+
+const POWERUPS_FILE: &str = "assets/powerups.json";
+
+static POWERUPS: OnceLock<PowerupTable> = OnceLock::new();
+
+impl PowerupTable {
+	/// Load the powerup table, if not yet loaded.
+	/// Call on startup to report a missing or invalid file gracefully,
+	/// instead of panicking on first use.
+	pub fn init() -> Result<()> {
+		if POWERUPS.get().is_none() {
+			let _ = POWERUPS.set(Self::load()?);
+		}
+		Ok(())
+	}
+
+	/// The powerup table (loaded on first use if `init` was not called).
+	pub fn get() -> &'static Self {
+		POWERUPS.get_or_init(|| Self::load().unwrap_or_else(|err| panic!("{}", err)))
+	}
+
+	fn load() -> Result<Self> {
+		let file = asset_path(POWERUPS_FILE);
+		let (defs, hash) = load_json(&file)?;
+		let table = Self::from_defs(defs).map_err(|err| anyhow!("Error in {}: {}", file.to_string_lossy(), err))?;
+		Ok(Self { hash, ..table })
+	}
+
+	fn from_defs(defs: Vec<PowerupDef>) -> Result<Self> {
+		if defs.len() > (PowerupID::MAX as usize) + 1 {
+			return Err(anyhow!("too many powerups: {}", defs.len()));
+		}
+		for (i, def) in defs.iter().enumerate() {
+			if defs[..i].iter().any(|other| other.name == def.name) {
+				return Err(anyhow!("duplicate powerup: {}", def.name));
+			}
+			if !def.spawn_weight.is_finite() || def.spawn_weight < 0.0 {
+				return Err(anyhow!("{}: spawn_weight must be >= 0, got {}", def.name, def.spawn_weight));
+			}
+			if let Some(duration) = def.duration {
+				if duration.is_nan() || duration <= 0.0 {
+					return Err(anyhow!("{}: duration must be > 0, got {}", def.name, duration));
+				}
+			}
+		}
+		if !defs.is_empty() && defs.iter().all(|def| def.spawn_weight == 0.0) {
+			return Err(anyhow!("at least one powerup must have spawn_weight > 0"));
+		}
+		Ok(Self { defs, hash: 0 })
+	}
+
+	/// Hash of the file the table was loaded from,
+	/// to tell if server and client use the same one.
+	pub fn hash(&self) -> u64 {
+		self.hash
+	}
+
+	pub fn ids(&self) -> impl Iterator<Item = PowerupID> {
+		(0..self.defs.len()).map(|i| i as PowerupID)
+	}
+
+	pub fn by_name(&self, name: &str) -> Option<PowerupID> {
+		self.defs.iter().position(|def| def.name == name).map(|i| i as PowerupID)
+	}
+}
+
+impl Index<PowerupID> for PowerupTable {
+	type Output = PowerupDef;
+
+	fn index(&self, id: PowerupID) -> &PowerupDef {
+		&self.defs[id as usize]
+	}
+}
+
+impl PowerupDef {
+	pub fn model(&self) -> &str {
+		self.model.as_deref().unwrap_or(&self.name)
+	}
+
+	pub fn sound(&self) -> &str {
+		self.sound.as_deref().unwrap_or(&self.name)
+	}
+
+	pub fn expire_sound(&self) -> &str {
+		self.expire_sound.as_deref().unwrap_or("protect")
+	}
+}
+
+fn one() -> f32 {
+	1.0
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn parse_defaults() {
+		let defs: Vec<PowerupDef> = serde_json::from_str(r#"[{"name": "hat", "description": "A hat", "effects": {"jump_count": 2}}]"#).unwrap();
+		let table = PowerupTable::from_defs(defs).unwrap();
+		let hat = &table[table.by_name("hat").unwrap()];
+		assert_eq!(hat.model(), "hat");
+		assert_eq!(hat.sound(), "hat");
+		assert_eq!(hat.spawn_weight, 1.0);
+		assert_eq!(hat.duration, None);
+		assert_eq!(hat.expire_sound(), "protect");
+		assert_eq!(hat.effects.jump_count, 2);
+		assert_eq!(hat.effects.fire_rate, 1.0);
+		assert_eq!(hat.effects.speed, 1.0);
+		assert!(!hat.effects.lava_immunity);
+	}
+
+	#[test]
+	fn reject_invalid() {
+		for json in [
+			r#"[{"name": "hat", "description": ""}, {"name": "hat", "description": ""}]"#,
+			r#"[{"name": "hat", "description": "", "duration": 0}]"#,
+			r#"[{"name": "hat", "description": "", "spawn_weight": -1}]"#,
+			r#"[{"name": "hat", "description": "", "spawn_weight": 0}, {"name": "cap", "description": "", "spawn_weight": 0}]"#,
+		] {
+			let defs: Vec<PowerupDef> = serde_json::from_str(json).unwrap();
+			assert!(PowerupTable::from_defs(defs).is_err(), "{}", json);
+		}
+	}
+}
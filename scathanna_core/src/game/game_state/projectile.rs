@@ -0,0 +1,107 @@
+use super::internal::*;
+
+// Projectiles are entities with a velocity (rockets, grenades).
+// The server advances them every tick (ServerState::tick_projectiles) and decides when they explode.
+// Clients only extrapolate their motion in between server updates.
+
+const GRAVITY: f32 = 48.0;
+
+// Fraction of velocity kept when a grenade bounces off a wall.
+const BOUNCE_DAMP: f32 = 0.5;
+
+// Sub-steps per tick, so that fast projectiles don't tunnel through thin walls.
+const SUBSTEPS: usize = 16;
+
+/// What a projectile ran into during a tick (which makes it explode).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Impact {
+	Voxel,
+	Player(ID),
+}
+
+impl EKind {
+	pub fn is_projectile(self) -> bool {
+		matches!(self, EKind::Rocket { .. } | EKind::Grenade { .. })
+	}
+
+	/// Player who fired a projectile.
+	pub fn owner(self) -> Option<ID> {
+		match self {
+			EKind::Rocket { owner } | EKind::Grenade { owner } => Some(owner),
+			_ => None,
+		}
+	}
+
+	/// Seconds before a projectile explodes by itself
+	/// (grenade fuse, or a rocket that flew off into the sky).
+	pub fn fuse(self) -> f32 {
+		match self {
+			EKind::Grenade { .. } => 2.5,
+			_ => 10.0,
+		}
+	}
+
+	/// Players within this distance of an explosion get splash damage.
+	pub fn splash_radius(self) -> f32 {
+		match self {
+			EKind::Grenade { .. } => 16.0,
+			_ => 12.0,
+		}
+	}
+
+	/// Splash damage at the center of an explosion, decreasing linearly towards `splash_radius`.
+	pub fn splash_damage(self) -> i32 {
+		Player::MAX_HEALTH
+	}
+
+	fn gravity(self) -> f32 {
+		match self {
+			EKind::Grenade { .. } => GRAVITY,
+			_ => 0.0,
+		}
+	}
+
+	// Does this projectile bounce off walls (rather than exploding)?
+	fn bounces(self) -> bool {
+		matches!(self, EKind::Grenade { .. })
+	}
+}
+
+impl Entity {
+	/// Advance a projectile by `dt` seconds.
+	/// Returns what it ran into, if anything.
+	/// Grenades bounce off walls, so they only report hitting players.
+	/// Like players and bullets, projectiles pass through non-solid voxels (water).
+	/// The owner can't be hit directly (only by splash damage).
+	pub fn tick_projectile(&mut self, world: &World, dt: f32) -> Option<Impact> {
+		self.velocity[Y] -= self.kind.gravity() * dt;
+
+		let sub_dt = dt / (SUBSTEPS as f32);
+		for _ in 0..SUBSTEPS {
+			for axis in 0..3 {
+				let mut delta = vec3::ZERO;
+				delta[axis] = self.velocity[axis] * sub_dt;
+				if !world.map.voxels.at_pos(self.position + delta).is_solid() {
+					self.position += delta;
+				} else if self.kind.bounces() {
+					self.velocity[axis] *= -BOUNCE_DAMP;
+				} else {
+					return Some(Impact::Voxel);
+				}
+			}
+
+			for (id, player) in world.players.iter() {
+				if player.spawned && Some(id) != self.kind.owner() && player.skeleton.bounds().contains(self.position) {
+					return Some(Impact::Player(id));
+				}
+			}
+		}
+		None
+	}
+
+	/// Client-side: move a projectile along its trajectory in between server updates (ignoring collisions).
+	pub fn extrapolate(&mut self, dt: f32) {
+		self.velocity[Y] -= self.kind.gravity() * dt;
+		self.position += self.velocity * dt;
+	}
+}
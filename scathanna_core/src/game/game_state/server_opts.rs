@@ -6,6 +6,39 @@ pub struct ServerOpts {
 	#[structopt(short, long, default_value = "dm")]
 	pub game_type: String,
 
+	/// Number of teams players are divided over (1 to 3)
+	#[structopt(long, default_value = "2")]
+	pub num_teams: usize,
+
+	/// Every hit kills instantly (no health/damage)
+	#[structopt(long)]
+	pub instagib: bool,
+
+	/// Voxel types that can be blown away by shots and explosions, e.g. `--destructible 6,9`.
+	/// (Default: nothing is destructible.)
+	#[structopt(long, use_delimiter = true)]
+	pub destructible: Vec<u8>,
+
+	/// Falls from extreme heights kill.
+	/// (Default: falls hurt, but leave at least 1 health.)
+	#[structopt(long)]
+	pub lethal_falls: bool,
+
+	/// Players block each other, e.g. `--player_collision false`.
+	/// (Default: depends on the game type.)
+	#[structopt(long)]
+	pub player_collision: Option<bool>,
+
+	/// Shots and explosions push players away, e.g. `--knockback true`.
+	/// (Default: depends on the game type.)
+	#[structopt(long)]
+	pub knockback: Option<bool>,
+
+	/// Random seed. A match can be replayed exactly from the seed and the client messages.
+	/// (Default: a different seed each time.)
+	#[structopt(long)]
+	pub seed: Option<u64>,
+
 	//#[structopt(long)]
 	//pub enable_levelling: bool,
 	/// TCP listen address
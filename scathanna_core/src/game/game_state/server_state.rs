@@ -1,11 +1,17 @@
 use super::internal::*;
+use std::collections::VecDeque;
 use ServerMsg::*;
 
 /// A game server's mutable state and business logic.
 ///
 /// Owned and controlled by a NetServer, who adds an RPC layer on top.
+///
+/// Deterministic: all randomness comes from `rng` (seeded by `ServerOpts::seed`),
+/// and time only advances through `handle_tick`.
+/// So a match can be replayed exactly from its seed plus the sequence of join, drop, message and tick events.
 pub struct ServerState {
 	next_player_id: ID,
+	next_entity_id: EID,
 	rng: RefCell<StdRng>,
 	maplist: Vec<String>,
 	curr_map: usize,
@@ -15,44 +21,220 @@ pub struct ServerState {
 	score: HashMap<ID, i32>,
 	gametype: GameType,
 
+	// Seconds until each projectile explodes by itself.
+	fuses: HashMap<EID, f32>,
+
+	// Taken pickup points: seconds until re-populated, and with what.
+	pickup_timers: HashMap<usize, (f32, EKind)>,
+
+	// Pickup point each (not yet picked up) item sits on.
+	pickup_entities: HashMap<EID, usize>,
+
+	// Seconds since each spawn point was last used (avoid spawning players on top of each other).
+	spawn_since_used: Vec<f32>,
+
+	// Players are divided over the first `num_teams` teams.
+	num_teams: usize,
+
 	// Tilt the odds of good powerups in favor of the worst player.
 	enable_levelling: bool,
 
+	// Every hit kills, regardless of hit location.
+	instagib: bool,
+
+	// Voxel types blown away by shots and explosions (empty: indestructible map).
+	destructible: Vec<VoxelType>,
+
+	// Positions of the voxels changed since the map was loaded, sent to players joining later
+	// (as they are now, see changed_voxels: shooting the same spot again does not add to it).
+	voxel_edits: HashSet<ivec3>,
+
+	// Seconds until players standing on a damaging voxel (lava, ...) get hurt again.
+	hazard_cooldown: HashMap<ID, f32>,
+
+	// Falls from extreme heights kill (otherwise falls leave at least 1 health).
+	lethal_falls: bool,
+
+	// Players currently in the air (not standing, not swimming).
+	falls: HashMap<ID, Fall>,
+
+	// Knockback sent to players, in order, not yet seen in their moves (see MoveInput::knockback).
+	knockback: HashMap<ID, VecDeque<vec3>>,
+
+	// Ammo given to players since their last death (see GiveWeapon), to check which weapons they may use.
+	inventory: HashMap<ID, [u32; NUM_WEAPONS]>,
+
+	// Seconds until players may fire again.
+	gun_cooldown: HashMap<ID, f32>,
+
+	// Bullets of players' last (instant) shot that may still be reported as hits: weapon and number left.
+	bullets: HashMap<ID, (Weapon, usize)>,
+
+	// Physics steps players may still take (see validate_move): refilled as time passes, up to MAX_STEP_BUDGET.
+	step_budget: HashMap<ID, f32>,
+
+	// Number of rejected moves, shots, ... per player (see log_rejected).
+	rejections: HashMap<ID, u32>,
+
 	pub pending_diffs: Vec<Envelope<ServerMsg>>,
 }
 
 // invulnerability duration (e.g. after respawn, party hat protection).
 const DEFAULT_INVULN_TTL: f32 = 1.5;
 
+// health regeneration: seconds after being hit before health starts regenerating,
+// then REGEN_STEP health every REGEN_INTERVAL seconds.
+const REGEN_DELAY: f32 = 4.0;
+const REGEN_INTERVAL: f32 = 0.5;
+const REGEN_STEP: i32 = 5;
+
+// seconds in advance to announce items appearing at pickup points (if enabled in the map's metadata).
+const PICKUP_ANNOUNCE_TIME: f32 = 10.0;
+
+// seconds between damage by voxels like lava (see Palette).
+const HAZARD_INTERVAL: f32 = 1.0;
+
+// seconds by which shots may arrive faster than the weapon's cooldown (network jitter).
+const FIRE_TOLERANCE: f32 = 0.2;
+
+// fall damage: landing faster than SAFE_LANDING_SPEED costs FALL_DAMAGE health per unit of speed,
+// landing faster than LETHAL_LANDING_SPEED kills (with --lethal_falls).
+// (Walking off a ledge 24 units high is safe, 104 units is lethal.)
+const SAFE_LANDING_SPEED: f32 = 48.0;
+const LETHAL_LANDING_SPEED: f32 = 100.0;
+const FALL_DAMAGE: f32 = 2.0;
+
+// a player this close to a jump pad may have been launched by it.
+const JUMP_PAD_MARGIN: f32 = 4.0;
+
+// Tracked by the server from a player's (validated) moves between leaving the ground and landing.
+struct Fall {
+	// highest point reached.
+	peak: f32,
+	// launched by a jump pad: no fall damage.
+	launched: bool,
+}
+
+// knockback: speed per health point of damage dealt (see GameRules),
+// and the upward tilt that lifts players hit by bullets off the ground (else friction stops them at once).
+const KNOCKBACK: f32 = 0.4;
+const KNOCKBACK_LIFT: f32 = 0.5;
+
+// client movement may differ this much from the server re-playing it before being corrected.
+const MOVE_TOLERANCE: f32 = 2.0;
+
+// moving further than this in a single frame can only be a teleport (see handle_fall).
+const MAX_MOVE_STEP: f32 = 50.0;
+
+// physics steps a client may take ahead of the server's clock (1 second's worth, for network jitter).
+const MAX_STEP_BUDGET: f32 = 1.0 / TIMESTEP;
+
+// capture-the-flag scoring.
+const FLAG_CAPTURE_SCORE: i32 = 5;
+const FLAG_RETURN_SCORE: i32 = 1;
+
+// seconds before a dropped flag returns to its base by itself.
+const FLAG_RETURN_TTL: f32 = 30.0;
+
+// king-of-the-hill: seconds a zone must be held per point scored.
+const ZONE_SCORE_INTERVAL: f32 = 1.0;
+
+// elimination: seconds between a round's winner announcement and the next round,
+// the score for the last player standing (free-for-all),
+// and the number of round wins that wins the match.
+const ROUND_END_DELAY: f32 = 5.0;
+const ROUND_WIN_SCORE: i32 = 3;
+const ROUNDS_TO_WIN: u32 = 5;
+
+// radius of the hole blown into destructible voxels by a bullet and an explosion.
+const SHOT_CRATER_RADIUS: f32 = 1.5;
+const EXPLOSION_CRATER_RADIUS: f32 = 4.0;
+
 impl ServerState {
 	pub fn new(opts: ServerOpts) -> Result<Self> {
-		let ServerOpts { maplist, game_type, .. } = opts;
+		let ServerOpts {
+			maplist,
+			game_type,
+			num_teams,
+			instagib,
+			seed,
+			destructible,
+			lethal_falls,
+			player_collision,
+			knockback,
+			..
+		} = opts;
 		let enable_levelling = true; // TODO
+		PowerupTable::init()?;
+		Palette::init()?;
 
 		println!("maplist: {}", maplist.join(", "));
 		println!("game type: {}", &game_type);
+		if instagib {
+			println!("instagib: every hit kills");
+		}
+		if !destructible.is_empty() {
+			println!("destructible voxels: {:?}", &destructible);
+		}
+		if lethal_falls {
+			println!("lethal falls");
+		}
+		let gametype: GameType = game_type.parse()?;
+		let rules = GameRules {
+			player_collision: player_collision.unwrap_or(gametype.default_rules().player_collision),
+			knockback: knockback.unwrap_or(gametype.default_rules().knockback),
+		};
+		println!("player collision: {}", rules.player_collision);
+		println!("knockback: {}", rules.knockback);
+		let seed = seed.unwrap_or_else(rand::random);
+		println!("random seed: {}", seed);
 
 		if maplist.len() == 0 {
 			return Err(anyhow!("server: maplist: need at least one map"));
 		}
+		if !(1..=NUM_TEAMS).contains(&num_teams) {
+			return Err(anyhow!("server: num_teams: need 1 to {} teams, got {}", NUM_TEAMS, num_teams));
+		}
 		// TODO: verify all maps on startup.
 
 		let curr_map = 0;
 
-		let world = World::from_map(&maplist[curr_map], default(), default())?;
+		let mut world = World::from_map(&maplist[curr_map], default(), default())?;
+		world.rules = rules;
+		check_pickup_points(&world)?;
 
 		let mut slf = Self {
 			enable_levelling,
-			gametype: game_type.parse()?,
+			instagib,
+			destructible: destructible.into_iter().map(VoxelType).collect(),
+			voxel_edits: default(),
+			hazard_cooldown: default(),
+			lethal_falls,
+			falls: default(),
+			knockback: default(),
+			inventory: default(),
+			gun_cooldown: default(),
+			bullets: default(),
+			step_budget: default(),
+			rejections: default(),
+			gametype,
+			num_teams,
 			maplist,
 			curr_map,
 			pending_diffs: default(),
 			world,
 			next_player_id: 1,
-			rng: RefCell::new(StdRng::seed_from_u64(123)),
+			next_entity_id: 1,
+			rng: RefCell::new(StdRng::seed_from_u64(seed)),
 			score: default(),
+			fuses: default(),
+			spawn_since_used: default(),
+			pickup_timers: default(),
+			pickup_entities: default(),
 		};
 		slf.populate_all_pickups();
+		slf.populate_flags();
+		slf.reset_zones();
 		Ok(slf)
 	}
 
@@ -61,10 +243,17 @@ impl ServerState {
 	// ____________________________________________________________________________ join
 
 	/// Add a new player to the game and return their unique ID.
-	pub fn join_new_player(&mut self, join_msg: JoinMsg) -> ID {
+	/// Refused if the client's asset files differ from ours.
+	pub fn join_new_player(&mut self, join_msg: JoinMsg) -> Result<ID> {
+		if join_msg.assets_hash != JoinMsg::assets_hash() {
+			return Err(anyhow!("{}'s asset files (assets/*.json) differ from the server's", &join_msg.name));
+		}
 		let player_id = self.new_player_id();
-		let spawn_point = self.pick_spawn_point();
-		let player = Player::new(player_id, spawn_point.position(), spawn_point.orientation(), join_msg.name, join_msg.avatar_id, join_msg.team);
+		let team = self.assign_team(join_msg.team);
+		let spawn_point = self.pick_spawn_point(team);
+		let mut player = Player::new(player_id, spawn_point.position(), spawn_point.orientation(), join_msg.name, join_msg.avatar_id, team);
+		// elimination: joining mid-round means waiting for the next one.
+		player.spectating = self.round_in_progress();
 
 		// insert score 0 or keep previous score if same player joined earlier.
 		self.score.entry(player_id).or_default();
@@ -82,14 +271,25 @@ impl ServerState {
 			}
 			.to_just(player_id),
 		);
+		self.pending_diffs.push(SetRules(self.world.rules).to_just(player_id));
+		// the map on the client's disk does not have the holes blown into it so far.
+		for (range, value) in self.changed_voxels() {
+			self.pending_diffs.push(SetVoxels { range, value }.to_just(player_id));
+		}
 
 		self.log(format!("{} joined", &player.name)); //
-		self.hud_message(player_id, format!("Welcome to {}.", self.map_name()));
+		match self.gametype.is_team() {
+			true => self.hud_message(player_id, format!("Welcome to {}.\nYou are on the {} team.", self.map_name(), team)),
+			false => self.hud_message(player_id, format!("Welcome to {}.", self.map_name())),
+		}
 		self.pending_diffs.push(PlaySound(SoundEffect::raw("begin")).to_just(player_id));
 		self.pending_diffs.push(AddPlayer(player).to_all());
+		if let GameType::KingOfTheHill(koth) = &self.gametype {
+			self.pending_diffs.push(UpdateZones(koth.zones.clone()).to_just(player_id));
+		}
 		self.broadcast_scores_mini();
 
-		player_id
+		Ok(player_id)
 	}
 
 	// ____________________________________________________________________________ msg
@@ -105,11 +305,14 @@ impl ServerState {
 
 		use ClientMsg::*;
 		match msg {
-			MovePlayer(frame) => self.handle_move_player(player_id, frame),
+			MovePlayer(frame, inputs) => self.handle_move_player(player_id, frame, inputs),
 			ReadyToSpawn => self.handle_ready_to_respawn(player_id),
 			AddEffect(effect) => self.handle_add_effect(player_id, effect),
 			PlaySound(sound) => self.handle_play_sound(player_id, sound),
-			HitPlayer(victim_id) => self.handle_hit_player(player_id, victim_id),
+			HitPlayer(victim_id, location) => self.handle_hit_player(player_id, victim_id, location),
+			HitVoxel(pos) => self.handle_hit_voxel(player_id, pos),
+			SwitchWeapon(weapon) => self.handle_switch_weapon(player_id, weapon),
+			Fire(weapon) => self.handle_fire(player_id, weapon),
 			Command(cmd) => self.handle_command(player_id, cmd),
 		};
 	}
@@ -117,7 +320,12 @@ impl ServerState {
 	// ____________________________________________________________________________ tick
 
 	pub fn handle_tick(&mut self, dt: f32) {
+		self.spawn_since_used.iter_mut().for_each(|t| *t += dt);
 		self.tick_pickups(dt);
+		self.tick_flags(dt);
+		self.tick_zones(dt);
+		self.tick_rounds(dt);
+		self.tick_projectiles(dt);
 		self.tick_players(dt);
 	}
 
@@ -125,37 +333,179 @@ impl ServerState {
 
 	// Handle a client's MovePlayer message:
 	// update the server's world and broadcast the move to all other clients.
-	fn handle_move_player(&mut self, player_id: ID, frame: Frame) {
+	fn handle_move_player(&mut self, player_id: ID, frame: Frame, inputs: Vec<MoveInput>) {
+		// the hitbox may only shrink by crouching.
+		let frame = Frame {
+			vsize: frame.vsize.clamp(Player::CROUCH_VSIZE, Player::VSIZE),
+			..frame
+		};
+		let frame = match (self.player(player_id).spawned, inputs.is_empty()) {
+			(true, false) => self.validate_move(player_id, frame, &inputs),
+			(true, true) => self.validate_look(player_id, frame),
+			(false, _) => frame,
+		};
+		let prev_position = self.player(player_id).position();
 		self.record_move_player(player_id, frame);
 
-		self.handle_lava(player_id);
+		self.handle_fall(player_id, prev_position);
+		self.handle_hazards(player_id);
 		self.handle_pickups(player_id);
 		self.handle_off_world(player_id);
 	}
 
-	// After a player has moved: check if they get lava damage.
-	fn handle_lava(&mut self, player_id: ID) {
+	// Re-play the client's physics steps from where we last had the player (see Player::step).
+	// The client's frame must agree, else we send a correction and use our own result.
+	// (Small differences are tolerated: e.g. a powerup may reach the client a little late.)
+	// Clients may not take more steps than fit in the time that has passed (see `step_budget`):
+	// excess steps are dropped (but counted as seen, so that the client does not re-play them after the correction).
+	fn validate_move(&mut self, player_id: ID, frame: Frame, inputs: &[MoveInput]) -> Frame {
+		let budget = self.step_budget.entry(player_id).or_insert(MAX_STEP_BUDGET);
+		let allowed = inputs.len().min(*budget as usize);
+		*budget -= allowed as f32;
+		let (inputs, excess) = inputs.split_at(allowed);
+
+		let mut replayed = self.player(player_id).clone();
+		let mut discard = vec![]; // sounds and effects were already produced by the client.
+		for input in inputs {
+			let mut input = input.sanitized();
+			// only knockback that we handed out ourselves (one impulse per step, in the order we sent them).
+			if input.knockback != vec3::ZERO {
+				input.knockback = self.knockback.get_mut(&player_id).and_then(VecDeque::pop_front).unwrap_or(vec3::ZERO);
+			}
+			replayed.step(&mut discard, &input, &self.world);
+		}
+		replayed.local.steps += excess.len() as u64;
+		let steps = replayed.local.steps;
+		self.player_mut(player_id).local = replayed.local; // jumps, stamina, ... (not synced)
+
+		// Position and velocity are always our own: a client may not e.g. launch itself without standing on a jump pad,
+		// nor gain up to MOVE_TOLERANCE with every message. Small differences (rounding) are not worth a correction.
+		if excess.is_empty() && (replayed.skeleton.position - frame.position).len() <= MOVE_TOLERANCE {
+			return Frame {
+				position: replayed.skeleton.position,
+				velocity: replayed.skeleton.velocity,
+				..frame
+			};
+		}
+		self.log_rejected(
+			player_id,
+			format!(
+				"corrected move by {} to {} (claimed {}, {} steps too many)",
+				&replayed.name,
+				replayed.skeleton.position,
+				frame.position,
+				excess.len()
+			),
+		);
+		let corrected = replayed.skeleton.frame();
+		self.pending_diffs.push(CorrectMove(corrected.clone(), steps).to_just(player_id));
+		corrected
+	}
+
+	// A frame without physics steps may only change where the player looks (see Player::control):
+	// any movement is corrected.
+	fn validate_look(&mut self, player_id: ID, frame: Frame) -> Frame {
+		let player = self.player(player_id);
+		let unmoved = Frame {
+			orientation: frame.orientation,
+			..player.skeleton.frame()
+		};
+		if (frame.position - unmoved.position).len() > MOVE_TOLERANCE || frame.vsize != unmoved.vsize {
+			let msg = format!("corrected move by {} to {} (claimed {} without steps)", &player.name, unmoved.position, frame.position);
+			self.pending_diffs.push(CorrectMove(unmoved.clone(), player.local.steps).to_just(player_id));
+			self.log_rejected(player_id, msg);
+		}
+		unmoved
+	}
+
+	// After a player has moved: check if they get damaged by lava (or other voxels, see Palette).
+	fn handle_hazards(&mut self, player_id: ID) {
 		// don't kill dead player again
 		if !self.player(player_id).spawned {
 			return;
 		}
 
-		// party hat grants lava immunity
-		if self.player(player_id).powerup == Some(EKind::PartyHat) {
+		// some powerups (e.g. party hat) grant lava immunity
+		if self.player(player_id).powerup_effects(&self.world).lava_immunity {
 			return;
 		}
 
-		if self.player(player_id).is_on_lava(&self.world) {
-			if self.try_kill_player(player_id, None) {
+		if self.hazard_cooldown.contains_key(&player_id) {
+			return;
+		}
+
+		let voxel = self.player(player_id).skeleton.ground_voxel(&self.world).def();
+		if voxel.damage > 0 {
+			self.hazard_cooldown.insert(player_id, HAZARD_INTERVAL);
+			if self.try_damage_player(player_id, None, voxel.damage) {
 				self.increment_score(player_id, -1);
 				self.record_add_effect(Effect::particle_explosion(self.player(player_id).center(), RED)); // TODO: duplicate with confetti
 				self.broadcast_sound_at("death_lava", self.player(player_id).center(), 3.0);
-				self.log(format!("{} went swimming in hot lava", &self.player(player_id).name));
-				self.pending_diffs.push(UpdateHUD(HUDUpdate::Message("You fell in lava".to_owned())).to_just(player_id));
+				self.log(format!("{} went swimming in hot {}", &self.player(player_id).name, voxel.name));
+				self.pending_diffs.push(UpdateHUD(HUDUpdate::Message(format!("You fell in {}", voxel.name))).to_just(player_id));
 			}
 		}
 	}
 
+	// After a player has moved: track falls, and hurt players landing too hard.
+	// The landing speed follows from the height fallen, as seen by the server,
+	// rather than from the velocity claimed by the client.
+	fn handle_fall(&mut self, player_id: ID, prev_position: vec3) {
+		let player = self.player(player_id);
+		let position = player.position();
+
+		// respawned, teleported or swimming: start over.
+		if !player.spawned || (position - prev_position).len() > MAX_MOVE_STEP || player.skeleton.is_in_water(&self.world) {
+			self.falls.remove(&player_id);
+			return;
+		}
+
+		if !player.skeleton.on_ground(&self.world) {
+			let launched = self
+				.world
+				.map
+				.metadata
+				.triggers
+				.iter()
+				.any(|t| matches!(t.action, TriggerAction::JumpPad { .. }) && t.is_near(prev_position, JUMP_PAD_MARGIN));
+			let fall = self.falls.entry(player_id).or_insert(Fall { peak: position.y(), launched });
+			fall.peak = f32::max(fall.peak, position.y());
+			fall.launched |= launched;
+			return;
+		}
+
+		// landed
+		let fall = match self.falls.remove(&player_id) {
+			Some(fall) => fall,
+			None => return,
+		};
+		let player = self.player(player_id);
+		if fall.launched || player.skeleton.ground_voxel(&self.world).def().bounciness > 0.0 || player.invulnerability_ttl.is_some() {
+			return;
+		}
+		let speed = Skeleton::landing_speed(fall.peak - position.y());
+		if speed <= SAFE_LANDING_SPEED {
+			return;
+		}
+
+		let damage = match self.lethal_falls && speed >= LETHAL_LANDING_SPEED {
+			true => Player::MAX_HEALTH,
+			false => ((speed - SAFE_LANDING_SPEED) * FALL_DAMAGE) as i32,
+		};
+		let health = self.player(player_id).health - damage;
+		if health <= 0 && self.lethal_falls {
+			if self.try_damage_player(player_id, None, damage) {
+				self.log(format!("{} fell to their death", &self.player(player_id).name));
+				self.hud_message(player_id, "You fell to your death".to_owned());
+			}
+			return;
+		}
+		self.record_apply_to_player(player_id, |p| {
+			p.health = i32::max(1, health);
+			p.regen_ttl = REGEN_DELAY;
+		});
+	}
+
 	fn handle_off_world(&mut self, player_id: ID) {
 		if !self.player(player_id).spawned {
 			return;
@@ -177,24 +527,52 @@ impl ServerState {
 		}
 	}
 
-	// re-populate taken pick-up points from time to time.
-	fn tick_pickups(&mut self, _dt: f32) {
-		let mut rng = rand::thread_rng();
-		for (i, pp) in self.world.map.metadata.pickup_points.iter().copied().collect::<SmallVec<_>>().into_iter().enumerate() {
-			if pp.taken && rng.gen::<f32>() < 0.001 {
-				self.populate_pickup(i);
+	// re-populate taken pick-up points once their respawn delay has passed.
+	fn tick_pickups(&mut self, dt: f32) {
+		// in a fixed order: re-populating draws random numbers and entity IDs (see ServerOpts::seed).
+		let mut timers = mem::take(&mut self.pickup_timers).into_iter().collect::<Vec<_>>();
+		timers.sort_by_key(|&(i, _)| i);
+		for (i, (ttl, item)) in timers.iter_mut() {
+			let i = *i;
+			let before = *ttl;
+			*ttl -= dt;
+			if self.world.map.metadata.pickup_points[i].announce && before > PICKUP_ANNOUNCE_TIME && *ttl <= PICKUP_ANNOUNCE_TIME {
+				self.announce(format!("{} in {} seconds", item.as_str().replace("_", " "), PICKUP_ANNOUNCE_TIME));
+			}
+		}
+		for (i, (ttl, item)) in timers {
+			if ttl <= 0.0 {
+				self.populate_pickup_with(i, item);
+			} else {
+				self.pickup_timers.insert(i, (ttl, item));
 			}
 		}
 	}
 
-	// re-populate pick-up point `i`.
+	// re-populate pick-up point `i` with an item from its item table.
 	pub fn populate_pickup(&mut self, i: usize) {
+		let item = self.world.map.metadata.pickup_points[i].random_item(&mut *self.rng.borrow_mut(), i);
+		self.populate_pickup_with(i, item)
+	}
+
+	fn populate_pickup_with(&mut self, i: usize, item: EKind) {
 		let pickup_point = &mut self.world.map.metadata.pickup_points[i];
 		pickup_point.taken = false;
-		let entity = Entity::new(pickup_point.position(), EKind::GiftBox { pickup_point_id: Some(i) });
+		let position = pickup_point.position();
+		let entity = self.new_entity(position, item);
+		self.pickup_entities.insert(entity.id(), i);
 		self.record_add_entity(entity);
 	}
 
+	// an item was taken from pick-up point `i`: start its respawn timer.
+	fn take_pickup(&mut self, i: usize) {
+		let pickup_point = &mut self.world.map.metadata.pickup_points[i];
+		pickup_point.taken = true;
+		let ttl = pickup_point.respawn_delay;
+		let item = pickup_point.random_item(&mut *self.rng.borrow_mut(), i);
+		self.pickup_timers.insert(i, (ttl, item));
+	}
+
 	// after a player has moved: check if they're on a pick-up.
 	pub fn handle_pickups(&mut self, player_id: ID) {
 		if !self.player(player_id).spawned {
@@ -203,8 +581,11 @@ impl ServerState {
 
 		let player_bounds = self.player(player_id).skeleton.bounds();
 		for eid in self.entity_ids() {
-			if touches(&player_bounds, self.entity(eid).unwrap().bounds()) {
-				self.pick_up(player_id, eid)
+			// entity may have been removed by an earlier pick-up (e.g. flag capture).
+			if let Some(entity) = self.entity(eid) {
+				if touches(&player_bounds, entity.bounds()) {
+					self.pick_up(player_id, eid)
+				}
 			}
 		}
 	}
@@ -213,36 +594,60 @@ impl ServerState {
 	// Gift boxes are replaced by a random other powerup.
 	fn pick_up(&mut self, player_id: ID, pickup_id: EID) {
 		let powerup = match self.entity(pickup_id).unwrap().kind {
-			EKind::GiftBox { pickup_point_id: pickup_point } => {
-				if let Some(pickup_point) = pickup_point {
-					self.world.map.metadata.pickup_points.get_mut(pickup_point).map(|pp| pp.taken = true);
-				}
-				self.randomish_powerup_for(player_id)
-			}
+			EKind::Flag { team } => return self.touch_flag(player_id, pickup_id, team),
+			EKind::Rocket { .. } | EKind::Grenade { .. } => return, // see tick_projectiles
+			EKind::GiftBox { .. } => self.randomish_powerup_for(player_id),
 			other => other,
 		};
+		if let Some(i) = self.pickup_entities.remove(&pickup_id) {
+			self.take_pickup(i);
+		}
 		let powerup_name = powerup.as_str().replace("_", " "); // "xmas_hat" => "xmas hat"
 
 		self.record_remove_entity(pickup_id);
-		self.record_apply_to_player(player_id, |p| p.powerup = Some(powerup));
-		self.broadcast_sound_at(powerup.as_str(), self.player(player_id).position(), 1.0);
+
+		if let EKind::Gun(weapon) = powerup {
+			return self.give_weapon(player_id, weapon);
+		}
+
+		self.record_apply_to_player(player_id, |p| {
+			p.powerup = Some(powerup);
+			p.extra_lives = powerup.effects().extra_lives;
+			p.powerup_ttl = powerup.duration();
+			p.health = Player::MAX_HEALTH; // pickups also restore health
+		});
+		self.broadcast_sound_at(powerup.sound(), self.player(player_id).position(), 1.0);
 
 		self.hud_message(player_id, format!("You got the {}\n[{}]", powerup_name, powerup.description()));
 		self.log(format!("{} has the {}", &self.player(player_id).name, powerup_name));
 	}
 
+	// Weapons go to the player's inventory (kept client-side, recorded here too), not in the powerup slot.
+	fn give_weapon(&mut self, player_id: ID, weapon: Weapon) {
+		let weapon_name = weapon.as_str().replace("_", " ");
+		self.inventory.entry(player_id).or_default()[weapon.index()] += weapon.ammo().unwrap_or_default();
+		self.pending_diffs.push(GiveWeapon(weapon).to_just(player_id));
+		self.record_apply_to_player(player_id, |p| p.health = Player::MAX_HEALTH);
+		self.broadcast_sound_at("gift_box", self.player(player_id).position(), 1.0);
+
+		self.hud_message(player_id, format!("You got the {}\n[{}]", weapon_name, weapon.description()));
+		self.log(format!("{} has the {}", &self.player(player_id).name, weapon_name));
+	}
+
 	// Pick a random-ish powerup for a player.
 	// Levelling gives better powerups to worse players.
 	fn randomish_powerup_for(&self, player_id: ID) -> EKind {
 		if self.enable_levelling {
-			if self.has_best_score(player_id) && by_chance(0.3) {
+			if self.has_best_score(player_id) && self.by_chance(0.3) {
 				return EKind::GiftBox { pickup_point_id: None };
 			}
-			if self.has_worst_score(player_id) && by_chance(0.3) {
-				return EKind::BerserkerHelmet;
+			if self.has_worst_score(player_id) && self.by_chance(0.3) {
+				if let Some(id) = PowerupTable::get().by_name("berserker_helmet") {
+					return EKind::Powerup(id);
+				}
 			}
 		}
-		EKind::random_powerup_except(self.player(player_id).powerup)
+		EKind::random_powerup_except(&mut *self.rng.borrow_mut(), self.player(player_id).powerup)
 	}
 
 	fn tick_players(&mut self, dt: f32) {
@@ -251,25 +656,90 @@ impl ServerState {
 		}
 	}
 
+	// A timed powerup has run out.
+	fn expire_powerup(&mut self, player_id: ID) {
+		let powerup = self.player(player_id).powerup;
+		self.record_apply_to_player(player_id, |p| {
+			p.powerup = None;
+			p.powerup_ttl = None;
+			p.extra_lives = 0;
+		});
+
+		if let Some(EKind::Powerup(id)) = powerup {
+			let def = &PowerupTable::get()[id];
+			let powerup_name = def.name.replace("_", " ");
+			self.broadcast_sound_at(def.expire_sound(), self.player(player_id).position(), 1.0);
+			self.hud_message(player_id, format!("Your {} wore off", powerup_name));
+			self.log(format!("{}'s {} wore off", self.player(player_id).name, powerup_name));
+		}
+	}
+
 	fn tick_player(&mut self, player_id: ID, dt: f32) {
+		// damaging voxels hurt again after a while
+		if let Some(ttl) = self.hazard_cooldown.get_mut(&player_id) {
+			*ttl -= dt;
+			if *ttl <= 0.0 {
+				self.hazard_cooldown.remove(&player_id);
+			}
+		}
+
+		// time passes for movement
+		let budget = self.step_budget.entry(player_id).or_insert(MAX_STEP_BUDGET);
+		*budget = f32::min(*budget + dt / TIMESTEP, MAX_STEP_BUDGET);
+
+		// guns cool down
+		if let Some(ttl) = self.gun_cooldown.get_mut(&player_id) {
+			*ttl -= dt;
+			if *ttl <= 0.0 {
+				self.gun_cooldown.remove(&player_id);
+			}
+		}
+
 		// invulnerability wears off after some time
 		if let Some(ttl) = self.player_mut(player_id).invulnerability_ttl {
 			let ttl = ttl - dt;
 			self.player_mut(player_id).invulnerability_ttl = if ttl > 0.0 { Some(ttl) } else { None }
 		}
+
+		// so do timed powerups
+		if let Some(ttl) = self.player_mut(player_id).powerup_ttl {
+			let ttl = ttl - dt;
+			self.player_mut(player_id).powerup_ttl = Some(ttl);
+			if ttl <= 0.0 {
+				self.expire_powerup(player_id);
+			}
+		}
+
+		// health regenerates some time after the last hit
+		let p = self.player_mut(player_id);
+		if p.spawned && p.health < Player::MAX_HEALTH {
+			p.regen_ttl -= dt;
+			if p.regen_ttl <= 0.0 {
+				self.record_apply_to_player(player_id, |p| {
+					p.health = (p.health + REGEN_STEP).min(Player::MAX_HEALTH);
+					p.regen_ttl = REGEN_INTERVAL;
+				});
+			}
+		}
 	}
 
 	// ____________________________________________________________________________ shoot
 
 	// Handle a client saying they just shot a player.
-	// We trust clients not to lie about this.
+	// We trust clients not to lie about what they hit,
+	// but they must have fired a bullet to do so (see take_bullet).
 	//
 	// Hitting players is computed client-side for latency reasons:
 	// a client always sees other players at a location that lags slightly behind.
 	// If a client hits a player where they see them on their screen, then it should
 	// count as a hit regardless of latency.
 	// Otherwise players with more than about 30ms latency would be at a noticeable disadvantage.
-	pub fn handle_hit_player(&mut self, player_id: ID, victim_id: ID) {
+	pub fn handle_hit_player(&mut self, player_id: ID, victim_id: ID, location: HitLocation) {
+		let weapon = match self.take_bullet(player_id) {
+			Some(weapon) => weapon,
+			None => return self.log_rejected(player_id, format!("rejected hit by {}: no bullet fired", &self.player(player_id).name)),
+		};
+
 		if !self.world.players.contains(victim_id) {
 			// victim has disconnected in a network race.
 			return;
@@ -281,29 +751,234 @@ impl ServerState {
 			return;
 		}
 
-		if self.try_kill_player(victim_id, Some(player_id)) {
-			self.increment_score(player_id, 1);
-			self.broadcast_sound_at("kill", self.player(victim_id).position(), 1.0);
-			self.hud_message(victim_id, format!("You got confettied by {}", self.player(player_id).name));
-			self.hud_message(player_id, format!("You confettied {}", self.player(victim_id).name));
-			self.log(format!(
-				"{} ({}) confetti'd {} ({})",
-				self.player(player_id).name,
-				self.score(player_id),
-				self.player(victim_id).name,
-				self.score(victim_id)
-			));
+		let damage = match self.instagib {
+			true => Player::MAX_HEALTH,
+			false => weapon.damage(location),
+		};
+
+		let damaged = !self.is_protected(victim_id, Some(player_id));
+		if self.try_damage_player(victim_id, Some(player_id), damage) {
+			let headshot = if location == HitLocation::Head { " (headshot)" } else { "" };
+			self.record_kill(player_id, victim_id, headshot);
+		}
+
+		if damaged {
+			let direction = (self.player(victim_id).center() - self.player(player_id).center()).safe_normalized();
+			self.knock_back(victim_id, damage as f32 * KNOCKBACK * (direction + vec3(0.0, KNOCKBACK_LIFT, 0.0)));
+		}
+	}
+
+	// Handle a client shooting a voxel: blow a small hole in the map, if destructible.
+	// Like hitting a player, this takes a bullet fired (see take_bullet).
+	// The voxel must be within range, and in sight of the player's gun (or eye, see Player::line_of_fire)
+	// as far as we know where the player is. (A ray towards the voxel's center may clip a neighbor first.)
+	fn handle_hit_voxel(&mut self, player_id: ID, pos: ivec3) {
+		let weapon = match self.take_bullet(player_id) {
+			Some(weapon) => weapon,
+			None => return self.log_rejected(player_id, format!("rejected hit by {} on voxel {}: no bullet fired", &self.player(player_id).name, pos)),
+		};
+
+		let center = pos.to_f32() + vec3(0.5, 0.5, 0.5);
+		let player = self.player(player_id);
+		let in_range = (center - player.center()).len() <= weapon.range() + SHOT_CRATER_RADIUS;
+		let in_sight = [player.line_of_fire(&self.world).start, player.line_of_sight().start]
+			.into_iter()
+			.filter_map(|start| self.first_voxel_hit(start.to_f32(), center))
+			.any(|hit| (hit - pos).map(i32::abs).reduce(i32::max) <= 1);
+		if !in_range || !in_sight {
+			// a network race, or a cheat.
+			self.log_rejected(player_id, format!("rejected hit by {} on voxel {} (out of range or out of sight)", &player.name, pos));
+			return;
+		}
+		if self.destroy_voxels(center, SHOT_CRATER_RADIUS) {
+			self.record_add_effect(Effect::particle_explosion(center, WHITE));
+		}
+	}
+
+	// The voxel first hit by a ray from `start` towards `target`, if any.
+	fn first_voxel_hit(&self, start: vec3, target: vec3) -> Option<ivec3> {
+		let ray = Ray64::new(start.into(), (target - start).normalized().into());
+		// just inside the voxel, like the client's shots (see Player::shoot_ray).
+		self.world.map.intersect(&ray).map(|t| ray.at(t + 0.01).to_f32().map(|v| v.floor() as i32))
+	}
+
+	// Score and announce that `player_id` killed `victim_id`.
+	// `detail` is appended to the HUD messages (e.g. " (headshot)").
+	fn record_kill(&mut self, player_id: ID, victim_id: ID, detail: &str) {
+		self.increment_score(player_id, 1);
+		self.broadcast_sound_at("kill", self.player(victim_id).position(), 1.0);
+		self.hud_message(victim_id, format!("You got confettied by {}{}", self.player(player_id).name, detail));
+		self.hud_message(player_id, format!("You confettied {}{}", self.player(victim_id).name, detail));
+		self.log(format!(
+			"{} ({}) confetti'd {} ({})",
+			self.player(player_id).name,
+			self.score(player_id),
+			self.player(victim_id).name,
+			self.score(victim_id)
+		));
+	}
+
+	// ____________________________________________________________________________ projectiles
+
+	// Handle a client firing their weapon.
+	// A projectile (rocket, grenade, ...) leaves from where we have the player's gun, in their look direction.
+	// From here on, it is simulated by the server.
+	// Instant bullets are traced by the client, which reports what they hit (see handle_hit_player, handle_hit_voxel).
+	fn handle_fire(&mut self, player_id: ID, weapon: Weapon) {
+		if !self.try_fire(player_id, weapon) {
+			return;
+		}
+
+		match weapon.projectile(player_id) {
+			Some(kind) => {
+				let line_of_fire = self.player(player_id).line_of_fire(&self.world);
+				let entity = self.new_entity(line_of_fire.start.to_f32(), kind).with_velocity(weapon.projectile_speed() * line_of_fire.dir.to_f32());
+				self.fuses.insert(entity.id(), kind.fuse());
+				self.record_add_entity(entity);
+			}
+			None => {
+				self.bullets.insert(player_id, (weapon, weapon.pellets()));
+			}
+		}
+	}
+
+	// Check that a player may fire `weapon` now: spawned, with the weapon in hand and ammo left,
+	// and not faster than the weapon fires. If so, use up one shot and start cooling down.
+	fn try_fire(&mut self, player_id: ID, weapon: Weapon) -> bool {
+		let player = self.player(player_id);
+		if !player.spawned || weapon != player.weapon || !self.owns_weapon(player_id, weapon) {
+			self.log_rejected(player_id, format!("rejected shot by {}: no {} in hand", &player.name, weapon.as_str()));
+			return false;
+		}
+		if self.gun_cooldown.get(&player_id).copied().unwrap_or_default() > FIRE_TOLERANCE {
+			self.log_rejected(player_id, format!("rejected shot by {}: faster than the {} fires", &player.name, weapon.as_str()));
+			return false;
 		}
+
+		// cooldowns add up, so that network jitter does not allow for a higher fire rate on average.
+		let cooldown = player.gun_cooldown(&self.world);
+		*self.gun_cooldown.entry(player_id).or_default() += cooldown;
+		if let Some(ammo) = self.inventory.get_mut(&player_id) {
+			ammo[weapon.index()] = ammo[weapon.index()].saturating_sub(1);
+		}
+		true
+	}
+
+	// Use up one bullet of the player's last shot (see handle_fire), return the weapon that fired it.
+	// `None` if they have no bullets left to hit anything with.
+	fn take_bullet(&mut self, player_id: ID) -> Option<Weapon> {
+		if !self.player(player_id).spawned {
+			return None;
+		}
+		let (weapon, left) = self.bullets.get_mut(&player_id)?;
+		*left = left.checked_sub(1)?;
+		Some(*weapon)
+	}
+
+	// Move projectiles, explode those that ran into something or whose fuse ran out.
+	// Moving projectiles are re-sent every tick, clients extrapolate in between.
+	fn tick_projectiles(&mut self, dt: f32) {
+		for eid in self.entity_ids() {
+			let mut entity = match self.entity(eid) {
+				Some(entity) if entity.kind.is_projectile() => entity.clone(),
+				_ => continue,
+			};
+
+			let impact = entity.tick_projectile(&self.world, dt);
+			let fuse = self.fuses.get(&eid).copied().unwrap_or_default() - dt;
+			self.fuses.insert(eid, fuse);
+
+			if impact.is_some() || fuse <= 0.0 {
+				self.explode(entity);
+			} else {
+				self.record_add_entity(entity);
+			}
+		}
+	}
+
+	// Remove a projectile with a bang, damaging players nearby.
+	// Walls protect against splash damage.
+	fn explode(&mut self, projectile: Entity) {
+		let kind = projectile.kind;
+		let center = projectile.position;
+		self.record_remove_entity(projectile.id());
+		self.fuses.remove(&projectile.id());
+
+		self.record_add_effect(Effect::particle_explosion(center, kind.fallback_color()));
+		self.broadcast_sound_at(self.pick_random(&["bang1", "bang2", "bang3", "bang4"]), center, 60.0);
+		self.destroy_voxels(center, EXPLOSION_CRATER_RADIUS);
+
+		// owner may have left the game while their projectile was underway.
+		let owner = kind.owner().filter(|&id| self.world.players.contains(id));
+
+		for victim_id in self.player_ids() {
+			let victim_center = self.player(victim_id).center();
+			let dist = (victim_center - center).len();
+			if !self.player(victim_id).spawned || dist > kind.splash_radius() || self.world.is_obstructed(center, victim_center) {
+				continue;
+			}
+
+			let damage = match self.instagib {
+				true => Player::MAX_HEALTH,
+				false => (kind.splash_damage() as f32 * (1.0 - dist / kind.splash_radius())).ceil() as i32,
+			};
+
+			let damaged = !self.is_protected(victim_id, owner);
+			if self.try_damage_player(victim_id, owner, damage) {
+				match owner {
+					Some(owner) if owner != victim_id => self.record_kill(owner, victim_id, ""),
+					_ => {
+						self.increment_score(victim_id, -1);
+						self.hud_message(victim_id, "You blew yourself up".to_owned());
+						self.log(format!("{} blew themselves up", self.player(victim_id).name));
+					}
+				}
+			}
+
+			// rocket jumping: blasting yourself works too.
+			if damaged {
+				self.knock_back(victim_id, damage as f32 * KNOCKBACK * (victim_center - center).safe_normalized());
+			}
+		}
+	}
+
+	// Push a player away (if the game's rules allow, and they survived the hit).
+	// Only their client moves them, so we ask it to, and expect to see the impulse when re-playing their moves.
+	fn knock_back(&mut self, victim_id: ID, impulse: vec3) {
+		if !self.world.rules.knockback || !self.player(victim_id).spawned {
+			return;
+		}
+		self.knockback.entry(victim_id).or_default().push_back(impulse);
+		self.pending_diffs.push(Knockback(impulse).to_just(victim_id));
+	}
+
+	// Handle a client saying they took another weapon in hand.
+	// Only needed so that other players see the right gun, and damage is computed for the right weapon.
+	fn handle_switch_weapon(&mut self, player_id: ID, weapon: Weapon) {
+		if !self.owns_weapon(player_id, weapon) {
+			self.log_rejected(player_id, format!("rejected switch by {} to {} (not owned)", &self.player(player_id).name, weapon.as_str()));
+			return;
+		}
+		self.record_apply_to_player(player_id, |p| p.weapon = weapon);
+	}
+
+	// Was the player given this weapon (since their last death), with ammo left?
+	// (Like Player::owns, but using the ammo we handed out).
+	fn owns_weapon(&self, player_id: ID, weapon: Weapon) -> bool {
+		weapon.ammo().is_none() || self.inventory.get(&player_id).map(|ammo| ammo[weapon.index()] > 0).unwrap_or(false)
 	}
 
 	fn increment_score(&mut self, player_id: ID, delta: i32) {
 		*self.score.entry(player_id).or_default() += delta;
 
 		let team = self.player(player_id).team;
+		self.increment_team_score(team, delta);
+	}
 
-		match &mut self.gametype {
-			GameType::DeadMatch(_) => (),
-			GameType::TeamMatch(tm) => tm.team_score[team as usize] += delta,
+	// add `delta` to a team's score, if this is a team game.
+	fn increment_team_score(&mut self, team: Team, delta: i32) {
+		if let Some(team_score) = self.gametype.team_score_mut() {
+			team_score[team as usize] += delta;
 		}
 
 		self.broadcast_scores_mini();
@@ -322,30 +997,41 @@ impl ServerState {
 	}
 
 	fn format_scores_for(&self, player_id: ID) -> String {
-		use Team::*;
-		match &self.gametype {
-			GameType::DeadMatch(_) => format!("score: {}", self.score(player_id)),
-			GameType::TeamMatch(tm) => format!(
-				"Red: {} | Blu: {} | Green: {}\nYou: {}",
-				tm.team_score[Red as usize],
-				tm.team_score[Blue as usize],
-				tm.team_score[Green as usize],
-				self.score(player_id)
-			),
+		let scores = match self.gametype.team_score() {
+			None => format!("score: {}", self.score(player_id)),
+			Some(team_score) => format!("{}\nYou: {}", self.format_team_scores(team_score), self.score(player_id)),
+		};
+		match self.elimination() {
+			Some(el) => format!("Round {} (first to {} wins)\nWins: {}\n{}", el.round, ROUNDS_TO_WIN, self.format_wins(el, player_id), scores),
+			None => scores,
+		}
+	}
+
+	// Rounds won, e.g.: "Red 2 | Blu 1" (teams) or "1" (free-for-all, own wins).
+	fn format_wins(&self, el: &Elimination, player_id: ID) -> String {
+		match el.teams {
+			true => self.teams().map(|team| format!("{} {}", team.short_name(), el.wins(Side::Team(team)))).collect::<Vec<_>>().join(" | "),
+			false => el.wins(Side::Player(player_id)).to_string(),
 		}
 	}
 
+	// E.g.: "Red (3): 12 | Blu (2): 8",
+	// where the number in parentheses is the team size.
+	fn format_team_scores(&self, team_score: &[i32; NUM_TEAMS]) -> String {
+		let sizes = self.team_sizes();
+		self.teams()
+			.map(|team| format!("{} ({}): {}", team.short_name(), sizes[team as usize], team_score[team as usize]))
+			.collect::<Vec<_>>()
+			.join(" | ")
+	}
+
 	fn format_scoreboard(&self) -> String {
 		let mut s = String::new();
 
-		use Team::*;
-		s.push_str(&match &self.gametype {
-			GameType::DeadMatch(_) => String::new(),
-			GameType::TeamMatch(tm) => format!(
-				"Red: {} | Blu: {} | Green: {}\n",
-				tm.team_score[Red as usize], tm.team_score[Blue as usize], tm.team_score[Green as usize],
-			),
-		});
+		if let Some(team_score) = self.gametype.team_score() {
+			s.push_str(&self.format_team_scores(team_score));
+			s.push('\n');
+		}
 
 		let mut scores = self.score.iter().map(|(p, s)| (p.to_owned(), *s)).collect::<SmallVec<(ID, i32)>>();
 		scores.sort_by_key(|(_, score)| *score);
@@ -361,7 +1047,23 @@ impl ServerState {
 
 	// ____________________________________________________________________________ respawn
 
+	// Ask a player's client to respawn (when the player is ready) at a spawn point of our choosing.
+	fn request_respawn(&mut self, player_id: ID) -> SpawnPoint {
+		let spawn_point = self.pick_spawn_point(self.player(player_id).team);
+		let player = self.player_mut(player_id);
+		player.next_spawn_point = spawn_point.position();
+		player.next_spawn_orientation = spawn_point.orientation();
+		self.pending_diffs.push(RequestRespawn(spawn_point).to_just(player_id));
+		spawn_point
+	}
+
 	pub fn handle_ready_to_respawn(&mut self, player_id: ID) {
+		// elimination: no respawn until the next round.
+		// (clients don't ask while spectating, but may have raced the server).
+		if self.player(player_id).spectating {
+			return;
+		}
+
 		// levelling: best player gets no spawn protection
 		let spawn_protect = match (self.enable_levelling, self.has_best_score(player_id)) {
 			(true, true) => None,
@@ -369,9 +1071,21 @@ impl ServerState {
 		};
 		self.record_apply_to_player(player_id, |p| {
 			p.spawned = true;
+			p.health = Player::MAX_HEALTH;
 			p.invulnerability_ttl = spawn_protect // spawn kill protection
 		});
-		self.broadcast_sound_at("respawn", self.player(player_id).center(), 1.0)
+
+		// at the spawn point we handed out (the client moves there too, see Player::control).
+		let player = self.player(player_id);
+		let frame = Frame {
+			position: player.next_spawn_point,
+			velocity: vec3::ZERO,
+			orientation: player.next_spawn_orientation,
+			..player.skeleton.frame()
+		};
+		self.record_move_player(player_id, frame);
+		self.broadcast_sound_at("respawn", self.player(player_id).center(), 1.0);
+		self.check_round_start();
 	}
 
 	// ____________________________________________________________________________ effects
@@ -399,9 +1113,20 @@ impl ServerState {
 
 	pub fn handle_drop_player(&mut self, player_id: ID) {
 		self.log(format!("{} left", &self.player(player_id).name));
+		self.drop_flag(player_id);
 		self.world.players.remove(player_id);
 		self.score.remove(&player_id);
+		self.hazard_cooldown.remove(&player_id);
+		self.falls.remove(&player_id);
+		self.knockback.remove(&player_id);
+		self.inventory.remove(&player_id);
+		self.gun_cooldown.remove(&player_id);
+		self.bullets.remove(&player_id);
+		self.step_budget.remove(&player_id);
+		self.rejections.remove(&player_id);
 		self.pending_diffs.push(DropPlayer(player_id).to_not(player_id));
+		self.broadcast_scores_mini();
+		self.check_round_over();
 	}
 
 	// ____________________________________________________________________________ commands
@@ -426,6 +1151,7 @@ impl ServerState {
 		let args = &split[1..];
 		match cmd {
 			"summon" => self.summon(player_id, one_arg(args)?),
+			"team" => self.switch_team(player_id, one_arg(args)?.parse()?),
 			"switch" => self.switch_map(one_arg(args)?),
 			unknown => Err(anyhow!("unknown command: `{}`", unknown)),
 		}
@@ -435,20 +1161,37 @@ impl ServerState {
 		const SUMMON_DIST: f32 = 5.0;
 		let player = self.player(player_id);
 		let position = player.position() + SUMMON_DIST * player.orientation().look_dir_h();
-		Ok(self.record_add_entity(Entity::new(position, EKind::from_str(arg)?)))
+		let entity = self.new_entity(position, EKind::from_str(arg)?);
+		Ok(self.record_add_entity(entity))
 	}
 
 	fn switch_map(&mut self, arg: &str) -> Result<()> {
 		let i = self.maplist.iter().position(|name| name == arg).ok_or(anyhow!("`{}` not in map list", arg))?;
 
 		let mut world2 = World::from_map(&self.maplist[i], default(), default())?;
+		world2.rules = self.world.rules;
+		check_pickup_points(&world2)?;
+		self.fuses.clear();
+		self.voxel_edits.clear();
+		self.pickup_timers.clear();
+		self.pickup_entities.clear();
+		self.spawn_since_used.clear();
+		self.inventory.clear();
+		self.bullets.clear();
 
 		self.curr_map = i;
 		mem::swap(&mut world2.players, &mut self.world.players);
 		mem::swap(&mut self.world, &mut world2);
 
+		// before picking spawn points for the new teams.
+		self.balance_teams();
+
 		for player_id in self.player_ids() {
-			self.record_apply_to_player(player_id, |p| p.spawned = false);
+			self.record_apply_to_player(player_id, |p| {
+				p.spawned = false;
+				p.flag = None;
+				p.weapon = default();
+			});
 
 			self.pending_diffs.push(
 				ServerMsg::SwitchMap {
@@ -459,13 +1202,13 @@ impl ServerState {
 				}
 				.to_just(player_id),
 			);
+			self.pending_diffs.push(SetRules(self.world.rules).to_just(player_id));
 
 			// request respawn but also force player to move to the respawn point immediately.
 			// (normally when we request a respawn the player stays at their death location
 			// so they can see who killed them. But when switching maps, that location could be out of the world).
-			let spawn_point = self.pick_spawn_point();
-			self.pending_diffs.push(ForceMovePlayer(spawn_point.position()).to_just(player_id));
-			self.pending_diffs.push(RequestRespawn(spawn_point).to_just(player_id));
+			let spawn_point = self.request_respawn(player_id);
+			self.pending_diffs.push(ForceMovePlayer(spawn_point.position(), spawn_point.orientation()).to_just(player_id));
 		}
 
 		self.broadcast_scoreboard();
@@ -473,10 +1216,403 @@ impl ServerState {
 		// TODO: reset GameType state.
 
 		self.populate_all_pickups();
+		self.populate_flags();
+		self.reset_zones();
 
 		Ok(())
 	}
 
+	// ________________________________________________________________________ capture the flag
+
+	// place every team's flag at its base
+	// (called when a map first loads).
+	fn populate_flags(&mut self) {
+		let ctf = match &mut self.gametype {
+			GameType::CaptureTheFlag(ctf) => ctf,
+			_ => return,
+		};
+		ctf.flags = default();
+
+		for base in self.world.map.metadata.flag_bases.clone() {
+			self.return_flag(base.team);
+		}
+	}
+
+	// dropped flags return to their base by themselves after a while,
+	// so that flags dropped in lava or off the world don't stay lost.
+	fn tick_flags(&mut self, dt: f32) {
+		for team in Team::ALL {
+			if let Some(FlagState::Dropped(eid, ttl)) = self.flag_state(team) {
+				let ttl = ttl - dt;
+				if ttl > 0.0 {
+					self.set_flag_state(team, FlagState::Dropped(eid, ttl));
+				} else {
+					self.record_remove_entity(eid);
+					self.return_flag(team);
+					self.announce(format!("The {} flag returned to base", team));
+				}
+			}
+		}
+	}
+
+	// A player touches a flag entity:
+	//  - an enemy flag gets picked up,
+	//  - their own dropped flag gets returned to base,
+	//  - their own flag at base scores a capture if they are carrying an enemy flag.
+	fn touch_flag(&mut self, player_id: ID, eid: EID, flag_team: Team) {
+		let player_team = self.player(player_id).team;
+
+		if flag_team != player_team {
+			if self.player(player_id).flag.is_some() {
+				return; // can carry only one flag at a time.
+			}
+			self.record_remove_entity(eid);
+			self.record_apply_to_player(player_id, |p| p.flag = Some(flag_team));
+			self.set_flag_state(flag_team, FlagState::Carried(player_id));
+			self.broadcast_sound_at("protect", self.player(player_id).position(), 1.0);
+			self.hud_message(player_id, format!("You have the {} flag\n[{}]", flag_team, EKind::Flag { team: flag_team }.description()));
+			self.log(format!("{} took the {} flag", &self.player(player_id).name, flag_team));
+			return;
+		}
+
+		match self.flag_state(flag_team) {
+			Some(FlagState::Dropped(..)) => {
+				self.record_remove_entity(eid);
+				self.return_flag(flag_team);
+				self.increment_score(player_id, FLAG_RETURN_SCORE);
+				self.broadcast_sound_at("respawn", self.player(player_id).position(), 1.0);
+				self.announce(format!("{} returned the {} flag", &self.player(player_id).name, flag_team));
+			}
+			Some(FlagState::AtBase(_)) => {
+				if let Some(enemy_team) = self.player(player_id).flag {
+					self.record_apply_to_player(player_id, |p| p.flag = None);
+					self.return_flag(enemy_team);
+					self.increment_score(player_id, FLAG_CAPTURE_SCORE);
+					self.broadcast_sound_at("kill", self.player(player_id).position(), 3.0);
+					self.announce(format!("{} captured the {} flag!", &self.player(player_id).name, enemy_team));
+				}
+			}
+			_ => (),
+		}
+	}
+
+	// A flag carrier died or left: drop the flag where they are.
+	fn drop_flag(&mut self, player_id: ID) {
+		if let Some(team) = self.player(player_id).flag {
+			let entity = self.new_entity(self.player(player_id).position(), EKind::Flag { team });
+			let eid = entity.id();
+			self.record_apply_to_player(player_id, |p| p.flag = None);
+			self.record_add_entity(entity);
+			self.set_flag_state(team, FlagState::Dropped(eid, FLAG_RETURN_TTL));
+			self.log(format!("{} dropped the {} flag", &self.player(player_id).name, team));
+		}
+	}
+
+	// place a team's flag back at its base.
+	fn return_flag(&mut self, team: Team) {
+		let base = match self.world.map.metadata.flag_bases.iter().find(|b| b.team == team) {
+			Some(base) => *base,
+			None => return,
+		};
+		let entity = self.new_entity(base.position(), EKind::Flag { team });
+		let eid = entity.id();
+		self.record_add_entity(entity);
+		self.set_flag_state(team, FlagState::AtBase(eid));
+	}
+
+	fn flag_state(&self, team: Team) -> Option<FlagState> {
+		match &self.gametype {
+			GameType::CaptureTheFlag(ctf) => ctf.flags[team as usize],
+			_ => None,
+		}
+	}
+
+	fn set_flag_state(&mut self, team: Team, state: FlagState) {
+		if let GameType::CaptureTheFlag(ctf) = &mut self.gametype {
+			ctf.flags[team as usize] = Some(state)
+		}
+	}
+
+	// ________________________________________________________________________ king of the hill
+
+	// all control zones start out neutral
+	// (called when a map first loads).
+	fn reset_zones(&mut self) {
+		if let GameType::KingOfTheHill(koth) = &mut self.gametype {
+			koth.zones = vec![ZoneOwner::Nobody; self.world.map.metadata.control_zones.len()];
+			koth.score_timer = 0.0;
+			self.pending_diffs.push(UpdateZones(koth.zones.clone()).to_all());
+		}
+	}
+
+	// Update who holds each control zone, broadcast changes,
+	// and periodically award a point per zone to the side (team or player) holding it,
+	// regardless of how many of them are inside.
+	fn tick_zones(&mut self, dt: f32) {
+		let teams = match &self.gametype {
+			GameType::KingOfTheHill(koth) => koth.teams,
+			_ => return,
+		};
+
+		let owners = self
+			.world
+			.map
+			.metadata
+			.control_zones
+			.iter()
+			.map(|zone| ZoneOwner::from_occupants(self.zone_occupants(zone).into_iter().map(|id| self.side_of(id, teams))))
+			.collect::<Vec<_>>();
+
+		let koth = match &mut self.gametype {
+			GameType::KingOfTheHill(koth) => koth,
+			_ => unreachable!(),
+		};
+
+		if koth.zones != owners {
+			koth.zones = owners.clone();
+			self.pending_diffs.push(UpdateZones(owners.clone()).to_all());
+		}
+
+		koth.score_timer += dt;
+		if koth.score_timer < ZONE_SCORE_INTERVAL {
+			return;
+		}
+		koth.score_timer -= ZONE_SCORE_INTERVAL;
+
+		for owner in owners {
+			match owner {
+				ZoneOwner::Team(team) => self.increment_team_score(team, 1),
+				ZoneOwner::Player(player_id) => self.increment_score(player_id, 1),
+				ZoneOwner::Nobody | ZoneOwner::Contested => (),
+			}
+		}
+	}
+
+	// IDs of spawned players standing inside a control zone.
+	fn zone_occupants(&self, zone: &ControlZone) -> SmallVec<ID> {
+		let bounds = zone.bounds();
+		self.world.players.iter().filter(|(_, p)| p.spawned && bounds.contains(p.center())).map(|(id, _)| id).collect()
+	}
+
+	// ________________________________________________________________________ elimination rounds
+
+	fn elimination(&self) -> Option<&Elimination> {
+		match &self.gametype {
+			GameType::Elimination(el) => Some(el),
+			_ => None,
+		}
+	}
+
+	fn elimination_mut(&mut self) -> Option<&mut Elimination> {
+		match &mut self.gametype {
+			GameType::Elimination(el) => Some(el),
+			_ => None,
+		}
+	}
+
+	// Are dead players currently eliminated (rather than respawning)?
+	fn round_in_progress(&self) -> bool {
+		self.elimination().map(|el| el.in_progress).unwrap_or(false)
+	}
+
+	// The side a player is on: their team in team games, or just themselves.
+	fn side_of(&self, player_id: ID, teams: bool) -> Side {
+		match teams {
+			true => Side::Team(self.player(player_id).team),
+			false => Side::Player(player_id),
+		}
+	}
+
+	// The sides (teams or players) that still have someone standing.
+	fn surviving_sides(&self) -> SmallVec<Side> {
+		let teams = self.elimination().map(|el| el.teams).unwrap_or(false);
+		let mut sides = SmallVec::new();
+		for (id, player) in self.world.players.iter() {
+			if player.spawned && !player.spectating {
+				let side = self.side_of(id, teams);
+				if !sides.contains(&side) {
+					sides.push(side);
+				}
+			}
+		}
+		sides
+	}
+
+	// After a player spawned: a round starts as soon as two opponents are alive.
+	fn check_round_start(&mut self) {
+		let n = self.surviving_sides().len();
+		if let Some(el) = self.elimination_mut() {
+			if !el.in_progress && el.next_round_ttl.is_none() && n >= 2 {
+				el.in_progress = true;
+			}
+		}
+	}
+
+	// After a player died or left: the round is over when only one side is left standing.
+	fn check_round_over(&mut self) {
+		if !self.round_in_progress() {
+			return;
+		}
+
+		let sides = self.surviving_sides();
+		if sides.len() > 1 {
+			return;
+		}
+
+		let el = self.elimination_mut().unwrap();
+		el.in_progress = false;
+		el.next_round_ttl = Some(ROUND_END_DELAY);
+		let round = el.round;
+
+		let winner = match sides.first().copied() {
+			None => return self.announce(format!("Round {} is a draw", round)),
+			Some(side) => side,
+		};
+		let wins = self.elimination_mut().unwrap().add_win(winner);
+		let name = match winner {
+			Side::Team(team) => format!("{} team", team),
+			Side::Player(player_id) => {
+				self.increment_score(player_id, ROUND_WIN_SCORE);
+				self.player(player_id).name.clone()
+			}
+		};
+		self.broadcast_scores_mini();
+		if wins < ROUNDS_TO_WIN {
+			self.announce(format!("{} wins round {}", name, round));
+		} else {
+			self.end_match(format!("{} wins round {} and the match!", name, round));
+		}
+	}
+
+	// Someone won enough rounds: announce the final scores, and start over from round 1
+	// (after the usual delay).
+	fn end_match(&mut self, announcement: String) {
+		self.announce(format!("{}\n{}", announcement, self.format_scoreboard()));
+		self.score = default();
+		if let Some(el) = self.elimination_mut() {
+			*el = Elimination {
+				round: 0, // incremented when the next round starts
+				next_round_ttl: el.next_round_ttl,
+				..Elimination::new(el.teams)
+			};
+		}
+		self.broadcast_scores_mini();
+	}
+
+	// After a round has been won: wait a bit, then start the next one.
+	fn tick_rounds(&mut self, dt: f32) {
+		if let Some(el) = self.elimination_mut() {
+			if let Some(ttl) = el.next_round_ttl {
+				let ttl = ttl - dt;
+				if ttl > 0.0 {
+					el.next_round_ttl = Some(ttl);
+				} else {
+					el.next_round_ttl = None;
+					el.round += 1;
+					self.start_round();
+				}
+			}
+		}
+	}
+
+	// Everyone (survivors and spectators alike) respawns together.
+	fn start_round(&mut self) {
+		self.balance_teams();
+		for player_id in self.player_ids() {
+			self.record_apply_to_player(player_id, |p| {
+				p.spawned = false;
+				p.spectating = false;
+			});
+			self.request_respawn(player_id);
+		}
+
+		let round = self.elimination().map(|el| el.round).unwrap_or_default();
+		self.announce(format!("Round {}", round));
+		self.broadcast_scores_mini();
+	}
+
+	// ________________________________________________________________________ teams
+
+	// The teams in play.
+	fn teams(&self) -> impl Iterator<Item = Team> {
+		Team::ALL.into_iter().take(self.num_teams)
+	}
+
+	// Number of players per team.
+	fn team_sizes(&self) -> [usize; NUM_TEAMS] {
+		let mut sizes = [0; NUM_TEAMS];
+		for (_, player) in self.world.players.iter() {
+			sizes[player.team as usize] += 1;
+		}
+		sizes
+	}
+
+	// Team for a newly joined player: the smallest team,
+	// or their preferred team if that is (one of) the smallest.
+	fn assign_team(&self, preferred: Option<Team>) -> Team {
+		let sizes = self.team_sizes();
+		let smallest = self.teams().map(|t| sizes[t as usize]).min().unwrap_or_default();
+		match preferred {
+			Some(team) if self.teams().any(|t| t == team) && sizes[team as usize] == smallest => team,
+			_ => self.teams().find(|&t| sizes[t as usize] == smallest).unwrap_or(Team::Red),
+		}
+	}
+
+	// Handle the `team <color>` command:
+	// switch teams, but only if that does not unbalance them.
+	fn switch_team(&mut self, player_id: ID, team: Team) -> Result<()> {
+		let old = self.player(player_id).team;
+		if !self.gametype.is_team() {
+			return Err(anyhow!("teams are not in play in this game type"));
+		}
+		if !self.teams().any(|t| t == team) {
+			return Err(anyhow!("the {} team is not in play", team));
+		}
+		if team == old {
+			return Err(anyhow!("you are already on the {} team", team));
+		}
+		let sizes = self.team_sizes();
+		if sizes[team as usize] >= sizes[old as usize] {
+			return Err(anyhow!("switching to the {} team would unbalance teams", team));
+		}
+
+		// A living player dies first, so that nobody respawns on the wrong side.
+		if self.player(player_id).spawned {
+			self.kill_player(player_id);
+		}
+		self.move_to_team(player_id, team);
+		self.log(format!("{} switched to the {} team", &self.player(player_id).name, team));
+		Ok(())
+	}
+
+	// Between rounds: move players from the largest to the smallest team
+	// until team sizes differ by at most one.
+	// Must be called right before everybody respawns: players are not killed (no death effects, no dropped powerups).
+	fn balance_teams(&mut self) {
+		if !self.gametype.is_team() {
+			return;
+		}
+		loop {
+			let sizes = self.team_sizes();
+			let largest = self.teams().max_by_key(|&t| sizes[t as usize]).unwrap();
+			let smallest = self.teams().min_by_key(|&t| sizes[t as usize]).unwrap();
+			if sizes[largest as usize] <= sizes[smallest as usize] + 1 {
+				return;
+			}
+			// move the most recently joined player.
+			let player_id = self.player_ids().filter(|&id| self.player(id).team == largest).max().unwrap();
+			self.move_to_team(player_id, smallest);
+			self.hud_message(player_id, format!("Teams were balanced\nYou are now on the {} team", smallest));
+			self.log(format!("{} was moved to the {} team", &self.player(player_id).name, smallest));
+		}
+	}
+
+	// Unconditionally change a player's team.
+	fn move_to_team(&mut self, player_id: ID, team: Team) {
+		self.record_apply_to_player(player_id, |p| p.team = team);
+		self.broadcast_scores_mini();
+	}
+
 	// ________________________________________________________________________ HUD
 
 	// Send a message to be shown in the center of one player's screen.
@@ -485,6 +1621,13 @@ impl ServerState {
 		self.pending_diffs.push(UpdateHUD(HUDUpdate::Message(message)).to_just(player_id));
 	}
 
+	// Send a message to be shown in the center of everyone's screen,
+	// and in the logs. E.g.: "A captured the Red flag!".
+	fn announce(&mut self, message: String) {
+		println!("{}", &message);
+		self.pending_diffs.push(UpdateHUD(HUDUpdate::Message(message)).to_all());
+	}
+
 	// Send a message to shown in the logs of all players.
 	// E.g. "A killed B".
 	pub fn log(&mut self, message: String) {
@@ -492,6 +1635,16 @@ impl ServerState {
 		self.pending_diffs.push(UpdateHUD(HUDUpdate::Log(message)).to_all());
 	}
 
+	// Log a rejected or corrected client message (server-side only).
+	// A lagging or cheating client may cause many of these, so only the 1st, 2nd, 4th, 8th, ... are printed.
+	fn log_rejected(&mut self, player_id: ID, message: String) {
+		let count = self.rejections.entry(player_id).or_default();
+		*count += 1;
+		if count.is_power_of_two() {
+			println!("{} (#{} by this player)", message, count);
+		}
+	}
+
 	// ________________________________________________________________________ mutators
 
 	// add an entity to the world and record as pending diff
@@ -506,6 +1659,67 @@ impl ServerState {
 		self.pending_diffs.push(RemoveEntity(eid).to_all());
 	}
 
+	// Remove all destructible voxels within `radius` of `center`.
+	// Changes are sent as one SetVoxels per run of voxels along x.
+	// Returns true if anything was destroyed.
+	fn destroy_voxels(&mut self, center: vec3, radius: f32) -> bool {
+		if self.destructible.is_empty() {
+			return false;
+		}
+
+		let r = radius.ceil() as i32;
+		let c = center.map(|v| v.floor() as i32);
+		let in_crater = |pos: ivec3| (pos.to_f32() + vec3(0.5, 0.5, 0.5) - center).len() <= radius;
+
+		let mut runs = vec![];
+		for z in (c.z() - r)..=(c.z() + r) {
+			for y in (c.y() - r)..=(c.y() + r) {
+				let mut start = None;
+				for x in (c.x() - r)..=(c.x() + r + 1) {
+					let pos = ivec3(x, y, z);
+					let destroy = in_crater(pos) && self.destructible.contains(&self.world.map.voxels.at(pos));
+					match (start, destroy) {
+						(None, true) => start = Some(x),
+						(Some(x0), false) => {
+							runs.push(Cuboid::new(ivec3(x0, y, z), ivec3(x, y + 1, z + 1)));
+							start = None;
+						}
+						_ => (),
+					}
+				}
+			}
+		}
+
+		let destroyed = !runs.is_empty();
+		for range in runs {
+			self.record_set_voxels(range, VoxelType::EMPTY);
+		}
+		destroyed
+	}
+
+	fn record_set_voxels(&mut self, range: Cuboid, value: VoxelType) {
+		self.world.map.voxels.set_range(&range, value);
+		self.voxel_edits.extend(range.iter_by(1));
+		self.pending_diffs.push(SetVoxels { range, value }.to_all());
+	}
+
+	// Current value of all voxels changed since the map was loaded,
+	// as runs of equal voxels along x.
+	fn changed_voxels(&self) -> Vec<(Cuboid, VoxelType)> {
+		let mut positions = self.voxel_edits.iter().copied().collect::<Vec<_>>();
+		positions.sort_by_key(|pos| (pos.z(), pos.y(), pos.x()));
+
+		let mut runs: Vec<(Cuboid, VoxelType)> = vec![];
+		for pos in positions {
+			let value = self.world.map.voxels.at(pos);
+			match runs.last_mut() {
+				Some((range, v)) if *v == value && range.max == pos + ivec3(0, 1, 1) => range.max = pos + ivec3(1, 1, 1),
+				_ => runs.push((Cuboid::new(pos, pos + ivec3(1, 1, 1)), value)),
+			}
+		}
+		runs
+	}
+
 	// move a player and record as pending diff
 	fn record_move_player(&mut self, player_id: ID, frame: Frame) {
 		self.player_mut(player_id).skeleton.set_frame(frame);
@@ -518,34 +1732,45 @@ impl ServerState {
 		self.pending_diffs.push(UpdatePlayer(self.player(player_id).clone()).to_all());
 	}
 
-	// Try to kill a player, return true if successful.
+	// Try to inflict `damage` on a player, return true if this killed them.
 	#[must_use]
-	fn try_kill_player(&mut self, victim_id: ID, aggressor_id: Option<ID>) -> bool {
-		// temporarily invulnerable.
-		if self.player(victim_id).invulnerability_ttl.is_some() {
-			println!("{} invulnerable", self.player(victim_id).name);
-			if aggressor_id.is_some() {
-				self.record_add_effect(Effect::ricochet(self.player(victim_id).center(), self.player(victim_id).team.color_filter()));
+	fn try_damage_player(&mut self, victim_id: ID, aggressor_id: Option<ID>, damage: i32) -> bool {
+		if self.is_protected(victim_id, aggressor_id) {
+			if self.player(victim_id).invulnerability_ttl.is_some() {
+				println!("{} invulnerable", self.player(victim_id).name);
+				if aggressor_id.is_some() {
+					self.record_add_effect(self.ricochet(victim_id));
+				}
 			}
 			return false;
 		}
 
-		// same team.
-		// TODO: team should only be accessibly in team match.
-		if let Some(aggressor_id) = aggressor_id {
-			if self.gametype.is_team() && self.player(aggressor_id).team == self.player(victim_id).team {
-				return false;
-			}
+		// survived the hit: health regenerates only after a while.
+		let health = self.player(victim_id).health - damage;
+		if health > 0 {
+			self.record_apply_to_player(victim_id, |p| {
+				p.health = health;
+				p.regen_ttl = REGEN_DELAY;
+			});
+			self.record_add_effect(self.ricochet(victim_id));
+			let clip = self.pick_random(&["ricochet1", "ricochet2", "ricochet3", "ricochet4"]);
+			self.broadcast_sound_at(clip, self.player(victim_id).center(), 1.0);
+			return false;
 		}
 
-		// Party hat powerup grants immunity against one shot.
-		if self.player(victim_id).powerup == Some(EKind::PartyHat) {
+		// Some powerups (e.g. party hat) grant extra lives against otherwise deadly hits.
+		if self.player(victim_id).extra_lives > 0 {
+			let powerup_name = self.player(victim_id).powerup.map(|p| p.as_str().replace("_", " ")).unwrap_or_default();
 			self.record_add_effect(Effect::particle_explosion(self.player(victim_id).camera().position, WHITE));
 			self.record_apply_to_player(victim_id, |p| {
-				p.powerup = None;
+				p.extra_lives -= 1;
+				if p.extra_lives == 0 {
+					p.powerup = None;
+					p.powerup_ttl = None;
+				}
 				p.invulnerability_ttl = Some(DEFAULT_INVULN_TTL)
 			});
-			self.log(format!("{} was saved by their party hat", self.player(victim_id).name));
+			self.log(format!("{} was saved by their {}", self.player(victim_id).name, powerup_name));
 			self.broadcast_sound_at("protect", self.player(victim_id).center(), 1.0);
 			return false;
 		}
@@ -554,25 +1779,54 @@ impl ServerState {
 		true
 	}
 
+	// Whether `try_damage_player` would refuse to hurt the victim:
+	// temporarily invulnerable, or hit by a team mate.
+	// TODO: team should only be accessibly in team match.
+	fn is_protected(&self, victim_id: ID, aggressor_id: Option<ID>) -> bool {
+		if self.player(victim_id).invulnerability_ttl.is_some() {
+			return true;
+		}
+		match aggressor_id {
+			Some(aggressor_id) => self.gametype.is_team() && self.player(aggressor_id).team == self.player(victim_id).team,
+			None => false,
+		}
+	}
+
 	// unconditionally kill a player
 	fn kill_player(&mut self, player_id: ID) {
 		// drop powerup:
 		//  - always drop gift boxes
 		//  - hats are dropped by chance (to avoid hats staying around forever).
 		if let Some(powerup) = self.player(player_id).powerup {
-			if powerup.is_kind(&EKind::GiftBox { pickup_point_id: None }) || by_chance(self.hat_drop_chance(player_id)) {
-				self.record_add_entity(Entity::new(self.player(player_id).position(), powerup));
+			if powerup.is_kind(&EKind::GiftBox { pickup_point_id: None }) || self.by_chance(self.hat_drop_chance(player_id)) {
+				let entity = self.new_entity(self.player(player_id).position(), powerup);
+				self.record_add_entity(entity);
 			}
 		}
 
+		self.drop_flag(player_id);
+
+		// weapons are lost on death (clients also clear their ammo on RequestRespawn).
+		self.inventory.remove(&player_id);
+		self.bullets.remove(&player_id);
 		self.record_apply_to_player(player_id, |p| {
 			p.powerup = None;
+			p.extra_lives = 0;
+			p.powerup_ttl = None;
+			p.weapon = default();
+			p.health = 0;
 			p.spawned = false;
 		});
 
 		self.record_add_effect(Effect::particle_explosion(self.player(player_id).center(), WHITE));
 
-		self.pending_diffs.push(RequestRespawn(self.pick_spawn_point()).to_just(player_id));
+		if self.round_in_progress() {
+			// elimination: spectate until the next round.
+			self.record_apply_to_player(player_id, |p| p.spectating = true);
+			self.check_round_over();
+		} else {
+			self.request_respawn(player_id);
+		}
 	}
 
 	// levelling: best player is much more likely to drop their hat on death.
@@ -625,6 +1879,13 @@ impl ServerState {
 		self.score.values().copied().min().unwrap_or(0)
 	}
 
+	// A new entity with a fresh, unique ID.
+	fn new_entity(&mut self, position: vec3, kind: EKind) -> Entity {
+		let id = self.next_entity_id;
+		self.next_entity_id += 1;
+		Entity::new(id, position, kind)
+	}
+
 	// A fresh, unique player number.
 	fn new_player_id(&mut self) -> ID {
 		let id = self.next_player_id;
@@ -632,13 +1893,30 @@ impl ServerState {
 		id
 	}
 
-	fn pick_spawn_point(&self) -> SpawnPoint {
-		let n = self.world.map.metadata.spawn_points.len();
-		if n == 0 {
+	// Where a player on `team` should (re)spawn:
+	// away from living enemies and not where someone else just spawned.
+	fn pick_spawn_point(&mut self, team: Team) -> SpawnPoint {
+		let points = &self.world.map.metadata.spawn_points;
+		if points.is_empty() {
 			panic!("map has no spawn points")
 		}
-		let i = self.rng.borrow_mut().gen_range(0..n);
-		self.world.map.metadata.spawn_points[i]
+		self.spawn_since_used.resize(points.len(), f32::INFINITY);
+
+		let team_game = self.gametype.is_team();
+		let living = || self.world.players.iter().map(|(_, p)| p).filter(|p| p.spawned && !p.spectating);
+		let enemies = living().filter(|p| !team_game || p.team != team).map(|p| p.camera().position).collect::<SmallVec<_>>();
+		let friends = living().filter(|p| team_game && p.team == team).map(|p| p.position()).collect::<SmallVec<_>>();
+		let ctx = SpawnContext {
+			voxels: &self.world.map.voxels,
+			team: team_game.then_some(team),
+			enemies: &enemies,
+			friends: &friends,
+			since_used: &self.spawn_since_used,
+		};
+
+		let i = safest_spawn_point(&mut *self.rng.borrow_mut(), points, &ctx).unwrap();
+		self.spawn_since_used[i] = 0.0;
+		points[i]
 	}
 
 	pub fn map_name(&self) -> &str {
@@ -651,23 +1929,796 @@ impl ServerState {
 
 	// A copy of the current entity IDs.
 	// (Copy allows mutation while iterating).
+	// In increasing order, so that handling each entity in turn gives the same result every run
+	// (e.g. explosions draw random numbers, see ServerOpts::seed).
 	fn entity_ids(&self) -> impl Iterator<Item = EID> {
-		self.world.entities.keys().copied().collect::<SmallVec<_>>().into_iter()
+		let mut ids = self.world.entities.keys().copied().collect::<SmallVec<_>>();
+		ids.sort_unstable();
+		ids.into_iter()
 	}
 
 	fn player(&self, player_id: ID) -> &Player {
 		&self.world.players[player_id]
 	}
 
+	// In increasing order, like entity_ids.
 	fn player_ids(&self) -> impl Iterator<Item = ID> {
-		self.world.players.copied_ids()
+		let mut ids = self.world.players.ids().collect::<SmallVec<_>>();
+		ids.sort_unstable();
+		ids.into_iter()
 	}
 
 	fn player_mut(&mut self, player_id: ID) -> &mut Player {
 		&mut self.world.players[player_id]
 	}
+
+	// ________________________________________________________________________ randomness
+
+	// True with probability `probability`.
+	fn by_chance(&self, probability: f32) -> bool {
+		self.rng.borrow_mut().gen::<f32>() < probability
+	}
+
+	fn pick_random<'a>(&self, options: &[&'a str]) -> &'a str {
+		options[self.rng.borrow_mut().gen_range(0..options.len())]
+	}
+
+	// Particles bouncing off a player who was hit but not killed.
+	fn ricochet(&self, player_id: ID) -> Effect {
+		let player = self.player(player_id);
+		Effect::ricochet(&mut *self.rng.borrow_mut(), player.center(), player.team.color_filter())
+	}
 }
 
-fn by_chance(probabilty: f32) -> bool {
-	rand::thread_rng().gen::<f32>() < probabilty
+// Fail early on a map with an invalid pickup item table, rather than when the item spawns.
+fn check_pickup_points(world: &World) -> Result<()> {
+	world
+		.map
+		.metadata
+		.pickup_points
+		.iter()
+		.try_for_each(PickupPoint::validate)
+		.map_err(|err| anyhow!("map {}: {}", world.map.name, err))
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn test_opts(seed: u64) -> ServerOpts {
+		ServerOpts {
+			game_type: "dm".into(),
+			num_teams: 2,
+			instagib: false,
+			destructible: vec![],
+			lethal_falls: false,
+			player_collision: None,
+			knockback: None,
+			seed: Some(seed),
+			addr: default(),
+			maplist: vec![concat!(env!("CARGO_MANIFEST_DIR"), "/../assets/maps/bacon").into()],
+		}
+	}
+
+	fn test_server(seed: u64) -> ServerState {
+		ServerState::new(test_opts(seed)).unwrap()
+	}
+
+	fn join(server: &mut ServerState, name: &str) -> ID {
+		server
+			.join_new_player(JoinMsg {
+				name: name.into(),
+				avatar_id: 0,
+				team: None,
+				assets_hash: JoinMsg::assets_hash(),
+			})
+			.unwrap()
+	}
+
+	// Fire the weapon in hand (as soon as the gun has cooled down).
+	fn fire(server: &mut ServerState, player_id: ID) {
+		server.gun_cooldown.remove(&player_id);
+		server.handle_client_msg(player_id, ClientMsg::Fire(server.player(player_id).weapon));
+	}
+
+	// Fire, hitting `victim_id`.
+	fn shoot(server: &mut ServerState, player_id: ID, victim_id: ID, location: HitLocation) {
+		fire(server, player_id);
+		server.handle_client_msg(player_id, ClientMsg::HitPlayer(victim_id, location));
+	}
+
+	// Fire, hitting the voxel at `pos`.
+	fn shoot_voxel(server: &mut ServerState, player_id: ID, pos: ivec3) {
+		fire(server, player_id);
+		server.handle_client_msg(player_id, ClientMsg::HitVoxel(pos));
+	}
+
+	// A short match, returning everything the server sent (serialized).
+	fn play_match(seed: u64) -> Vec<Vec<u8>> {
+		let mut server = test_server(seed);
+		let alice = join(&mut server, "alice");
+		let bob = join(&mut server, "bob");
+		server.handle_client_msg(alice, ClientMsg::ReadyToSpawn);
+		server.handle_client_msg(bob, ClientMsg::ReadyToSpawn);
+		for i in 0..1000 {
+			server.handle_tick(0.1);
+			shoot(&mut server, alice, bob, HitLocation::Body);
+			server.handle_client_msg(bob, ClientMsg::ReadyToSpawn);
+			// now and then, two rockets go off in the same tick.
+			if i % 50 == 0 {
+				for player_id in [alice, bob] {
+					let position = server.player(player_id).center() + vec3(0.0, 3.0, 0.0);
+					launch(&mut server, player_id, position);
+				}
+			}
+		}
+		server.pending_diffs.iter().map(|envelope| bincode::serialize(&envelope.msg).unwrap()).collect()
+	}
+
+	// A rocket hanging still at `position` until its fuse runs out.
+	fn launch(server: &mut ServerState, owner: ID, position: vec3) -> EID {
+		let kind = EKind::Rocket { owner };
+		let entity = server.new_entity(position, kind);
+		let eid = entity.id();
+		server.fuses.insert(eid, kind.fuse());
+		server.record_add_entity(entity);
+		eid
+	}
+
+	#[test]
+	fn rocket_into_water() {
+		let mut server = test_server(1);
+		let alice = join(&mut server, "alice");
+		let pool = Cuboid::new(ivec3(0, 200, 0), ivec3(16, 208, 16));
+		server.world.map.voxels.set_range(&pool, VoxelType::WATER);
+
+		// a rocket fired straight down into the water dives in, rather than exploding on the surface.
+		let eid = launch(&mut server, alice, vec3(8.0, 210.0, 8.0));
+		server.world.entities.get_mut(&eid).unwrap().velocity = vec3(0.0, -20.0, 0.0);
+		for _ in 0..5 {
+			server.tick_projectiles(0.1);
+		}
+		let rocket = server.entity(eid).expect("rocket exploded");
+		assert!(rocket.position.y() < 201.0);
+	}
+
+	#[test]
+	fn replay_is_exact() {
+		assert_eq!(play_match(1), play_match(1));
+	}
+
+	#[test]
+	fn explosion_order() {
+		// projectiles going off in the same tick explode in order of entity ID,
+		// regardless of how the hash map happens to order them.
+		let mut server = test_server(1);
+		let alice = join(&mut server, "alice");
+		server.handle_client_msg(alice, ClientMsg::ReadyToSpawn);
+		let rockets = (0..20).map(|i| launch(&mut server, alice, vec3(i as f32, 250.0, 0.0))).collect::<Vec<_>>();
+		server.pending_diffs.clear();
+		server.handle_tick(EKind::Rocket { owner: alice }.fuse());
+		let removed = server
+			.pending_diffs
+			.iter()
+			.filter_map(|e| match e.msg {
+				RemoveEntity(eid) => Some(eid),
+				_ => None,
+			})
+			.collect::<Vec<_>>();
+		assert_eq!(removed, rockets);
+	}
+
+	#[test]
+	fn join_asset_mismatch() {
+		let mut server = test_server(1);
+		let refused = server.join_new_player(JoinMsg {
+			name: "mallory".into(),
+			avatar_id: 0,
+			team: None,
+			assets_hash: JoinMsg::assets_hash() ^ 1,
+		});
+		assert!(refused.is_err());
+		assert_eq!(server.world.players.ids().count(), 0);
+	}
+
+	#[test]
+	fn teleport_validation() {
+		let mut server = test_server(1);
+		let alice = join(&mut server, "alice");
+		server.handle_client_msg(alice, ClientMsg::ReadyToSpawn);
+		let start = server.player(alice).position();
+		let far = start + vec3(100.0, 0.0, 0.0);
+		let frame = Frame {
+			position: far,
+			..server.player(alice).skeleton.frame()
+		};
+
+		// moving without taking steps: corrected.
+		server.handle_client_msg(alice, ClientMsg::MovePlayer(frame, vec![]));
+		assert_eq!(server.player(alice).position(), start);
+		assert!(server.pending_diffs.iter().any(|e| matches!(&e.msg, CorrectMove(frame, _) if frame.position == start)));
+
+		// teleporter at our feet: the client teleports during a step, the server re-plays it.
+		let min = start.map(|v| v as i32) - ivec3(2, 0, 2);
+		let action = TriggerAction::Teleport {
+			destination: far,
+			orientation: default(),
+		};
+		server.world.map.metadata.triggers.push(Trigger {
+			min,
+			max: min + ivec3(4, 1, 4),
+			action,
+		});
+		let mut client = server.player(alice).clone();
+		let input = MoveInput::default();
+		client.step(&mut vec![], &input, &server.world);
+		assert_eq!(client.position(), far);
+		server.handle_client_msg(alice, ClientMsg::MovePlayer(client.skeleton.frame(), vec![input]));
+		assert_eq!(server.player(alice).position(), far);
+	}
+
+	#[test]
+	fn move_validation() {
+		let mut server = test_server(1);
+		let alice = join(&mut server, "alice");
+		server.handle_client_msg(alice, ClientMsg::ReadyToSpawn);
+
+		// the client walks, the server re-plays its steps.
+		let mut client = server.player(alice).clone();
+		let mut upd = vec![];
+		let walk = |client: &mut Player, upd: &mut ClientMsgs, world: &World| {
+			let inputs = vec![MoveInput { walk_dir: vec3::EX, ..default() }; 30];
+			for input in &inputs {
+				client.step(upd, input, world);
+			}
+			inputs
+		};
+		let inputs = walk(&mut client, &mut upd, &server.world);
+		server.pending_diffs.clear();
+		server.handle_client_msg(alice, ClientMsg::MovePlayer(client.skeleton.frame(), inputs));
+		assert_eq!(server.player(alice).skeleton, client.skeleton);
+		assert!(!server.pending_diffs.iter().any(|e| matches!(e.msg, CorrectMove(..))));
+
+		// a client claiming to have walked further gets corrected.
+		let inputs = walk(&mut client, &mut upd, &server.world);
+		let cheat = Frame {
+			position: client.position() + vec3(20.0, 0.0, 0.0),
+			..client.skeleton.frame()
+		};
+		server.handle_client_msg(alice, ClientMsg::MovePlayer(cheat, inputs));
+		assert_eq!(server.player(alice).position(), client.position());
+		assert!(server
+			.pending_diffs
+			.iter()
+			.any(|e| matches!(&e.msg, CorrectMove(frame, steps) if frame.position == client.position() && *steps == client.local.steps)));
+
+		// a client taking more steps than fit in the time passed gets corrected.
+		let inputs = vec![MoveInput { walk_dir: -vec3::EX, ..default() }; 2 * MAX_STEP_BUDGET as usize];
+		for input in &inputs {
+			client.step(&mut upd, input, &server.world);
+		}
+		server.pending_diffs.clear();
+		server.handle_client_msg(alice, ClientMsg::MovePlayer(client.skeleton.frame(), inputs));
+		assert!(server.player(alice).position().x() > client.position().x() + 10.0);
+		assert!(server.pending_diffs.iter().any(|e| matches!(&e.msg, CorrectMove(_, steps) if *steps == client.local.steps)));
+
+		// the velocity is re-played too, never taken from the client (e.g. launching without a jump pad).
+		server.handle_tick(1.0);
+		let mut client = server.player(alice).clone();
+		let input = MoveInput::default();
+		client.step(&mut upd, &input, &server.world);
+		let launch = Frame {
+			velocity: vec3(0.0, 100.0, 0.0),
+			..client.skeleton.frame()
+		};
+		server.handle_client_msg(alice, ClientMsg::MovePlayer(launch, vec![input]));
+		assert_eq!(server.player(alice).skeleton.velocity, client.skeleton.velocity);
+	}
+
+	#[test]
+	fn move_drift() {
+		let mut server = test_server(1);
+		let alice = join(&mut server, "alice");
+		server.handle_client_msg(alice, ClientMsg::ReadyToSpawn);
+
+		// a client claiming a bit more than it walked, each time just within tolerance, does not get ahead.
+		let mut honest = server.player(alice).clone();
+		let mut upd = vec![];
+		let input = MoveInput { walk_dir: vec3::EX, ..default() };
+		for _ in 0..50 {
+			honest.step(&mut upd, &input, &server.world);
+			let cheat = Frame {
+				position: honest.position() + vec3(1.9, 0.0, 0.0),
+				..honest.skeleton.frame()
+			};
+			server.handle_client_msg(alice, ClientMsg::MovePlayer(cheat, vec![input]));
+		}
+		assert!((server.player(alice).position() - honest.position()).len() < 0.01);
+	}
+
+	#[test]
+	fn respawn() {
+		let mut server = test_server(1);
+		let alice = join(&mut server, "alice");
+		server.handle_client_msg(alice, ClientMsg::ReadyToSpawn);
+
+		// die far away from the next spawn point.
+		server.player_mut(alice).skeleton.position += vec3(0.0, 500.0, 0.0);
+		server.pending_diffs.clear();
+		server.kill_player(alice);
+		let spawn_point = server
+			.pending_diffs
+			.iter()
+			.find_map(|e| match e.msg {
+				RequestRespawn(spawn_point) => Some(spawn_point),
+				_ => None,
+			})
+			.expect("respawn request");
+
+		// the client moves to the spawn point when ready (see Player::control).
+		server.handle_client_msg(alice, ClientMsg::ReadyToSpawn);
+		let frame = Frame {
+			position: spawn_point.position(),
+			orientation: spawn_point.orientation(),
+			..server.player(alice).skeleton.frame()
+		};
+		server.pending_diffs.clear();
+		server.handle_client_msg(alice, ClientMsg::MovePlayer(frame, vec![]));
+		assert_eq!(server.player(alice).position(), spawn_point.position());
+		assert!(!server.pending_diffs.iter().any(|e| matches!(e.msg, CorrectMove(..))));
+	}
+
+	#[test]
+	fn knockback() {
+		let mut server = test_server(1);
+		let alice = join(&mut server, "alice");
+		let bob = join(&mut server, "bob");
+		server.handle_client_msg(alice, ClientMsg::ReadyToSpawn);
+		server.handle_client_msg(bob, ClientMsg::ReadyToSpawn);
+		server.pending_diffs.clear();
+
+		// spawn protection: no damage, no knockback.
+		shoot(&mut server, alice, bob, HitLocation::Feet);
+		assert!(!server.pending_diffs.iter().any(|e| matches!(e.msg, Knockback(..))));
+		server.handle_tick(DEFAULT_INVULN_TTL + 0.1);
+		server.pending_diffs.clear();
+
+		// deathmatch: getting shot pushes bob away, through his own client.
+		shoot(&mut server, alice, bob, HitLocation::Feet);
+		let impulse = server
+			.pending_diffs
+			.iter()
+			.find_map(|e| match (&e.to, &e.msg) {
+				(Addressee::Just(id), Knockback(impulse)) if *id == bob => Some(*impulse),
+				_ => None,
+			})
+			.expect("knockback");
+		assert!(impulse.len() > 0.0);
+
+		// bob's client applies the knockback at its next step, the server re-plays it.
+		let mut client = server.player(bob).clone();
+		let mut upd = vec![];
+		let input = MoveInput { knockback: impulse, ..default() };
+		client.step(&mut upd, &input, &server.world);
+		server.pending_diffs.clear();
+		server.handle_client_msg(bob, ClientMsg::MovePlayer(client.skeleton.frame(), vec![input]));
+		assert_eq!(server.player(bob).skeleton, client.skeleton);
+		assert!(!server.pending_diffs.iter().any(|e| matches!(e.msg, CorrectMove(..))));
+
+		// two hits in a row: the client applies one impulse per step, in order.
+		server.player_mut(bob).health = Player::MAX_HEALTH;
+		server.pending_diffs.clear();
+		shoot(&mut server, alice, bob, HitLocation::Feet);
+		shoot(&mut server, alice, bob, HitLocation::Feet);
+		let impulses = server
+			.pending_diffs
+			.iter()
+			.filter_map(|e| match (&e.to, &e.msg) {
+				(Addressee::Just(id), Knockback(impulse)) if *id == bob => Some(*impulse),
+				_ => None,
+			})
+			.collect::<Vec<_>>();
+		assert_eq!(impulses.len(), 2);
+		let inputs = impulses.iter().map(|&knockback| MoveInput { knockback, ..default() }).collect::<Vec<_>>();
+		for input in &inputs {
+			client.step(&mut upd, input, &server.world);
+		}
+		server.pending_diffs.clear();
+		server.handle_client_msg(bob, ClientMsg::MovePlayer(client.skeleton.frame(), inputs));
+		assert_eq!(server.player(bob).skeleton, client.skeleton);
+		assert!(!server.pending_diffs.iter().any(|e| matches!(e.msg, CorrectMove(..))));
+
+		// knockback not handed out by the server is not accepted.
+		let mut inputs = vec![MoveInput::default(); 30];
+		inputs[0].knockback = vec3(0.0, 50.0, 0.0);
+		for input in &inputs {
+			client.step(&mut upd, input, &server.world);
+		}
+		server.handle_client_msg(bob, ClientMsg::MovePlayer(client.skeleton.frame(), inputs));
+		assert!(server.pending_diffs.iter().any(|e| matches!(e.msg, CorrectMove(..))));
+
+		// elimination: no knockback (unless enabled explicitly).
+		let mut server = ServerState::new(ServerOpts {
+			game_type: "elim".into(),
+			..test_opts(1)
+		})
+		.unwrap();
+		assert_eq!(
+			server.world.rules,
+			GameRules {
+				player_collision: true,
+				knockback: false
+			}
+		);
+		let alice = join(&mut server, "alice");
+		let bob = join(&mut server, "bob");
+		server.handle_client_msg(alice, ClientMsg::ReadyToSpawn);
+		server.handle_client_msg(bob, ClientMsg::ReadyToSpawn);
+		shoot(&mut server, alice, bob, HitLocation::Feet);
+		assert!(!server.pending_diffs.iter().any(|e| matches!(e.msg, Knockback(..))));
+
+		let server = ServerState::new(ServerOpts {
+			game_type: "elim".into(),
+			knockback: Some(true),
+			..test_opts(1)
+		})
+		.unwrap();
+		assert!(server.world.rules.knockback);
+	}
+
+	// Fall from `height` onto a floating platform, in moves of 10 units.
+	fn fall(server: &mut ServerState, player_id: ID, height: f32) {
+		let floor = Cuboid::new(ivec3(0, 300, 0), ivec3(32, 304, 32));
+		server.world.map.voxels.set_range(&floor, VoxelType(5));
+		let landing = vec3(16.0, 304.0, 16.0);
+		server.player_mut(player_id).skeleton.position = landing + vec3(0.0, height, 0.0);
+		server.player_mut(player_id).skeleton.velocity = vec3::ZERO;
+
+		// the client falls in steps, re-played by the server.
+		let mut client = server.player(player_id).clone();
+		while !client.skeleton.on_ground(&server.world) {
+			let inputs = vec![MoveInput::default(); 12];
+			for input in &inputs {
+				client.step(&mut vec![], input, &server.world);
+			}
+			server.handle_client_msg(player_id, ClientMsg::MovePlayer(client.skeleton.frame(), inputs));
+			server.handle_tick(12.0 * TIMESTEP);
+		}
+	}
+
+	#[test]
+	fn fall_damage() {
+		let mut server = test_server(1);
+		let alice = join(&mut server, "alice");
+		server.handle_client_msg(alice, ClientMsg::ReadyToSpawn);
+		server.handle_tick(DEFAULT_INVULN_TTL + 0.1); // spawn protection wears off
+
+		fall(&mut server, alice, 20.0);
+		assert_eq!(server.player(alice).health, Player::MAX_HEALTH);
+
+		fall(&mut server, alice, 50.0);
+		let health = server.player(alice).health;
+		assert!(health > 0 && health < Player::MAX_HEALTH);
+
+		// not lethal by default.
+		fall(&mut server, alice, 200.0);
+		assert_eq!(server.player(alice).health, 1);
+		assert!(server.player(alice).spawned);
+
+		let mut server = ServerState::new(ServerOpts { lethal_falls: true, ..test_opts(1) }).unwrap();
+		let bob = join(&mut server, "bob");
+		server.handle_client_msg(bob, ClientMsg::ReadyToSpawn);
+		server.handle_tick(DEFAULT_INVULN_TTL + 0.1);
+		fall(&mut server, bob, 200.0);
+		assert!(!server.player(bob).spawned);
+	}
+
+	#[test]
+	fn destructible_voxels() {
+		let mut server = ServerState::new(ServerOpts {
+			destructible: vec![9],
+			..test_opts(1)
+		})
+		.unwrap();
+		let alice = join(&mut server, "alice");
+		server.handle_client_msg(alice, ClientMsg::ReadyToSpawn);
+
+		// a destructible and an indestructible block, floating high above the map.
+		let sponge = Cuboid::new(ivec3(0, 200, 0), ivec3(8, 208, 8));
+		let stone = Cuboid::new(ivec3(8, 200, 0), ivec3(16, 208, 8));
+		server.world.map.voxels.set_range(&sponge, VoxelType(9));
+		server.world.map.voxels.set_range(&stone, VoxelType(5));
+
+		// alice stands in front of the blocks.
+		server.player_mut(alice).skeleton.position = vec3(7.5, 200.0, -10.0);
+
+		shoot_voxel(&mut server, alice, ivec3(7, 204, 0));
+		assert_eq!(server.world.map.voxels.at(ivec3(7, 204, 0)), VoxelType::EMPTY);
+		assert_eq!(server.world.map.voxels.at(ivec3(6, 204, 0)), VoxelType::EMPTY);
+		assert_eq!(server.world.map.voxels.at(ivec3(8, 204, 0)), VoxelType(5));
+		assert_eq!(server.world.map.voxels.at(ivec3(4, 204, 0)), VoxelType(9));
+
+		// voxels out of sight cannot be hit.
+		shoot_voxel(&mut server, alice, ivec3(3, 204, 4));
+		assert_eq!(server.world.map.voxels.at(ivec3(3, 204, 4)), VoxelType(9));
+
+		// one hit per bullet fired: no digging through walls by spamming hits.
+		server.handle_client_msg(alice, ClientMsg::HitVoxel(ivec3(2, 207, 0)));
+		assert_eq!(server.world.map.voxels.at(ivec3(2, 207, 0)), VoxelType(9));
+		shoot_voxel(&mut server, alice, ivec3(2, 207, 0));
+		assert_eq!(server.world.map.voxels.at(ivec3(2, 207, 0)), VoxelType::EMPTY);
+		server.handle_client_msg(alice, ClientMsg::HitVoxel(ivec3(1, 204, 0)));
+		assert_eq!(server.world.map.voxels.at(ivec3(1, 204, 0)), VoxelType(9));
+
+		// a player joining later gets the holes blown into the map so far.
+		let edits = server.changed_voxels();
+		assert!(!edits.is_empty());
+		assert!(edits.iter().all(|&(_, value)| value == VoxelType::EMPTY));
+
+		// hitting the same spot again does not add to that.
+		shoot_voxel(&mut server, alice, ivec3(7, 204, 0));
+		assert_eq!(server.changed_voxels(), edits);
+		server.pending_diffs.clear();
+		let bob = join(&mut server, "bob");
+		let sent = server
+			.pending_diffs
+			.iter()
+			.filter(|e| matches!(e.to, Addressee::Just(id) if id == bob))
+			.filter_map(|e| match e.msg {
+				SetVoxels { range, value } => Some((range, value)),
+				_ => None,
+			})
+			.collect::<Vec<_>>();
+		assert_eq!(sent, edits);
+	}
+
+	#[test]
+	fn teams() {
+		// no teams in deathmatch.
+		let mut server = test_server(1);
+		let alice = join(&mut server, "alice");
+		assert!(server.switch_team(alice, Team::Blue).is_err());
+
+		let mut server = ServerState::new(ServerOpts {
+			game_type: "tm".into(),
+			..test_opts(1)
+		})
+		.unwrap();
+		let players = ["alice", "bob", "carol", "dave"].map(|name| join(&mut server, name));
+		for &player_id in &players {
+			server.handle_client_msg(player_id, ClientMsg::ReadyToSpawn);
+		}
+		assert_eq!(server.team_sizes()[..2], [2, 2]);
+
+		// leaving players: remaining team sizes are re-broadcast.
+		let blue = players.into_iter().filter(|&id| server.player(id).team == Team::Blue).collect::<Vec<_>>();
+		server.pending_diffs.clear();
+		for &player_id in &blue {
+			server.handle_drop_player(player_id);
+		}
+		assert!(server.pending_diffs.iter().any(|e| matches!(e.msg, UpdateHUD(HUDUpdate::Score(..)))));
+		assert_eq!(server.team_sizes()[..2], [2, 0]);
+
+		// balancing moves a player without killing them.
+		server.pending_diffs.clear();
+		server.balance_teams();
+		assert_eq!(server.team_sizes()[..2], [1, 1]);
+		assert!(players.iter().filter(|&id| !blue.contains(id)).all(|&id| server.player(id).spawned));
+		assert!(!server.pending_diffs.iter().any(|e| matches!(e.msg, AddEffect(..))));
+	}
+
+	#[test]
+	fn weapons() {
+		let mut server = test_server(1);
+		let alice = join(&mut server, "alice");
+		server.handle_client_msg(alice, ClientMsg::ReadyToSpawn);
+
+		// only weapons that were handed out can be used.
+		server.handle_client_msg(alice, ClientMsg::SwitchWeapon(Weapon::LaserRifle));
+		assert_eq!(server.player(alice).weapon, Weapon::default());
+		server.give_weapon(alice, Weapon::LaserRifle);
+		server.handle_client_msg(alice, ClientMsg::SwitchWeapon(Weapon::LaserRifle));
+		assert_eq!(server.player(alice).weapon, Weapon::LaserRifle);
+
+		// projectiles leave from the player's gun, not wherever the client says.
+		server.give_weapon(alice, Weapon::CarrotLauncher);
+		server.handle_client_msg(alice, ClientMsg::SwitchWeapon(Weapon::CarrotLauncher));
+		let first = server.next_entity_id;
+		let launched = |server: &ServerState| server.next_entity_id - first;
+		server.handle_client_msg(alice, ClientMsg::Fire(Weapon::CarrotLauncher));
+		assert_eq!(launched(&server), 1);
+		let rocket = server.entity(first).unwrap();
+		let line_of_fire = server.player(alice).line_of_fire(&server.world);
+		assert!((rocket.position - line_of_fire.start.to_f32()).len() < 1e-3);
+		assert!((rocket.velocity - Weapon::CarrotLauncher.projectile_speed() * line_of_fire.dir.to_f32()).len() < 1e-3);
+
+		// not faster than the weapon's cooldown.
+		server.handle_client_msg(alice, ClientMsg::Fire(Weapon::CarrotLauncher));
+		assert_eq!(launched(&server), 1);
+		server.handle_tick(Weapon::CarrotLauncher.cooldown());
+		server.handle_client_msg(alice, ClientMsg::Fire(Weapon::CarrotLauncher));
+		assert_eq!(launched(&server), 2);
+
+		// only with a weapon in hand.
+		server.handle_tick(Weapon::CarrotLauncher.cooldown());
+		server.handle_client_msg(alice, ClientMsg::Fire(Weapon::ConfettiGrenade));
+		assert_eq!(launched(&server), 2);
+
+		// instant bullets: no hits without firing, no more hits than bullets fired.
+		let bob = join(&mut server, "bob");
+		server.handle_client_msg(bob, ClientMsg::ReadyToSpawn);
+		server.player_mut(bob).invulnerability_ttl = None;
+		server.give_weapon(alice, Weapon::LaserRifle);
+		server.handle_client_msg(alice, ClientMsg::SwitchWeapon(Weapon::LaserRifle));
+		server.handle_client_msg(alice, ClientMsg::HitPlayer(bob, HitLocation::Feet));
+		assert_eq!(server.player(bob).health, Player::MAX_HEALTH);
+		server.handle_tick(Weapon::CarrotLauncher.cooldown());
+		server.handle_client_msg(alice, ClientMsg::Fire(Weapon::LaserRifle));
+		server.handle_client_msg(alice, ClientMsg::HitPlayer(bob, HitLocation::Feet));
+		let health = server.player(bob).health;
+		assert!(health < Player::MAX_HEALTH);
+		server.handle_client_msg(alice, ClientMsg::HitPlayer(bob, HitLocation::Feet));
+		assert_eq!(server.player(bob).health, health);
+
+		// not faster than the weapon fires, and only as long as there is ammo left.
+		let ammo = |server: &ServerState| server.inventory[&alice][Weapon::LaserRifle.index()];
+		let left = ammo(&server);
+		server.handle_client_msg(alice, ClientMsg::Fire(Weapon::LaserRifle));
+		assert_eq!(ammo(&server), left);
+		for _ in 0..20 {
+			server.handle_tick(Weapon::LaserRifle.cooldown());
+			server.handle_client_msg(alice, ClientMsg::Fire(Weapon::LaserRifle));
+		}
+		assert_eq!(ammo(&server), 0);
+		server.handle_client_msg(alice, ClientMsg::HitPlayer(bob, HitLocation::Feet)); // last bullet
+		let health = server.player(bob).health;
+		server.handle_tick(Weapon::LaserRifle.cooldown());
+		server.handle_client_msg(alice, ClientMsg::Fire(Weapon::LaserRifle));
+		server.handle_client_msg(alice, ClientMsg::HitPlayer(bob, HitLocation::Feet));
+		assert_eq!(server.player(bob).health, health);
+
+		// weapons are lost on death.
+		server.kill_player(alice);
+		server.handle_client_msg(alice, ClientMsg::SwitchWeapon(Weapon::LaserRifle));
+		assert_eq!(server.player(alice).weapon, Weapon::default());
+	}
+
+	#[test]
+	fn levelling() {
+		let mut server = test_server(1);
+		let best = join(&mut server, "best");
+		let worst = join(&mut server, "worst");
+		server.increment_score(best, 10);
+		let berserker = EKind::Powerup(PowerupTable::get().by_name("berserker_helmet").unwrap());
+
+		let count = |server: &ServerState, player_id: ID, kind: EKind| (0..1000).filter(|_| server.randomish_powerup_for(player_id) == kind).count();
+		let gift_box = EKind::GiftBox { pickup_point_id: None };
+
+		// exact counts: all randomness comes from the server's seeded rng.
+		assert_eq!(count(&server, worst, berserker), 398);
+		assert_eq!(count(&server, best, berserker), 76);
+		assert_eq!(count(&server, best, gift_box), 391);
+		assert_eq!(count(&server, worst, gift_box), 86);
+	}
+
+	#[test]
+	fn flag_returns() {
+		let mut server = ServerState::new(ServerOpts {
+			game_type: "ctf".into(),
+			..test_opts(1)
+		})
+		.unwrap();
+		server.world.map.metadata.flag_bases = vec![
+			FlagBase {
+				pos: ivec3(8, 204, 8),
+				team: Team::Red,
+			},
+			FlagBase {
+				pos: ivec3(24, 204, 24),
+				team: Team::Blue,
+			},
+		];
+		server.populate_flags();
+		let alice = join(&mut server, "alice");
+		let bob = join(&mut server, "bob");
+		server.handle_client_msg(alice, ClientMsg::ReadyToSpawn);
+		server.handle_client_msg(bob, ClientMsg::ReadyToSpawn);
+		let (alice_team, bob_team) = (server.player(alice).team, server.player(bob).team);
+		assert_ne!(alice_team, bob_team);
+		let announced = |server: &ServerState, text: &str| {
+			server
+				.pending_diffs
+				.iter()
+				.any(|e| matches!(&e.msg, UpdateHUD(HUDUpdate::Message(m)) if m.contains(text)) && matches!(e.to, Addressee::All))
+		};
+
+		// bob drops alice's flag, alice returns it.
+		server.player_mut(bob).flag = Some(alice_team);
+		server.drop_flag(bob);
+		let eid = match server.flag_state(alice_team) {
+			Some(FlagState::Dropped(eid, _)) => eid,
+			other => panic!("{:?}", other),
+		};
+		server.pending_diffs.clear();
+		server.touch_flag(alice, eid, alice_team);
+		assert!(announced(&server, "alice returned"));
+
+		// left alone, a dropped flag returns by itself.
+		server.player_mut(alice).flag = Some(bob_team);
+		server.drop_flag(alice);
+		server.pending_diffs.clear();
+		server.tick_flags(FLAG_RETURN_TTL + 1.0);
+		assert!(announced(&server, "flag returned to base"));
+	}
+
+	#[test]
+	fn king_of_the_hill() {
+		for teams in [false, true] {
+			let mut server = ServerState::new(ServerOpts {
+				game_type: if teams { "tkoth" } else { "koth" }.into(),
+				num_teams: 1,
+				..test_opts(1)
+			})
+			.unwrap();
+			let alice = join(&mut server, "alice");
+			let bob = join(&mut server, "bob");
+			server.handle_client_msg(alice, ClientMsg::ReadyToSpawn);
+			server.handle_client_msg(bob, ClientMsg::ReadyToSpawn);
+			let team = server.player(alice).team;
+			let center = server.player(alice).center().map(|v| v as i32);
+			server.world.map.metadata.control_zones.push(ControlZone {
+				min: center - ivec3(2, 2, 2),
+				max: center + ivec3(2, 2, 2),
+			});
+			server.reset_zones();
+
+			// alice alone: one point per interval.
+			server.tick_zones(ZONE_SCORE_INTERVAL);
+			let owner = if teams { ZoneOwner::Team(team) } else { ZoneOwner::Player(alice) };
+			assert!(server.pending_diffs.iter().any(|e| matches!(&e.msg, UpdateZones(zones) if zones == &[owner])));
+			match teams {
+				true => assert_eq!(server.gametype.team_score().unwrap()[team as usize], 1),
+				false => assert_eq!(server.score(alice), 1),
+			}
+
+			// bob joins her: contested between players, still one point per interval for a team.
+			server.player_mut(bob).skeleton.position = server.player(alice).position();
+			server.tick_zones(ZONE_SCORE_INTERVAL);
+			match teams {
+				true => assert_eq!(server.gametype.team_score().unwrap()[team as usize], 2),
+				false => assert_eq!((server.score(alice), server.score(bob)), (1, 0)),
+			}
+		}
+	}
+
+	#[test]
+	fn elimination_rounds() {
+		let mut server = ServerState::new(ServerOpts {
+			game_type: "telim".into(),
+			..test_opts(1)
+		})
+		.unwrap();
+		let alice = join(&mut server, "alice");
+		let bob = join(&mut server, "bob");
+		let team = server.player(alice).team;
+		let wins = |server: &ServerState| server.elimination().unwrap().wins(Side::Team(team));
+
+		for round in 1..=ROUNDS_TO_WIN {
+			server.handle_client_msg(alice, ClientMsg::ReadyToSpawn);
+			server.handle_client_msg(bob, ClientMsg::ReadyToSpawn);
+			assert!(server.round_in_progress());
+			server.kill_player(bob);
+			assert!(!server.round_in_progress());
+			if round < ROUNDS_TO_WIN {
+				assert_eq!(wins(&server), round);
+				// round wins are not kills.
+				assert_eq!(server.gametype.team_score().unwrap()[team as usize], 0);
+			}
+			server.handle_tick(ROUND_END_DELAY + 0.1);
+		}
+
+		// the match is over: start over from round 1.
+		assert_eq!(wins(&server), 0);
+		assert_eq!(server.elimination().unwrap().round, 1);
+	}
 }
@@ -3,14 +3,216 @@ use super::internal::*;
 #[derive(Debug, Serialize, Deserialize, Clone, Copy)]
 pub struct SpawnPoint {
 	pub pos: ivec3,
+
+	/// Only this team spawns here (team games). Untagged points are used by everyone.
+	#[serde(default)]
+	pub team: Option<Team>,
+
+	/// Direction players face when spawning here.
+	#[serde(default)]
+	pub orientation: Orientation,
+}
+
+/// What to take into account when choosing where a player spawns.
+pub struct SpawnContext<'a> {
+	pub voxels: &'a Voxels,
+
+	/// Team of the spawning player (`None` in free-for-all).
+	pub team: Option<Team>,
+
+	/// Camera positions of living enemies.
+	pub enemies: &'a [vec3],
+
+	/// Positions of living teammates.
+	pub friends: &'a [vec3],
+
+	/// Seconds since each spawn point was last used (`f32::INFINITY` if never).
+	pub since_used: &'a [f32],
 }
 
+// Enemies further away than this don't make a spawn point any safer.
+const SAFE_DISTANCE: f32 = 64.0;
+
+// Penalty per enemy with a line of sight to the spawn point.
+const VISIBLE_PENALTY: f32 = 1.0;
+
+// Penalty for spawning on top of another player.
+const CROWDED_RADIUS: f32 = 8.0;
+const CROWDED_PENALTY: f32 = 2.0;
+
+// Penalty for a spawn point that was just used, decaying linearly over RECENT_TIME seconds.
+const RECENT_TIME: f32 = 5.0;
+const RECENT_PENALTY: f32 = 1.5;
+
+// Random score added to each point, so that equally good points are picked at random.
+const JITTER: f32 = 0.1;
+
 impl SpawnPoint {
 	pub fn position(&self) -> vec3 {
 		self.pos.to_f32()
 	}
 
 	pub fn orientation(&self) -> Orientation {
-		Orientation::default() // TODO
+		self.orientation
+	}
+
+	/// May a player on `team` spawn here?
+	pub fn allows(&self, team: Option<Team>) -> bool {
+		match (self.team, team) {
+			(Some(tag), Some(team)) => tag == team,
+			_ => true,
+		}
+	}
+
+	// Higher is safer:
+	// far from enemies, out of their sight, not crowded and not just used by someone else.
+	fn score(&self, since_used: f32, ctx: &SpawnContext) -> f32 {
+		let pos = self.position();
+		let eye = pos + vec3(0.0, Player::CAM_HEIGHT, 0.0);
+
+		let nearest = ctx.enemies.iter().map(|&enemy| (enemy - eye).len()).fold(SAFE_DISTANCE, f32::min);
+		let visible = ctx.enemies.iter().filter(|&&enemy| line_of_sight(ctx.voxels, enemy, eye)).count();
+		let crowded = ctx.enemies.iter().filter(|&&enemy| (enemy - eye).len() < CROWDED_RADIUS).count() + ctx.friends.iter().filter(|&&friend| (friend - pos).len() < CROWDED_RADIUS).count();
+		let recent = f32::max(0.0, 1.0 - since_used / RECENT_TIME);
+
+		nearest / SAFE_DISTANCE - VISIBLE_PENALTY * (visible as f32) - CROWDED_PENALTY * (crowded as f32) - RECENT_PENALTY * recent
+	}
+}
+
+/// Index of the safest spawn point in `points` for the player described by `ctx`.
+/// Points tagged for other teams are only used if there is no alternative.
+/// `None` if there are no spawn points at all.
+pub fn safest_spawn_point(rng: &mut impl Rng, points: &[SpawnPoint], ctx: &SpawnContext) -> Option<usize> {
+	let allowed = |i: &usize| points[*i].allows(ctx.team);
+	let candidates = match (0..points.len()).any(|i| allowed(&i)) {
+		true => (0..points.len()).filter(allowed).collect::<Vec<_>>(),
+		false => (0..points.len()).collect::<Vec<_>>(),
+	};
+
+	candidates
+		.into_iter()
+		.map(|i| {
+			let since_used = ctx.since_used.get(i).copied().unwrap_or(f32::INFINITY);
+			(i, points[i].score(since_used, ctx) + JITTER * rng.gen::<f32>())
+		})
+		.max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+		.map(|(i, _)| i)
+}
+
+// Can someone at `from` see `to`?
+fn line_of_sight(voxels: &Voxels, from: vec3, to: vec3) -> bool {
+	let len = (to - from).len();
+	let ray = Ray64::new(from.into(), (to - from).normalized().into());
+	if !voxels.intersects(&ray) {
+		return true;
+	}
+	// something is in the line of fire, but it may be beyond `to`.
+	voxels.intersect(&ray).map(|(_, t)| t as f32 >= len).unwrap_or(true)
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn point(x: i32, z: i32) -> SpawnPoint {
+		SpawnPoint {
+			pos: ivec3(x, 0, z),
+			team: None,
+			orientation: default(),
+		}
+	}
+
+	fn ctx<'a>(voxels: &'a Voxels, enemies: &'a [vec3], since_used: &'a [f32]) -> SpawnContext<'a> {
+		SpawnContext {
+			voxels,
+			team: None,
+			enemies,
+			friends: &[],
+			since_used,
+		}
+	}
+
+	fn eye(x: f32, z: f32) -> vec3 {
+		vec3(x, Player::CAM_HEIGHT, z)
+	}
+
+	#[test]
+	fn parse_old_format() {
+		// metadata saved before spawn points had a team or orientation.
+		let sp: SpawnPoint = serde_json::from_str(r#"{"pos": [1, 2, 3]}"#).unwrap();
+		assert_eq!(sp.pos, ivec3(1, 2, 3));
+		assert_eq!(sp.team, None);
+		assert_eq!(sp.orientation(), Orientation::default());
+	}
+
+	#[test]
+	fn avoid_nearby_enemy() {
+		let voxels = Voxels::new();
+		let points = [point(0, 0), point(100, 0)];
+		let enemies = [eye(10.0, 0.0)];
+		for seed in 0..20 {
+			let mut rng = StdRng::seed_from_u64(seed);
+			assert_eq!(safest_spawn_point(&mut rng, &points, &ctx(&voxels, &enemies, &[])), Some(1));
+		}
+	}
+
+	#[test]
+	fn avoid_line_of_sight() {
+		// enemy is equally far from both points, but a wall blocks the view of point 0.
+		let mut voxels = Voxels::new();
+		voxels.set_range(&Cuboid::new(ivec3(8, 0, 24), ivec3(12, 20, 40)), VoxelType(1));
+		let points = [point(0, 32), point(40, 32)];
+		let enemies = [eye(20.0, 33.0)];
+		for seed in 0..20 {
+			let mut rng = StdRng::seed_from_u64(seed);
+			assert_eq!(safest_spawn_point(&mut rng, &points, &ctx(&voxels, &enemies, &[])), Some(0));
+		}
+	}
+
+	#[test]
+	fn avoid_recently_used() {
+		let voxels = Voxels::new();
+		let points = [point(0, 0), point(100, 0)];
+		let since_used = [0.5, f32::INFINITY];
+		for seed in 0..20 {
+			let mut rng = StdRng::seed_from_u64(seed);
+			assert_eq!(safest_spawn_point(&mut rng, &points, &ctx(&voxels, &[], &since_used)), Some(1));
+		}
+	}
+
+	#[test]
+	fn random_when_equal() {
+		let voxels = Voxels::new();
+		let points = [point(0, 0), point(100, 0)];
+		let mut rng = StdRng::seed_from_u64(1);
+		let picks = (0..100).map(|_| safest_spawn_point(&mut rng, &points, &ctx(&voxels, &[], &[])).unwrap()).collect::<Vec<_>>();
+		assert!(picks.contains(&0) && picks.contains(&1));
+	}
+
+	#[test]
+	fn team_tags() {
+		let voxels = Voxels::new();
+		let points = [
+			SpawnPoint { team: Some(Team::Red), ..point(0, 0) },
+			SpawnPoint {
+				team: Some(Team::Blue),
+				..point(100, 0)
+			},
+		];
+		let mut rng = StdRng::seed_from_u64(1);
+		for _ in 0..20 {
+			let ctx = SpawnContext {
+				team: Some(Team::Blue),
+				..ctx(&voxels, &[], &[])
+			};
+			assert_eq!(safest_spawn_point(&mut rng, &points, &ctx), Some(1));
+		}
+		// no points for the green team: use any.
+		let ctx = SpawnContext {
+			team: Some(Team::Green),
+			..ctx(&voxels, &[], &[])
+		};
+		assert!(safest_spawn_point(&mut rng, &points, &ctx).is_some());
+		assert_eq!(safest_spawn_point(&mut rng, &[], &ctx), None);
 	}
 }
@@ -13,12 +13,14 @@ pub const NUM_TEAMS: usize = 3;
 use Team::*;
 
 impl Team {
-	pub fn random() -> Self {
-		match rand::thread_rng().gen_range((Red as u8)..(NUM_TEAMS as u8)) {
-			0 => Red,
-			1 => Blue,
-			2 => Green,
-			_ => unreachable!(),
+	pub const ALL: [Team; NUM_TEAMS] = [Red, Blue, Green];
+
+	/// Abbreviated name, for compact score display.
+	pub fn short_name(self) -> &'static str {
+		match self {
+			Red => "Red",
+			Blue => "Blu",
+			Green => "Grn",
 		}
 	}
 
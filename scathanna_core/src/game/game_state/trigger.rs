@@ -0,0 +1,61 @@
+use super::internal::*;
+
+/// A region of the map that does something to players walking into it
+/// (jump pads, teleporters).
+///
+/// Triggers are applied client-side for responsiveness (see `Player::tick_triggers`),
+/// the server only checks that teleports are legit.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct Trigger {
+	pub min: ivec3,
+	pub max: ivec3,
+	pub action: TriggerAction,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum TriggerAction {
+	/// Launch players with this velocity.
+	JumpPad { velocity: vec3 },
+
+	/// Move players to `destination`, facing `orientation`.
+	Teleport { destination: vec3, orientation: Orientation },
+}
+
+impl Trigger {
+	pub fn bounds(&self) -> BoundingBox<f32> {
+		BoundingBox::new(self.min.to_f32(), self.max.to_f32())
+	}
+
+	/// Does a player standing at `position` (center bottom) set off this trigger?
+	pub fn contains(&self, position: vec3) -> bool {
+		self.bounds().contains(position)
+	}
+
+	/// Is `position` within `margin` of this trigger?
+	pub fn is_near(&self, position: vec3, margin: f32) -> bool {
+		let margin = vec3(margin, margin, margin);
+		BoundingBox::new(self.min.to_f32() - margin, self.max.to_f32() + margin).contains(position)
+	}
+
+	/// Color to draw the outline with (in the editor).
+	pub fn color(&self) -> vec3 {
+		match self.action {
+			TriggerAction::JumpPad { .. } => GREEN,
+			TriggerAction::Teleport { .. } => MAGENTA,
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn parse() {
+		let json = r#"{"min": [0, 0, 0], "max": [4, 1, 4], "action": {"JumpPad": {"velocity": [0, 80, 0]}}}"#;
+		let trigger: Trigger = serde_json::from_str(json).unwrap();
+		assert_eq!(trigger.action, TriggerAction::JumpPad { velocity: vec3(0.0, 80.0, 0.0) });
+		assert!(trigger.contains(vec3(2.0, 0.0, 2.0)));
+		assert!(!trigger.contains(vec3(2.0, 2.0, 2.0)));
+	}
+}
@@ -0,0 +1,177 @@
+use super::internal::*;
+
+/// A gun a player can hold.
+///
+/// Every player always owns the default weapon (the bubble gun, with unlimited ammo).
+/// Other weapons are collected as pickups and hold a limited number of shots.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Weapon {
+	#[default]
+	BubbleGun,
+	ConfettiBlaster,
+	LaserRifle,
+	CarrotLauncher,
+	ConfettiGrenade,
+}
+
+pub const NUM_WEAPONS: usize = 5;
+
+use Weapon::*;
+
+impl Weapon {
+	/// All weapons, in the order of the numeric keys used to select them.
+	pub const ALL: [Weapon; NUM_WEAPONS] = [BubbleGun, ConfettiBlaster, LaserRifle, CarrotLauncher, ConfettiGrenade];
+
+	pub fn as_str(self) -> &'static str {
+		match self {
+			BubbleGun => "bubble_gun",
+			ConfettiBlaster => "confetti_blaster",
+			LaserRifle => "laser_rifle",
+			CarrotLauncher => "carrot_launcher",
+			ConfettiGrenade => "confetti_grenade",
+		}
+	}
+
+	pub fn description(self) -> &'static str {
+		match self {
+			BubbleGun => "Trusty old bubble gun",
+			ConfettiBlaster => "Devastating at close range",
+			LaserRifle => "Slow but deadly",
+			CarrotLauncher => "Fires explosive carrots",
+			ConfettiGrenade => "Bounces around corners",
+		}
+	}
+
+	/// Index into `ALL`, for per-weapon arrays.
+	pub fn index(self) -> usize {
+		self as usize
+	}
+
+	/// Seconds between shots.
+	pub fn cooldown(self) -> f32 {
+		match self {
+			BubbleGun => 0.7,
+			ConfettiBlaster => 1.0,
+			LaserRifle => 1.5,
+			CarrotLauncher => 1.0,
+			ConfettiGrenade => 0.8,
+		}
+	}
+
+	/// Number of (instant) bullets fired per shot.
+	pub fn pellets(self) -> usize {
+		match self {
+			ConfettiBlaster => 8,
+			CarrotLauncher | ConfettiGrenade => 0, // fire a projectile instead
+			_ => 1,
+		}
+	}
+
+	/// Maximum random deviation of each bullet from the line of fire (radians).
+	pub fn spread(self) -> f32 {
+		match self {
+			ConfettiBlaster => 5.0 * DEG,
+			_ => 0.0,
+		}
+	}
+
+	/// Distance beyond which bullets have no effect.
+	pub fn range(self) -> f32 {
+		match self {
+			ConfettiBlaster => 80.0,
+			_ => 10000.0,
+		}
+	}
+
+	/// Damage dealt by a single bullet hitting at `location`.
+	/// (Projectiles deal splash damage instead, see `EKind::splash_damage`).
+	pub fn damage(self, location: HitLocation) -> i32 {
+		match self {
+			ConfettiBlaster => location.damage() / 3,
+			LaserRifle => 2 * location.damage(),
+			_ => location.damage(),
+		}
+	}
+
+	/// The projectile fired by this weapon, if it does not fire instant bullets.
+	pub fn projectile(self, owner: ID) -> Option<EKind> {
+		match self {
+			CarrotLauncher => Some(EKind::Rocket { owner }),
+			ConfettiGrenade => Some(EKind::Grenade { owner }),
+			_ => None,
+		}
+	}
+
+	/// Launch speed of `projectile`.
+	pub fn projectile_speed(self) -> f32 {
+		match self {
+			CarrotLauncher => 80.0,
+			_ => 50.0,
+		}
+	}
+
+	/// Shots gained when picking up this weapon.
+	/// `None` means unlimited (the default weapon).
+	pub fn ammo(self) -> Option<u32> {
+		match self {
+			BubbleGun => None,
+			ConfettiBlaster => Some(10),
+			LaserRifle => Some(5),
+			CarrotLauncher => Some(5),
+			ConfettiGrenade => Some(5),
+		}
+	}
+
+	/// Name of the wavefront file used to draw this weapon.
+	pub fn obj_name(self) -> &'static str {
+		match self {
+			BubbleGun => "bubblegun",
+			_ => "gun",
+		}
+	}
+
+	/// Name of the texture used to draw this weapon.
+	pub fn texture_name(self) -> &'static str {
+		match self {
+			BubbleGun => "party_hat",
+			other => other.as_str(),
+		}
+	}
+
+	/// Color to draw with in case no texture file is found.
+	pub fn fallback_color(self) -> vec3 {
+		match self {
+			BubbleGun => YELLOW,
+			ConfettiBlaster => MAGENTA,
+			LaserRifle => RED,
+			CarrotLauncher => ORANGE,
+			ConfettiGrenade => MAGENTA,
+		}
+	}
+
+	/// Sound clips played (one picked at random) when firing.
+	pub fn shoot_sounds(self) -> &'static [&'static str] {
+		match self {
+			BubbleGun => &["bang1", "bang2", "bang3", "bang4"],
+			ConfettiBlaster => &["shoot1"],
+			LaserRifle => &["bang3", "bang4"],
+			CarrotLauncher | ConfettiGrenade => &["shoot1"],
+		}
+	}
+
+	/// Volume of `shoot_sounds`.
+	pub fn shoot_volume(self) -> f32 {
+		match self {
+			LaserRifle => 60.0,
+			_ => 30.0,
+		}
+	}
+
+	/// Visual effect of a bullet flying from `start` over a distance `len`.
+	pub fn beam_effect(self, start: vec3, orientation: Orientation, len: f32, color_filter: vec3) -> Effect {
+		match self {
+			LaserRifle => Effect::laserbeam(start, orientation, len),
+			_ => Effect::particle_beam(start, orientation, len, color_filter),
+		}
+	}
+}
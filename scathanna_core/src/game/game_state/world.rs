@@ -13,6 +13,7 @@ pub struct World {
 	pub players: Players,
 	pub entities: HashMap<EID, Entity>, // TODO: struct Entities. fn insert(Entity), etc.
 	pub effects: Vec<Effect>,
+	pub rules: GameRules,
 }
 
 impl World {
@@ -24,6 +25,7 @@ impl World {
 			players,
 			entities,
 			effects: default(),
+			rules: default(),
 		})
 	}
 
@@ -47,6 +49,15 @@ impl World {
 		nearest
 	}
 
+	/// Does a wall obstruct the line of sight between two positions?
+	pub fn is_obstructed(&self, pos1: vec3, pos2: vec3) -> bool {
+		let dir = (pos2 - pos1).normalized();
+		let len = (pos2 - pos1).len();
+		let ray = Ray64::new(pos1.into(), dir.into());
+		let t = self.map.intersect(&ray).unwrap_or(f64::INFINITY) as f32;
+		t < len
+	}
+
 	// Iterate over (a copy of) all player IDs.
 	// (Does not hold self borrowed so can be conveniently used to self-modify).
 	pub fn entity_ids(&self) -> impl Iterator<Item = EID> {
@@ -24,6 +24,7 @@ pub struct GLClient {
 	// GPU state needed to render the world.
 	//   - changes on every map reload:
 	voxel_models: VoxelModels,
+	light_probes: LightProbes,
 
 	//   - immutable forever:
 	model_pack: ModelPack,
@@ -32,14 +33,23 @@ pub struct GLClient {
 pub const DBG_GEOMETRY: bool = false;
 const ENABLE_BORDERS: bool = false;
 
+// Opacity of the tint over control zones (king-of-the-hill).
+const ZONE_ALPHA: f32 = 0.25;
+
 impl GLClient {
 	/// Construct a GLClient that renders and controls a GameState through Player `ID`.
 	/// Loads the needed textures and models from local disk.
 	pub fn new(engine: Rc<Engine>, world: World, player_id: ID) -> Result<Self> {
 		let dir = map_directory(&world.map.name);
 		let voxel_models = VoxelModels::load(&engine, &world.map.voxels, &dir, ENABLE_BORDERS)?;
+		let light_probes = LightProbes::load(&dir).unwrap_or_else(|e| {
+			eprintln!("no light probes for map {} ({}), players and entities will be lit uniformly", &world.map.name, e);
+			default()
+		});
 
 		engine.set_sun_direction(world.map.metadata.sun_direction);
+		PowerupTable::init()?;
+		Palette::init()?;
 
 		Ok(Self {
 			last_tick: Instant::now(),
@@ -48,6 +58,7 @@ impl GLClient {
 
 			state: ClientState::new(engine.clone(), player_id, world),
 			voxel_models,
+			light_probes,
 			model_pack: ModelPack::new(engine.clone())?,
 			engine,
 		})
@@ -69,27 +80,62 @@ impl GLClient {
 
 	/// Handle draw request.
 	pub fn draw(&self, width: u32, height: u32) {
-		let camera = &self.state.local_player().camera();
+		let camera = &self.state.camera_player().camera();
+		if self.engine.begin_shadow_pass(camera.position) {
+			self.draw_shadow_casters(camera.position);
+			self.engine.end_shadow_pass();
+		}
 		self.engine.set_camera((width, height), camera);
 		self.engine.clear(0.8, 0.8, 1.0);
 
 		//glDisable(gl::SAMPLE_SHADING);
 
-		self.voxel_models.draw(&self.engine, camera);
+		self.voxel_models.draw(&self.engine, &self.state.world().map.voxels, camera);
 		self.draw_players(camera);
 		self.draw_entities(camera);
+		self.voxel_models.draw_translucent(&self.engine, &self.state.world().map.voxels, camera);
+		self.draw_zones();
 		self.draw_effects(camera);
 		self.engine.draw_crosshair();
 		self.state.hud().draw(&self.engine, &self.state.local_player());
 	}
 
+	// Draw everything that casts dynamic shadows near `center` (see `Engine::begin_shadow_pass`).
+	// This includes the camera player (as seen by others), whose own shadow is visible too.
+	fn draw_shadow_casters(&self, center: vec3) {
+		let nearby = |pos: vec3| (pos - center).len() < ShadowMap::RADIUS;
+		for (_, player) in self.state.world().players.iter() {
+			if player.spawned && nearby(player.position()) {
+				self.draw_player_3d_person(player)
+			}
+		}
+		for entity in self.state.world().entities.values() {
+			if nearby(entity.position) {
+				self.engine.draw_model_at(self.model_pack.entity_model(entity.kind), entity.position);
+			}
+		}
+	}
+
 	fn draw_entities(&self, camera: &Camera) {
 		for (_, entity) in &self.state.world().entities {
 			if camera.can_see(entity.position) {
 				let model = self.model_pack.entity_model(entity.kind);
+				self.engine.set_light(self.light_at(entity.position));
 				self.engine.draw_model_at(&model, entity.position);
 			}
 		}
+		self.engine.set_light(LightProbe::DEFAULT);
+	}
+
+	// Tint control zones (king-of-the-hill) in the color of their current owner.
+	// Translucent, so drawn after everything else in the world.
+	fn draw_zones(&self) {
+		let zones = &self.state.world().map.metadata.control_zones;
+		for (zone, owner) in zones.iter().zip(self.state.zones()) {
+			let color = owner.color(self.state.player_id());
+			self.engine.draw_boundingbox_with_color(zone.bounds(), color);
+			self.engine.draw_tinted_box(zone.bounds(), color, ZONE_ALPHA);
+		}
 	}
 
 	fn draw_effects(&self, camera: &Camera) {
@@ -133,15 +179,25 @@ impl GLClient {
 				continue;
 			}
 
-			if player.id == self.state.player_id() {
-				let sun_intens = self.sun_intensity_at(player.center());
-				self.draw_player_1st_person(player, sun_intens);
+			self.engine.set_light(self.light_at(player.center()));
+			if player.id == self.state.camera_player().id {
+				self.draw_player_1st_person(player);
 			} else {
 				if camera.can_see(player.position()) {
 					self.draw_player_3d_person(player)
 				}
 			}
 		}
+		self.engine.set_light(LightProbe::DEFAULT);
+	}
+
+	// Light falling on a dynamic model at `pos`:
+	// interpolated from the map's light probes if baked, else just sun or shade.
+	fn light_at(&self, pos: vec3) -> LightProbe {
+		self.light_probes.sample(pos).unwrap_or(LightProbe {
+			sun: self.sun_intensity_at(pos),
+			..LightProbe::DEFAULT
+		})
 	}
 
 	fn sun_intensity_at(&self, pos: vec3) -> f32 {
@@ -153,9 +209,9 @@ impl GLClient {
 		}
 	}
 
-	fn draw_player_1st_person(&self, player: &Player, sun_intens: f32) {
+	fn draw_player_1st_person(&self, player: &Player) {
 		let model = self.model_pack.player_model(player.avatar_id);
-		model.draw_1st_person(&self.engine, player, sun_intens);
+		model.draw_1st_person(&self.engine, player);
 		if DBG_GEOMETRY {
 			self.draw_line_of_fire(player);
 		}
@@ -169,6 +225,10 @@ impl GLClient {
 			let hat = self.model_pack.entity_model(hat);
 			model.draw_hat(&self.engine, player, hat)
 		}
+		if let Some(team) = player.flag {
+			let flag = self.model_pack.entity_model(EKind::Flag { team });
+			model.draw_carried_flag(&self.engine, player, flag)
+		}
 	}
 
 	fn draw_line_of_fire(&self, player: &Player) {
@@ -185,7 +245,11 @@ impl GLClient {
 	#[must_use]
 	pub fn tick_and_diff(&mut self) -> ClientMsgs {
 		let dt = self.update_dt();
-		let upd = self.state.tick(&self.input_state, dt.as_secs_f32());
+		let upd = self.state.tick(&mut self.input_state, dt.as_secs_f32());
+		for range in self.state.take_dirty_voxels() {
+			self.voxel_models.update(&self.state.world().map.voxels, &range);
+		}
+		self.voxel_models.receive(&self.engine);
 		self.input_state.clear(); // must be last
 		upd
 	}
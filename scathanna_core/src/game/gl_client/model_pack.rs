@@ -25,10 +25,14 @@ impl ModelPack {
 	fn load_entity_models(engine: &Engine) -> Result<HashMap<String, Model>> {
 		let mut models = HashMap::default();
 
-		for kind in EKind::ALL_EKINDS {
-			let obj_file = kind.as_str();
-			let model = Model::new(engine.wavefront_obj_scaled(obj_file, 3.0)?, Material::Glossy(engine.texture(obj_file, GREY))).double_sided();
-			models.insert(obj_file.to_owned(), model);
+		for kind in EKind::all_ekinds() {
+			let name = kind.as_str();
+			let model = Model::new(
+				engine.wavefront_obj_scaled(kind.obj_name(), kind.model_scale())?,
+				Material::Glossy(engine.texture(name, kind.fallback_color())),
+			)
+			.double_sided();
+			models.insert(name.to_owned(), model);
 		}
 		Ok(models)
 	}
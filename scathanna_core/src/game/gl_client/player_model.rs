@@ -46,15 +46,26 @@ pub struct PlayerModel {
 	head: Rc<VertexArray>,
 	foot: Rc<VertexArray>,
 	texture: Rc<Texture>,
-	gun: (Rc<VertexArray>, Rc<Texture>),
+	guns: [(Rc<VertexArray>, Rc<Texture>); NUM_WEAPONS], // indexed by `Weapon::index`
 	head_height: f32,
 	head_scale: f32,
 	foot_scale: f32,
 	foot_sep: f32,
 }
 
-fn gun(engine: &Engine) -> Result<(Rc<VertexArray>, Rc<Texture>)> {
-	Ok((engine.wavefront_obj("bubblegun")?, engine.texture("party_hat", YELLOW)))
+fn gun(engine: &Engine, weapon: Weapon) -> Result<(Rc<VertexArray>, Rc<Texture>)> {
+	Ok((engine.wavefront_obj(weapon.obj_name())?, engine.texture(weapon.texture_name(), weapon.fallback_color())))
+}
+
+fn guns(engine: &Engine) -> Result<[(Rc<VertexArray>, Rc<Texture>); NUM_WEAPONS]> {
+	use Weapon::*;
+	Ok([
+		gun(engine, BubbleGun)?,
+		gun(engine, ConfettiBlaster)?,
+		gun(engine, LaserRifle)?,
+		gun(engine, CarrotLauncher)?,
+		gun(engine, ConfettiGrenade)?,
+	])
 }
 
 impl PlayerModel {
@@ -63,7 +74,7 @@ impl PlayerModel {
 			head: engine.wavefront_obj("froghead")?,
 			foot: engine.wavefront_obj("frogfoot")?,
 			texture: engine.texture("frog", GREEN),
-			gun: gun(engine)?,
+			guns: guns(engine)?,
 			head_height: 2.0,
 			head_scale: 4.0,
 			foot_scale: 2.5,
@@ -76,8 +87,8 @@ impl PlayerModel {
 			head: engine.wavefront_obj("pandahead")?,
 			foot: engine.wavefront_obj("frogfoot")?,
 			texture: engine.texture("panda", WHITE),
-			gun: gun(engine)?,
-			head_height: 1.5,
+			guns: guns(engine)?,
+			head_height: Player::HEAD_HEIGHT,
 			head_scale: 4.2,
 			foot_scale: 1.6,
 			foot_sep: 0.05,
@@ -89,8 +100,8 @@ impl PlayerModel {
 			head: engine.wavefront_obj("pighead")?,
 			foot: engine.wavefront_obj("simple_foot")?,
 			texture: engine.texture("pig", WHITE),
-			gun: gun(engine)?,
-			head_height: 1.5,
+			guns: guns(engine)?,
+			head_height: Player::HEAD_HEIGHT,
 			head_scale: 4.2,
 			foot_scale: 1.6,
 			foot_sep: 0.05,
@@ -102,8 +113,8 @@ impl PlayerModel {
 			head: engine.wavefront_obj("turkeyhead")?,
 			foot: engine.wavefront_obj("chickenleg")?,
 			texture: engine.texture("turkey", WHITE),
-			gun: gun(engine)?,
-			head_height: 1.5,
+			guns: guns(engine)?,
+			head_height: Player::HEAD_HEIGHT,
 			head_scale: 4.2,
 			foot_scale: 3.0,
 			foot_sep: 0.05,
@@ -115,8 +126,8 @@ impl PlayerModel {
 			head: engine.wavefront_obj("hamsterhead")?,
 			foot: engine.wavefront_obj("simple_foot")?,
 			texture: engine.texture("hamster", WHITE),
-			gun: gun(engine)?,
-			head_height: 1.5,
+			guns: guns(engine)?,
+			head_height: Player::HEAD_HEIGHT,
 			head_scale: 4.2,
 			foot_scale: 1.6,
 			foot_sep: 0.05,
@@ -128,8 +139,8 @@ impl PlayerModel {
 			head: engine.wavefront_obj("chickenhead")?,
 			foot: engine.wavefront_obj("chickenleg")?,
 			texture: engine.texture("chicken", WHITE),
-			gun: gun(engine)?,
-			head_height: 1.5,
+			guns: guns(engine)?,
+			head_height: Player::HEAD_HEIGHT,
 			head_scale: 4.2,
 			foot_scale: 2.8,
 			foot_sep: 0.05,
@@ -141,8 +152,8 @@ impl PlayerModel {
 			head: engine.wavefront_obj("bunnyhead")?,
 			foot: engine.wavefront_obj("simple_foot")?,
 			texture: engine.texture("bunny", WHITE),
-			gun: gun(engine)?,
-			head_height: 1.5,
+			guns: guns(engine)?,
+			head_height: Player::HEAD_HEIGHT,
 			head_scale: 4.2,
 			foot_scale: 1.6,
 			foot_sep: 0.05,
@@ -153,7 +164,7 @@ impl PlayerModel {
 	pub fn draw_3rd_person(&self, engine: &Engine, player: &Player) {
 		self.draw_head(engine, player);
 		self.draw_feet(engine, player);
-		self.draw_gun(engine, player, 1.0 /* sun_intens*/);
+		self.draw_gun(engine, player);
 
 		if DBG_GEOMETRY {
 			engine.draw_boundingbox(player.skeleton.bounds());
@@ -161,12 +172,12 @@ impl PlayerModel {
 	}
 
 	/// Draw player model as seen by self.
-	pub fn draw_1st_person(&self, engine: &Engine, player: &Player, sun_intens: f32) {
+	pub fn draw_1st_person(&self, engine: &Engine, player: &Player) {
 		self.draw_feet(engine, player);
-		self.draw_gun(engine, player, sun_intens);
+		self.draw_gun(engine, player);
 	}
 
-	fn draw_gun(&self, engine: &Engine, player: &Player, sun_intens: f32) {
+	fn draw_gun(&self, engine: &Engine, player: &Player) {
 		let scale_mat = scale_matrix(4.5);
 		let Orientation { yaw, pitch } = player.orientation();
 		let pitch_mat = pitch_matrix(-pitch);
@@ -175,30 +186,27 @@ impl PlayerModel {
 		let pos_mat = translation_matrix(player.position());
 
 		let transf = &pos_mat * &yaw_mat * &hand_mat * &pitch_mat * &scale_mat;
-		let ambient = 0.3;
 
-		engine.use_texture(&self.gun.1);
-		engine.shaders().use_glossy(engine.sun_direction(), sun_intens, ambient, &transf);
-		engine.draw_triangles(&self.gun.0);
+		let (gun, texture) = &self.guns[player.weapon.index()];
+		engine.use_texture(texture);
+		engine.shaders().use_glossy(engine.sun_direction(), &engine.light(), &transf);
+		engine.draw_triangles(gun);
 	}
 
 	fn draw_head(&self, engine: &Engine, player: &Player) {
 		let Orientation { yaw, pitch } = player.orientation();
-		let head_pos = self.head_height * vec3::EY;
+		let head_pos = self.head_pos(player) * vec3::EY;
 		let transf = translation_matrix(player.position() + head_pos) * yaw_matrix(-yaw) * pitch_matrix(-pitch * HEAD_PITCH_FACTOR) * scale_matrix(self.head_scale);
 
-		let ambient = 0.3;
-		let sun_intens = 1.0;
-
 		engine.use_texture(&self.texture);
-		engine.shaders().use_glossy(engine.sun_direction(), sun_intens, ambient, &transf);
+		engine.shaders().use_glossy(engine.sun_direction(), &engine.light(), &transf);
 		engine.draw_triangles(&self.head);
 	}
 
 	pub fn draw_hat(&self, engine: &Engine, player: &Player, hat: &Model) {
 		let Orientation { yaw, pitch } = player.orientation();
 		let pitch_mat = pitch_matrix(-pitch * HEAD_PITCH_FACTOR);
-		let top_mat = translation_matrix((self.head_height + 0.75 * self.head_scale) * vec3::EY);
+		let top_mat = translation_matrix((self.head_pos(player) + 0.75 * self.head_scale) * vec3::EY);
 
 		let yaw_mat = yaw_matrix(-yaw);
 		let pos_mat = translation_matrix(player.position());
@@ -206,6 +214,15 @@ impl PlayerModel {
 		engine.draw_model_with(hat, &transf);
 	}
 
+	// Draw a capture-the-flag flag sticking out behind the player's back.
+	pub fn draw_carried_flag(&self, engine: &Engine, player: &Player, flag: &Model) {
+		let back_mat = translation_matrix(vec3(0.0, 0.5 * self.head_pos(player), 0.5 * player.skeleton.hsize));
+		let yaw_mat = yaw_matrix(-player.orientation().yaw);
+		let pos_mat = translation_matrix(player.position());
+		let transf = pos_mat * yaw_mat * back_mat;
+		engine.draw_model_with(flag, &transf);
+	}
+
 	fn draw_feet(&self, engine: &Engine, player: &Player) {
 		let scale_mat = scale_matrix(self.foot_scale);
 		let pitch_mat = pitch_matrix(player.local.feet_pitch);
@@ -216,25 +233,29 @@ impl PlayerModel {
 		let transf_l = &pos_mat * &yaw_mat * &left_mat * &pitch_mat * &scale_mat;
 		let transf_r = &pos_mat * &yaw_mat * &right_mat * &pitch_mat * &scale_mat;
 
-		let ambient = 0.3;
-		let sun_intens = 1.0;
-
 		engine.use_texture(&self.texture);
 
-		engine.shaders().use_glossy(engine.sun_direction(), sun_intens, ambient, &transf_l);
+		engine.shaders().use_glossy(engine.sun_direction(), &engine.light(), &transf_l);
 		engine.draw_triangles(&self.foot);
 
-		engine.shaders().use_glossy(engine.sun_direction(), sun_intens, ambient, &transf_r);
+		engine.shaders().use_glossy(engine.sun_direction(), &engine.light(), &transf_r);
 		engine.draw_triangles(&self.foot);
 	}
 
+	// Height of the head above the ground. Lower when crouching.
+	fn head_pos(&self, player: &Player) -> f32 {
+		self.head_height - player.crouch_depth()
+	}
+
 	fn feet_pos_internal(&self, player: &Player) -> [vec3; 2] {
 		let anim_r = 1.0;
 		let c = anim_r * player.local.feet_phase.cos();
 		let s = anim_r * player.local.feet_phase.sin();
+		// crouching: feet wide apart, to make room for the head.
+		let spread = if player.is_crouching() { 0.5 } else { 0.35 };
 		[
-			vec3(-0.35 * player.skeleton.hsize, f32::max(0.0, s), c) - self.foot_sep * vec3::EX,
-			vec3(0.35 * player.skeleton.hsize, f32::max(0.0, -s), -c) + self.foot_sep * vec3::EX,
+			vec3(-spread * player.skeleton.hsize, f32::max(0.0, s), c) - self.foot_sep * vec3::EX,
+			vec3(spread * player.skeleton.hsize, f32::max(0.0, -s), -c) + self.foot_sep * vec3::EX,
 		]
 	}
 }
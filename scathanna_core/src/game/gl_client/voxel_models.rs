@@ -2,6 +2,13 @@ use super::internal::*;
 
 pub struct VoxelModels {
 	cell_models: HashMap<ivec3, Vec<Model>>,
+
+	// Re-meshes cells changed during the game (destructible voxels).
+	// Started on the first change.
+	bakery: Option<Bakery>,
+
+	// Cells hidden behind walls (if enabled, see Config::occlusion_culling).
+	occlusion: Occlusion,
 }
 
 impl VoxelModels {
@@ -22,35 +29,85 @@ impl VoxelModels {
 			}
 		}
 
-		Ok(Self { cell_models })
+		Ok(Self {
+			cell_models,
+			bakery: None,
+			occlusion: default(),
+		})
 	}
 
-	pub fn draw(&self, engine: &Engine, camera: &Camera) {
-		engine.set_line_width(2.0);
-		let visible_range = VoxelWorld::visible_range(camera);
-		for pos in visible_range.iter_by(Voxels::CELL_SIZE) {
-			if let Some(models) = self.cell_models.get(&pos) {
-				if can_see_cell(camera, pos) {
-					for model in models {
-						engine.draw_model(model);
-					}
+	/// Re-build the models affected by a change of `range` (already applied to `voxels`).
+	/// Uses fast (low-quality) lightmaps, like the editor.
+	/// The new models appear after `receive`.
+	pub fn update(&mut self, voxels: &Voxels, range: &Cuboid) {
+		self.occlusion.invalidate();
+		let bakery = self.bakery.get_or_insert_with(|| Bakery::new(voxels));
+
+		// grow range by 1 voxel so that immediately adjacent cells are updated too.
+		let range = Cuboid::new(range.min - ivec3(1, 1, 1), range.max + ivec3(1, 1, 1));
+		for pos in Voxels::aligned_hull(&range).iter_by(Voxels::CELL_SIZE) {
+			match voxels.cell_at(pos) {
+				// blown away entirely: nothing left to bake.
+				Some(cell) if matches!(*cell, Node::Uniform(v) if v.is_empty()) => {
+					self.cell_models.remove(&pos);
+				}
+				Some(cell) => {
+					bakery.update_cell(pos, cell);
+					bakery.request_vao(pos);
 				}
+				None => (),
 			}
 		}
 	}
-}
 
-fn can_see_cell(camera: &Camera, cell_pos: ivec3) -> bool {
-	const CS: i32 = Voxels::CELL_SIZE as i32;
-	for dx in [0, CS] {
-		for dy in [0, CS] {
-			for dz in [0, CS] {
-				let pos = cell_pos + ivec3(dx, dy, dz);
-				if camera.can_see(pos.to_f32()) {
-					return true;
+	/// Swap in freshly re-built models, if any.
+	pub fn receive(&mut self, engine: &Engine) {
+		if let Some(bakery) = &mut self.bakery {
+			while let Some(models) = bakery.try_recv() {
+				for (pos, buffer) in models {
+					self.cell_models.insert(pos, VoxelWorld::upload_cell_models(engine, buffer));
 				}
 			}
 		}
 	}
-	false
+
+	/// Draw the opaque models (everything but water).
+	pub fn draw(&self, engine: &Engine, voxels: &Voxels, camera: &Camera) {
+		engine.set_line_width(2.0);
+		self.draw_filtered(engine, voxels, camera, false)
+	}
+
+	/// Draw the translucent models (water), blended over what's behind them.
+	/// Must be called after everything else in the world has been drawn.
+	pub fn draw_translucent(&self, engine: &Engine, voxels: &Voxels, camera: &Camera) {
+		engine.set_translucent(true);
+		self.draw_filtered(engine, voxels, camera, true);
+		engine.set_translucent(false);
+	}
+
+	fn draw_filtered(&self, engine: &Engine, voxels: &Voxels, camera: &Camera, translucent: bool) {
+		let mut cells = self.visible_cells(engine, voxels, camera);
+		if translucent {
+			// back to front, so that nearer water is blended over farther water.
+			let dist2 = |pos: ivec3| (VoxelWorld::cell_bounds(pos).center() - camera.position).len2();
+			cells.sort_by(|&a, &b| dist2(b).partial_cmp(&dist2(a)).unwrap());
+		}
+		for pos in cells {
+			for model in self.cell_models[&pos].iter().filter(|m| m.material.is_translucent() == translucent) {
+				engine.draw_model(model);
+			}
+		}
+	}
+
+	// Positions of the cells that we have models for, in view of the camera.
+	fn visible_cells(&self, engine: &Engine, voxels: &Voxels, camera: &Camera) -> Vec<ivec3> {
+		let visible_range = VoxelWorld::visible_range(camera, engine.view_distance());
+		let frustum = engine.frustum(camera);
+		visible_range
+			.iter_by(Voxels::CELL_SIZE)
+			.filter(|pos| self.cell_models.contains_key(pos))
+			.filter(|&pos| frustum.intersects(&VoxelWorld::cell_bounds(pos)))
+			.filter(|&pos| !(engine.occlusion_culling() && self.occlusion.is_hidden(voxels, camera.position, pos)))
+			.collect()
+	}
 }
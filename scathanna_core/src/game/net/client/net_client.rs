@@ -40,7 +40,7 @@ impl NetClient {
 		println!("connection accepted, joining...");
 
 		let avatar_id = parse_avatar_id(&opts.avatar)?;
-		let team = if opts.team.is_empty() { Team::random() } else { Team::from_str(&opts.team)? };
+		let team = if opts.team.is_empty() { None } else { Some(Team::from_str(&opts.team)?) };
 
 		serialize_into(
 			&mut tcp_stream,
@@ -48,6 +48,7 @@ impl NetClient {
 				name: opts.name.clone(),
 				avatar_id,
 				team,
+				assets_hash: JoinMsg::assets_hash(),
 			},
 		)?;
 		tcp_stream.flush()?;
@@ -60,6 +61,7 @@ impl NetClient {
 				players,
 				entities,
 			} => (map_name, player_id, players, entities),
+			ServerMsg::JoinRefused(reason) => return Err(anyhow!("server refused to join: {}", reason)),
 			_ => return Err(anyhow!("expected initial server message to be SwitchMap")),
 		};
 		println!("accepted as #{}", player_id);
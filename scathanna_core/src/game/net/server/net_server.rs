@@ -89,7 +89,14 @@ impl NetServer {
 		let join_msg: JoinMsg = wireformat::deserialize_from(&mut tcp_stream)?;
 
 		// add player to server game state.
-		let player_id = self.state.join_new_player(join_msg);
+		let player_id = match self.state.join_new_player(join_msg) {
+			Ok(player_id) => player_id,
+			Err(e) => {
+				// tell the client why, instead of just hanging up.
+				wireformat::serialize_into(&mut tcp_stream, &ServerMsg::JoinRefused(e.to_string()))?;
+				return Err(e);
+			}
+		};
 
 		// send "accepted" message with map info and player ID
 		//serialize_into(&mut BufWriter::new(&mut tcp_stream), &accepted)?;
@@ -1,6 +1,6 @@
 use super::internal::*;
 
-const MAGIC: u64 = 0xff53434154480003;
+const MAGIC: u64 = 0xff53434154480017;
 
 pub fn serialize_into<W, T>(mut w: W, msg: &T) -> Result<()>
 where
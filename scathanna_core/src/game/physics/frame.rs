@@ -5,4 +5,5 @@ pub struct Frame {
 	pub position: vec3,
 	pub velocity: vec3,
 	pub orientation: Orientation,
+	pub vsize: f32, // changes when crouching
 }
@@ -2,3 +2,4 @@ pub use super::super::internal::*;
 
 pub use super::frame::*;
 pub use super::skeleton::*;
+pub use super::sweep::*;
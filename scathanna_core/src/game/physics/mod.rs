@@ -2,6 +2,10 @@ mod internal;
 
 mod frame;
 mod skeleton;
+mod sweep;
+mod timestep;
 
 pub use frame::*;
 pub use skeleton::*;
+pub use sweep::*;
+pub use timestep::*;
@@ -11,6 +11,21 @@ pub struct Skeleton {
 
 const G: f32 = 48.0; // TODO
 const STAIRCLIMB_SPEED: f32 = 15.0; // TODO
+const STEP_HEIGHT: f32 = 2.1; // highest obstacle walked onto without jumping
+
+// Swimming: gravity minus buoyancy (slow sinking), drag,
+// maximum sinking speed and horizontal speed relative to walking.
+const WATER_G: f32 = 8.0;
+const WATER_DRAG: f32 = 3.0;
+const MAX_SINK_SPEED: f32 = 6.0;
+const SWIM_SPEED: f32 = 0.6;
+
+// Walking on a voxel with friction f < 1 (see `Palette`) only changes velocity
+// at a rate f * SLIP_RATE per second (friction 1: instantly).
+const SLIP_RATE: f32 = 20.0;
+
+// Bounce off bouncy voxels only when landing faster than this.
+const MIN_BOUNCE_SPEED: f32 = 4.0;
 
 impl Skeleton {
 	pub fn new(pos: vec3, orientation: Orientation, hsize: f32, vsize: f32) -> Self {
@@ -23,63 +38,58 @@ impl Skeleton {
 		}
 	}
 
-	pub fn tick(&mut self, upd: &mut ClientMsgs, world: &World, dt: f32) {
+	/// Fall and move, colliding with the map and `obstacles` (e.g. other players, see GameRules).
+	pub fn tick(&mut self, upd: &mut ClientMsgs, world: &World, obstacles: &[BoundingBox<f32>], dt: f32) {
 		let v1 = self.velocity.y();
-		self.tick_gravity(G, dt);
-		self.tick_move(world, dt);
+		match self.is_in_water(world) {
+			true => self.tick_buoyancy(dt),
+			false => self.tick_gravity(G, dt),
+		}
+		self.tick_move(world, obstacles, dt);
 		let v2 = self.velocity.y();
 		if v1 < -1.0 && v2 == 0.0 {
-			upd.push(ClientMsg::PlaySound(SoundEffect::spatial("land", self.position, 0.3)));
+			// harder landings sound louder
+			let volume = f32::min(1.0, 0.3 * (-v1 / Player::JUMP_SPEED));
+			upd.push(ClientMsg::PlaySound(SoundEffect::spatial("land", self.position, volume)));
+			let bounce = -v1 * self.ground_voxel(world).def().bounciness;
+			if bounce > MIN_BOUNCE_SPEED {
+				self.velocity[Y] = bounce;
+			}
 		}
 		self.tick_rescue(world, dt);
 	}
 
-	fn tick_move(&mut self, world: &World, dt: f32) {
-		let mut xbump = false;
-		let mut zbump = false;
+	fn tick_move(&mut self, world: &World, obstacles: &[BoundingBox<f32>], dt: f32) {
 		let delta = self.velocity * dt;
-		let sub_delta = delta / 16.0;
-
-		for _i in 0..16 {
-			let dx = vec3(sub_delta.x(), 0.0, 0.0);
-			let dy = vec3(0.0, sub_delta.y(), 0.0);
-			let dz = vec3(0.0, 0.0, sub_delta.z());
-
-			if self.pos_ok(world, self.position + dx) {
-				self.position += dx;
-			} else {
-				xbump = true;
-			}
-
-			if self.pos_ok(world, self.position + dy) {
-				self.position += dy;
-			} else {
-				self.velocity[Y] = 0.0;
-			}
-
-			if self.pos_ok(world, self.position + dz) {
-				self.position += dz;
-			} else {
-				zbump = true;
+		let (mut moved, mut touched) = slide(&world.map.voxels, obstacles, &self.bounds(), delta);
+
+		// stair climbing: blocked sideways while walking (or jumping), try stepping on top of the obstacle.
+		let walking = touched.contains(&Direction::Y) || self.velocity.y() >= 0.0;
+		if walking && touched.iter().any(|normal| normal.unoriented_axis() != Y) {
+			let (stepped, stepped_touched) = self.step_up(world, obstacles, delta);
+			if stepped.remove(Y).len() > moved.remove(Y).len() + SKIN {
+				moved = stepped;
+				touched = stepped_touched;
 			}
 		}
 
-		// stair climbing
-		if (xbump || zbump) && self.velocity.y() >= 0.0 {
-			let probe_pos = self.position + vec3(delta.x(), 2.1, delta.z()); // what if we kept moving horizontally and took one step up?
-			if self.pos_ok(world, probe_pos) {
-				self.position += vec3(delta.x(), 0.0, delta.z());
-			} else {
-				if xbump {
-					self.velocity[X] = 0.0;
-				}
-				if zbump {
-					self.velocity[Z] = 0.0;
-				}
-			}
+		self.position += moved;
+		for normal in touched {
+			self.velocity[normal.unoriented_axis()] = 0.0;
 		}
 	}
 
+	// Motion when first rising by STEP_HEIGHT (as far as the ceiling allows),
+	// then moving horizontally, then descending onto whatever is below.
+	fn step_up(&self, world: &World, obstacles: &[BoundingBox<f32>], delta: vec3) -> (vec3, Vec<Direction>) {
+		let voxels = &world.map.voxels;
+		let (up, _) = slide(voxels, obstacles, &self.bounds(), vec3(0.0, STEP_HEIGHT, 0.0));
+		let (across, mut touched) = slide(voxels, obstacles, &self.bounds_for(self.position + up), vec3(delta.x(), 0.0, delta.z()));
+		let (down, landed) = slide(voxels, obstacles, &self.bounds_for(self.position + up + across), -up);
+		touched.extend(landed);
+		(up + across + down, touched)
+	}
+
 	// rescue player if somehow stuck inside a block: move them up.
 	fn tick_rescue(&mut self, world: &World, dt: f32) {
 		if !self.pos_ok(world, self.position) {
@@ -91,6 +101,22 @@ impl Skeleton {
 		!self.pos_ok(world, self.position - vec3(0.0, 0.05, 0.0))
 	}
 
+	/// Swimming? (I.e. the center of the body is inside water, or another non-solid voxel).
+	pub fn is_in_water(&self, world: &World) -> bool {
+		world.map.voxels.at_pos(self.bounds().center()).is_transparent()
+	}
+
+	/// Speed when hitting the ground after falling from `height` (not accounting for air drag).
+	pub fn landing_speed(height: f32) -> f32 {
+		f32::sqrt(2.0 * G * f32::max(0.0, height))
+	}
+
+	/// The voxel right below our feet (empty if not standing on anything).
+	pub fn ground_voxel(&self, world: &World) -> VoxelType {
+		let probe = self.position - 0.2 * vec3::EY;
+		world.map.voxels.at(probe.floor())
+	}
+
 	// _________________________________________________________ mutators
 
 	pub fn try_jump(&mut self, world: &World, jump_speed: f32) -> bool {
@@ -111,8 +137,13 @@ impl Skeleton {
 		const AIRCTL_ACCEL: f32 = 2.0;
 
 		if self.on_ground(world) {
-			self.velocity[X] = walk_speed[X];
-			self.velocity[Z] = walk_speed[Z];
+			let friction = self.ground_voxel(world).def().friction;
+			let grip = if friction >= 1.0 { 1.0 } else { f32::min(1.0, friction * SLIP_RATE * dt) };
+			self.velocity[X] += grip * (walk_speed[X] - self.velocity[X]);
+			self.velocity[Z] += grip * (walk_speed[Z] - self.velocity[Z]);
+		} else if self.is_in_water(world) {
+			self.velocity[X] = SWIM_SPEED * walk_speed[X];
+			self.velocity[Z] = SWIM_SPEED * walk_speed[Z];
 		} else {
 			// flying through the air
 
@@ -134,6 +165,7 @@ impl Skeleton {
 		self.position = frame.position;
 		self.velocity = frame.velocity;
 		self.orientation = frame.orientation;
+		self.vsize = frame.vsize;
 	}
 
 	//______________________________________________________________________ accessors
@@ -143,6 +175,7 @@ impl Skeleton {
 			position: self.position,
 			velocity: self.velocity,
 			orientation: self.orientation,
+			vsize: self.vsize,
 		}
 	}
 
@@ -163,6 +196,13 @@ impl Skeleton {
 		self.bounds_for(self.position)
 	}
 
+	// Under water: sink slowly, movement is strongly damped.
+	fn tick_buoyancy(&mut self, dt: f32) {
+		self.velocity[Y] -= WATER_G * dt;
+		self.velocity *= 1.0 - f32::min(1.0, WATER_DRAG * dt);
+		self.velocity[Y] = f32::max(self.velocity[Y], -MAX_SINK_SPEED);
+	}
+
 	fn tick_gravity(&mut self, g: f32, dt: f32) {
 		self.velocity[Y] -= g * dt;
 		let damp = 0.05;
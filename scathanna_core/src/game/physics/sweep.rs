@@ -0,0 +1,243 @@
+use super::internal::*;
+
+/// Where a box moving through the voxel world first touches a solid voxel (or another box).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Contact {
+	/// Fraction of the motion completed before touching (0..=1).
+	pub t: f32,
+
+	/// Face of the voxel (or box) that was hit, pointing back at the moving box.
+	pub normal: Direction,
+}
+
+/// Gap left between a box and the voxel it stopped against,
+/// so that rounding errors never leave the box overlapping (and tunneling through) the voxel.
+pub const SKIN: f32 = 1.0 / 512.0;
+
+/// Sweep `bounds` by `delta`: when (if at all) does it hit a solid voxel or one of the `obstacles`
+/// (e.g. other players)?
+///
+/// Voxels and obstacles that `bounds` already overlaps are ignored, so that a box stuck inside a wall can get out.
+pub fn sweep(voxels: &Voxels, obstacles: &[BoundingBox<f32>], bounds: &BoundingBox<f32>, delta: vec3) -> Option<Contact> {
+	let reach = bounds.join(&translate(bounds, delta));
+	let imin = reach.min.map(f32::floor).floor();
+	let imax = reach.max.map(f32::ceil).floor() - ivec3(1, 1, 1);
+
+	let mut first: Option<Contact> = None;
+	let mut hit = |contact: Option<Contact>| {
+		if let Some(contact) = contact {
+			if first.map(|first| contact.t < first.t).unwrap_or(true) {
+				first = Some(contact)
+			}
+		}
+	};
+	for iz in imin.z()..=imax.z() {
+		for iy in imin.y()..=imax.y() {
+			for ix in imin.x()..=imax.x() {
+				let pos = ivec3(ix, iy, iz);
+				if !voxels.at(pos).is_solid() {
+					continue;
+				}
+				let voxel = BoundingBox::new(pos.to_f32(), (pos + ivec3(1, 1, 1)).to_f32());
+				hit(sweep_box(bounds, delta, &voxel));
+			}
+		}
+	}
+	for obstacle in obstacles {
+		hit(sweep_box(bounds, delta, obstacle));
+	}
+	first
+}
+
+/// Move `bounds` by `delta`, sliding along whatever solid voxels or obstacles are in the way.
+/// Returns the distance actually moved, and the faces that were slid along (at most 3).
+pub fn slide(voxels: &Voxels, obstacles: &[BoundingBox<f32>], bounds: &BoundingBox<f32>, delta: vec3) -> (vec3, Vec<Direction>) {
+	let mut moved = vec3::ZERO;
+	let mut remaining = delta;
+	let mut touched = Vec::new();
+
+	// each contact blocks one more axis, so after 3 contacts there is nowhere left to go.
+	for _ in 0..3 {
+		if remaining == vec3::ZERO {
+			break;
+		}
+		match sweep(voxels, obstacles, &translate(bounds, moved), remaining) {
+			None => {
+				moved += remaining;
+				break;
+			}
+			Some(Contact { t, normal }) => {
+				let axis = normal.unoriented_axis();
+				let mut step = t * remaining;
+				// stop SKIN away from the face (but never back up to before where we started).
+				step[axis] = if step[axis].abs() > SKIN { step[axis] + normal.vec()[axis] * SKIN } else { 0.0 };
+				moved += step;
+				remaining = (1.0 - t) * remaining;
+				remaining[axis] = 0.0;
+				touched.push(normal);
+			}
+		}
+	}
+	(moved, touched)
+}
+
+// Time of impact of box `a` moving by `delta` with static box `b`
+// (None if they do not touch during the motion, or already overlap).
+fn sweep_box(a: &BoundingBox<f32>, delta: vec3, b: &BoundingBox<f32>) -> Option<Contact> {
+	let mut entry = f32::NEG_INFINITY;
+	let mut exit = f32::INFINITY;
+	let mut normal = None;
+
+	for (axis, (minus, plus)) in [(Direction::MinusX, Direction::X), (Direction::MinusY, Direction::Y), (Direction::MinusZ, Direction::Z)]
+		.into_iter()
+		.enumerate()
+	{
+		let d = delta[axis];
+		let (t_entry, t_exit, face) = if d > 0.0 {
+			((b.min[axis] - a.max[axis]) / d, (b.max[axis] - a.min[axis]) / d, minus)
+		} else if d < 0.0 {
+			((b.max[axis] - a.min[axis]) / d, (b.min[axis] - a.max[axis]) / d, plus)
+		} else if a.max[axis] > b.min[axis] && a.min[axis] < b.max[axis] {
+			// not moving along this axis, but overlapping on it.
+			continue;
+		} else {
+			return None;
+		};
+		if t_entry > entry {
+			entry = t_entry;
+			normal = Some(face);
+		}
+		exit = f32::min(exit, t_exit);
+	}
+
+	match normal {
+		Some(normal) if entry < exit && (0.0..=1.0).contains(&entry) => Some(Contact { t: entry, normal }),
+		_ => None,
+	}
+}
+
+fn translate(bounds: &BoundingBox<f32>, delta: vec3) -> BoundingBox<f32> {
+	BoundingBox::new(bounds.min + delta, bounds.max + delta)
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	// A 2x4x2 box standing with its center bottom at `pos`.
+	fn body(pos: vec3) -> BoundingBox<f32> {
+		BoundingBox::new(pos - vec3(1.0, 0.0, 1.0), pos + vec3(1.0, 4.0, 1.0))
+	}
+
+	// Floor at y < 8, everything else empty.
+	fn floor() -> Voxels {
+		let mut voxels = Voxels::new();
+		voxels.set_range(&Cuboid::new(ivec3(0, 0, 0), ivec3(64, 8, 64)), VoxelType(5));
+		voxels
+	}
+
+	#[test]
+	fn fall_onto_floor() {
+		let voxels = floor();
+		let contact = sweep(&voxels, &[], &body(vec3(10.5, 12.0, 10.5)), vec3(0.0, -8.0, 0.0)).unwrap();
+		assert_eq!(contact, Contact { t: 0.5, normal: Direction::Y });
+
+		// missing the floor altogether.
+		assert_eq!(sweep(&voxels, &[], &body(vec3(10.5, 12.0, 10.5)), vec3(0.0, -3.0, 0.0)), None);
+	}
+
+	#[test]
+	fn high_velocity_fall() {
+		// falling 1000 units in one step must not tunnel through a floor 1 voxel thick.
+		let mut voxels = Voxels::new();
+		voxels.set_range(&Cuboid::new(ivec3(0, 8, 0), ivec3(64, 9, 64)), VoxelType(5));
+		let (moved, touched) = slide(&voxels, &[], &body(vec3(10.5, 500.0, 10.5)), vec3(3.0, -1000.0, 0.0));
+		assert_eq!(touched, vec![Direction::Y]);
+		let y = 500.0 + moved.y();
+		assert!((9.0..9.01).contains(&y), "{}", y);
+		// horizontal motion continues after landing.
+		assert_eq!(moved.x(), 3.0);
+	}
+
+	#[test]
+	fn slide_along_wall() {
+		let mut voxels = floor();
+		voxels.set_range(&Cuboid::new(ivec3(20, 8, 0), ivec3(21, 16, 64)), VoxelType(5));
+
+		// walking diagonally into the wall: blocked in x, sliding in z.
+		let start = vec3(18.5, 8.0, 10.0);
+		let (moved, touched) = slide(&voxels, &[], &body(start), vec3(2.0, 0.0, 2.0));
+		assert_eq!(touched, vec![Direction::MinusX]);
+		assert!(start.x() + moved.x() <= 19.0 && start.x() + moved.x() > 18.99);
+		assert_eq!(moved.z(), 2.0);
+
+		// walking along the wall while touching it: not blocked.
+		let start = start + moved;
+		let (moved, touched) = slide(&voxels, &[], &body(start), vec3(0.0, 0.0, -5.0));
+		assert!(touched.is_empty());
+		assert_eq!(moved, vec3(0.0, 0.0, -5.0));
+	}
+
+	#[test]
+	fn corner() {
+		let mut voxels = floor();
+		// walls at x >= 20 and z >= 20.
+		voxels.set_range(&Cuboid::new(ivec3(20, 8, 0), ivec3(21, 16, 21)), VoxelType(5));
+		voxels.set_range(&Cuboid::new(ivec3(0, 8, 20), ivec3(21, 16, 21)), VoxelType(5));
+
+		// walking into the corner: stuck in the corner, touching both walls.
+		let start = vec3(18.5, 8.0, 18.5);
+		let (moved, touched) = slide(&voxels, &[], &body(start), vec3(4.0, 0.0, 3.0));
+		assert_eq!(touched.len(), 2);
+		assert!(touched.contains(&Direction::MinusX) && touched.contains(&Direction::MinusZ));
+		let end = start + moved;
+		assert!(end.x() <= 19.0 && end.x() > 18.99);
+		assert!(end.z() <= 19.0 && end.z() > 18.99);
+
+		// an outside corner is passed without getting snagged.
+		let mut voxels = floor();
+		voxels.set_range(&Cuboid::new(ivec3(20, 8, 20), ivec3(30, 16, 30)), VoxelType(5));
+		let (moved, touched) = slide(&voxels, &[], &body(vec3(18.5, 8.0, 10.0)), vec3(0.0, 0.0, 20.0));
+		assert!(touched.is_empty());
+		assert_eq!(moved.z(), 20.0);
+	}
+
+	#[test]
+	fn ceiling() {
+		let mut voxels = floor();
+		voxels.set_range(&Cuboid::new(ivec3(0, 14, 0), ivec3(64, 15, 64)), VoxelType(5));
+
+		// jumping into the ceiling: head stops just below it, horizontal motion continues.
+		let (moved, touched) = slide(&voxels, &[], &body(vec3(10.5, 8.0, 10.5)), vec3(1.0, 5.0, 0.0));
+		assert_eq!(touched, vec![Direction::MinusY]);
+		let head = 8.0 + moved.y() + 4.0;
+		assert!(head <= 14.0 && head > 13.99, "{}", head);
+		assert_eq!(moved.x(), 1.0);
+	}
+
+	#[test]
+	fn already_overlapping() {
+		// a box stuck inside the floor may move out of it.
+		let voxels = floor();
+		let (moved, touched) = slide(&voxels, &[], &body(vec3(10.5, 7.5, 10.5)), vec3(0.0, 1.0, 0.0));
+		assert!(touched.is_empty());
+		assert_eq!(moved, vec3(0.0, 1.0, 0.0));
+	}
+
+	#[test]
+	fn obstacles() {
+		// walking into another player: blocked just like by a wall.
+		let voxels = floor();
+		let other = body(vec3(20.0, 8.0, 10.0));
+		let (moved, touched) = slide(&voxels, std::slice::from_ref(&other), &body(vec3(15.0, 8.0, 10.0)), vec3(5.0, 0.0, 1.0));
+		assert_eq!(touched, vec![Direction::MinusX]);
+		let x = 15.0 + moved.x();
+		assert!(x <= 18.0 && x > 17.99, "{}", x);
+		assert_eq!(moved.z(), 1.0);
+
+		// overlapping another player (e.g. spawned on top of each other): free to walk apart.
+		let (moved, touched) = slide(&voxels, &[other], &body(vec3(19.0, 8.0, 10.0)), vec3(-5.0, 0.0, 0.0));
+		assert!(touched.is_empty());
+		assert_eq!(moved, vec3(-5.0, 0.0, 0.0));
+	}
+}
@@ -0,0 +1,55 @@
+use super::internal::*;
+
+/// Seconds per physics step. Movement is always simulated in steps of exactly this size,
+/// so that it does not depend on frame rate, and so that the server can re-play it
+/// step by step to arrive at exactly the same result as the client.
+pub const TIMESTEP: f32 = 1.0 / 120.0;
+
+// Don't try to catch up on more than this many steps after a long stall (e.g. loading a map).
+const MAX_STEPS: u32 = 30;
+
+/// Accumulates frame time (of varying length) into a number of fixed physics steps.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct FixedTimestep {
+	accumulator: f32,
+}
+
+impl FixedTimestep {
+	/// Add `dt` seconds of frame time, return the number of TIMESTEPs to simulate.
+	/// The remainder is carried over to the next frame.
+	pub fn advance(&mut self, dt: f32) -> u32 {
+		self.accumulator += dt;
+		let steps = (self.accumulator / TIMESTEP) as u32;
+		self.accumulator -= (steps as f32) * TIMESTEP;
+		if steps > MAX_STEPS {
+			self.accumulator = 0.0;
+			return MAX_STEPS;
+		}
+		steps
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn accumulate() {
+		let mut timestep = FixedTimestep::default();
+		assert_eq!(timestep.advance(0.4 * TIMESTEP), 0);
+		assert_eq!(timestep.advance(0.4 * TIMESTEP), 0);
+		assert_eq!(timestep.advance(0.4 * TIMESTEP), 1);
+		assert_eq!(timestep.advance(2.0 * TIMESTEP), 2);
+
+		// 1 second at any frame rate is (about) 1 second of physics.
+		for fps in [30, 60, 75, 144] {
+			let mut timestep = FixedTimestep::default();
+			let steps: u32 = (0..fps).map(|_| timestep.advance(1.0 / fps as f32)).sum();
+			assert!((119..=120).contains(&steps), "{} fps: {} steps", fps, steps);
+		}
+
+		// no spiral of death after a stall.
+		assert_eq!(timestep.advance(10.0), MAX_STEPS);
+		assert_eq!(timestep.advance(0.0), 0);
+	}
+}
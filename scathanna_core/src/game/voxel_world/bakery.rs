@@ -139,7 +139,14 @@ impl Worker {
 				}
 				to_ctrl.lock().unwrap().send(vec![(cell_pos, model)]).unwrap()
 			}
-		})
+		});
+
+		println!("Bakery: baking light probes");
+		let probes = LightProbes::bake(&self.voxels);
+		match probes.save(&dir) {
+			Ok(_) => println!("Bakery: saved light probes"),
+			Err(e) => println!("Bakery: ERROR saving light probes: {}", e),
+		}
 	}
 
 	fn backlog_is_empty(&self) -> bool {
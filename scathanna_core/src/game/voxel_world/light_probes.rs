@@ -0,0 +1,114 @@
+use super::internal::*;
+use rayon::prelude::*;
+
+/// A sparse 3D grid of `LightProbe`s, baked together with the lightmaps
+/// and used to light dynamic models (players, entities) depending on where they are.
+#[derive(Serialize, Deserialize, Default)]
+pub struct LightProbes {
+	// grid position (voxel position / SPACING) to probe.
+	// Absent where there is nothing to light (empty cells, inside solid voxels).
+	probes: HashMap<ivec3, LightProbe>,
+}
+
+impl LightProbes {
+	pub const FILE: &'static str = "light_probes.bincode.gz";
+
+	/// Distance between probes, in voxels.
+	pub const SPACING: i32 = 8;
+
+	/// Bake probes for all non-empty cells (expensive, done in parallel).
+	pub fn bake(voxels: &Voxels) -> Self {
+		let per_cell = Voxels::ICELL_SIZE / Self::SPACING;
+		let grid_points = voxels
+			.iter_cell_positions()
+			.flat_map(|cell_pos| Cuboid::cube(cell_pos / Self::SPACING, per_cell as u32).iter_by(1))
+			.filter(|&g| !voxels.at(g * Self::SPACING).is_solid())
+			.collect::<Vec<_>>();
+
+		let probes = grid_points.into_par_iter().map(|g| (g, bake_light_probe(voxels, Self::world_pos(g)))).collect::<Vec<_>>();
+		Self { probes: probes.into_iter().collect() }
+	}
+
+	/// Light at `pos`, interpolated between the surrounding probes.
+	/// `None` if there are no probes nearby (e.g. the map was never fully baked).
+	pub fn sample(&self, pos: vec3) -> Option<LightProbe> {
+		let grid = (pos - vec3(0.5, 0.5, 0.5)) / (Self::SPACING as f32);
+		let g0 = grid.floor();
+		let frac = grid - g0.to_f32();
+
+		let mut weighted = SmallVec::<(f32, LightProbe)>::new();
+		for corner in Cuboid::cube(ivec3(0, 0, 0), 2).iter_by(1) {
+			if let Some(&probe) = self.probes.get(&(g0 + corner)) {
+				let w = |axis: usize| if corner[axis] == 0 { 1.0 - frac[axis] } else { frac[axis] };
+				weighted.push((w(0) * w(1) * w(2), probe));
+			}
+		}
+
+		let total = weighted.iter().map(|(w, _)| w).sum::<f32>();
+		if total <= 0.0 {
+			return None;
+		}
+		weighted.iter_mut().for_each(|(w, _)| *w /= total);
+		Some(LightProbe::weighted_sum(&weighted))
+	}
+
+	// Center of the voxel where grid point `g` is located.
+	fn world_pos(g: ivec3) -> vec3 {
+		(g * Self::SPACING).to_f32() + vec3(0.5, 0.5, 0.5)
+	}
+
+	/// Serialize to `dir`/`FILE`, gzipped bincode.
+	pub fn save(&self, dir: &Path) -> Result<()> {
+		let gz = GzEncoder::new(create(&dir.join(Self::FILE))?, flate2::Compression::best());
+		Ok(bincode::serialize_into(gz, self)?)
+	}
+
+	/// Deserialize from `dir`/`FILE`.
+	pub fn load(dir: &Path) -> Result<Self> {
+		let gz = GzDecoder::new(open(&dir.join(Self::FILE))?);
+		Ok(bincode::deserialize_from(gz)?)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn bake_probe() {
+		let c = Voxels::ICELL_SIZE;
+		let mut voxels = Voxels::new();
+		// a floor covering the cell, with a roof over the x < c/2 half.
+		voxels.set_range(&Cuboid::new(ivec3(0, 0, 0), ivec3(c, 1, c)), VoxelType(5));
+		voxels.set_range(&Cuboid::new(ivec3(0, 16, 0), ivec3(c / 2, 17, c)), VoxelType(5));
+
+		let open_sky = bake_light_probe(&voxels, vec3(48.5, 8.5, 32.5));
+		let under_roof = bake_light_probe(&voxels, vec3(8.5, 8.5, 32.5));
+		assert!(open_sky.sun > 0.9);
+		assert!(under_roof.sun < 0.1);
+		assert!(open_sky.ambient_at(vec3::EY).x() > under_roof.ambient_at(vec3::EY).x());
+	}
+
+	#[test]
+	fn sample() {
+		let dark = LightProbe { sun: 0.0, ..LightProbe::uniform(0.0) };
+		let bright = LightProbe::uniform(1.0);
+		let probes = LightProbes {
+			probes: [(ivec3(0, 0, 0), dark), (ivec3(1, 0, 0), bright)].into_iter().collect(),
+		};
+
+		// at a probe.
+		assert_eq!(probes.sample(LightProbes::world_pos(ivec3(1, 0, 0))), Some(bright));
+
+		// halfway between two probes.
+		let mid = probes.sample(vec3(4.5, 0.5, 0.5)).unwrap();
+		assert!((mid.sun - 0.5).abs() < 1e-6);
+
+		// missing probes (e.g. inside walls) do not darken the result.
+		let above = probes.sample(vec3(8.5, 4.5, 0.5)).unwrap();
+		assert_eq!(above, bright);
+
+		// far away from any probe.
+		assert_eq!(probes.sample(vec3(1000.0, 1000.0, 1000.0)), None);
+	}
+}
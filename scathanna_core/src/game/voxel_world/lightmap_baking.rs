@@ -57,10 +57,9 @@ pub fn bake_face(voxels: &Voxels, img: &mut Image<Color>, lm_idx: uvec2, rect: &
 
 			if ix < img.width() && iy < img.height() {
 				// HACK in case lightmap is too small
-				*img.at_mut((ix, iy)) = match voxeltype {
-					VoxelType(2) => Color::WHITE,
-					VoxelType(10) => Color::WHITE,
-					_ => match quality {
+				*img.at_mut((ix, iy)) = match voxeltype.def().emissive {
+					Some(_) => Color::WHITE,
+					None => match quality {
 						false => bake_point_fast(voxels, world_pos, rect.direction),
 						true => bake_point_traced(voxels, world_pos, rect.direction),
 					},
@@ -88,6 +87,14 @@ pub fn bake_point_traced(voxels: &Voxels, pos: ivec3, normal: Direction) -> Colo
 	direct + ambient
 }
 
+/// Light arriving at `pos` from all directions, for lighting dynamic models (see LightProbes).
+pub fn bake_light_probe(voxels: &Voxels, pos: vec3) -> LightProbe {
+	let mut rng = rand::thread_rng();
+	let ambient = Direction::ALL.map(|dir| integrate(&mut rng, voxels, pos, dir, 32..256, 0.005, sample_ambient_light).into());
+	let sun = integrate(&mut rng, voxels, pos, Direction::Y, 16..64, 0.01, sample_sun_visibility).r();
+	LightProbe { ambient, sun }
+}
+
 fn integrate<F>(rng: &mut ThreadRng, voxels: &Voxels, pos: vec3, normal: Direction, n: Range<u32>, max_err: f64, f: F) -> Color
 where
 	F: Fn(&mut ThreadRng, u32, (f32, f32), &Voxels, vec3, Direction) -> Color,
@@ -111,15 +118,16 @@ fn sample_ambient_light(rng: &mut ThreadRng, i: u32, (sh1, sh2): (f32, f32), vox
 	let ray = Ray64::new(pos.into(), dir.into()).offset(0.01);
 
 	match voxels.intersect(&ray) {
-		None => Color::new(0.85, 0.85, 1.0),                   // sky
-		Some((VoxelType(2), _)) => Color::new(1.0, 0.2, 0.2),  // lava
-		Some((VoxelType(10), _)) => Color::new(1.0, 1.0, 0.7), // light
-		Some((_, t)) => {
-			// world.
-			let secundary_sunlight = direct_sunlight_point(voxels, ray.at(t as f64 - 0.01).map(|v| v as f32), normal);
-			let spatial_occlusion = f32::min(1.0, square(t as f32 / 32.0));
-			((0.5 * secundary_sunlight) + (0.03 * spatial_occlusion)) * Color::WHITE
-		}
+		None => Color::new(0.85, 0.85, 1.0), // sky
+		Some((voxel, t)) => match voxel.def().emissive {
+			Some(glow) => Color::new(glow.x(), glow.y(), glow.z()), // lava, light, ...
+			None => {
+				// world.
+				let secundary_sunlight = direct_sunlight_point(voxels, ray.at(t as f64 - 0.01).map(|v| v as f32), normal);
+				let spatial_occlusion = f32::min(1.0, square(t as f32 / 32.0));
+				((0.5 * secundary_sunlight) + (0.03 * spatial_occlusion)) * Color::WHITE
+			}
+		},
 	}
 }
 
@@ -145,6 +153,17 @@ fn sample_direct_light(rng: &mut ThreadRng, i: u32, (sh1, sh2): (f32, f32), voxe
 	}
 }
 
+// 1 if the sun is visible from `pos` (in a direction jittered like `sample_direct_light`), 0 otherwise.
+fn sample_sun_visibility(_: &mut ThreadRng, i: u32, (sh1, sh2): (f32, f32), voxels: &Voxels, pos: vec3, _: Direction) -> Color {
+	let (r, s) = halton23_scrambled(i, (sh1, sh2));
+	let dir = (SUN_DIR + 0.04 * cosine_sphere((r, s), SUN_DIR)).normalized();
+	let ray = Ray64::new(pos.into(), dir.into()).offset(0.01);
+	match voxels.intersects(&ray) {
+		false => Color::WHITE,
+		true => Color::BLACK,
+	}
+}
+
 // Direct sunlight from a single point probe.
 pub fn direct_sunlight_point(voxels: &Voxels, pos: vec3, normal: Direction) -> f32 {
 	let ambient = 0.0;
@@ -182,7 +201,7 @@ fn simple_occlusion(voxels: &Voxels, pos: ivec3, normal: Direction) -> f32 {
 
 	let mut ambient = -0.2;
 	for probe in probes {
-		if voxels.at(probe) == VoxelType::EMPTY {
+		if !voxels.at(probe).is_solid() {
 			ambient += 0.25;
 		}
 	}
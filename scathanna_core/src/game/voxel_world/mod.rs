@@ -3,14 +3,18 @@ mod internal;
 pub use bakery::*;
 pub use borders::*;
 pub use cell_model::*;
+pub use light_probes::*;
 pub use lightmap_baking::*;
+pub use occlusion::*;
 pub use voxel_world::*; // TODO: move
 
 mod bakery;
 mod borders;
 mod cell_model;
 mod join_tiles;
+mod light_probes;
 mod lightmap;
 mod lightmap_allocator;
 mod lightmap_baking;
+mod occlusion;
 mod voxel_world;
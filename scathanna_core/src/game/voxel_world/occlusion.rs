@@ -0,0 +1,138 @@
+use super::internal::*;
+
+/// Coarse occlusion culling: which cells are hidden behind solid voxels, as seen from the camera.
+///
+/// A cell counts as hidden when the lines of sight to its center and (slightly inset) corners
+/// all hit a solid voxel before reaching the cell. This is approximate:
+/// a cell seen only through a small opening may be hidden by mistake.
+///
+/// Only depends on the camera position (not orientation), so results are cached
+/// until the camera moves by more than `VIEWPOINT_GRID` or the voxels change (`invalidate`).
+#[derive(Default)]
+pub struct Occlusion {
+	viewpoint: Cell<Option<ivec3>>,
+	hidden: RefCell<HashMap<ivec3, bool>>,
+}
+
+// Camera positions are rounded to this many voxels before checking lines of sight.
+const VIEWPOINT_GRID: i32 = 4;
+
+// Distance from a cell's corners to the line of sight targets.
+const CORNER_INSET: f32 = 0.5;
+
+impl Occlusion {
+	/// Is the cell at `cell_pos` hidden from a camera at `camera_pos`?
+	pub fn is_hidden(&self, voxels: &Voxels, camera_pos: vec3, cell_pos: ivec3) -> bool {
+		let viewpoint = camera_pos.floor() / VIEWPOINT_GRID;
+		if self.viewpoint.get() != Some(viewpoint) {
+			self.viewpoint.set(Some(viewpoint));
+			self.hidden.borrow_mut().clear();
+		}
+		*self.hidden.borrow_mut().entry(cell_pos).or_insert_with(|| Self::check_hidden(voxels, camera_pos, cell_pos))
+	}
+
+	/// Forget cached results, e.g. after voxels have been destroyed.
+	pub fn invalidate(&self) {
+		self.viewpoint.set(None);
+	}
+
+	fn check_hidden(voxels: &Voxels, camera_pos: vec3, cell_pos: ivec3) -> bool {
+		let cell = Cuboid::cube(cell_pos, Voxels::CELL_SIZE);
+		if cell.contains(camera_pos.floor()) {
+			return false;
+		}
+		let (min, max) = (
+			cell.min.to_f32() + vec3(CORNER_INSET, CORNER_INSET, CORNER_INSET),
+			cell.max.to_f32() - vec3(CORNER_INSET, CORNER_INSET, CORNER_INSET),
+		);
+		let mut targets = vec![(min + max) / 2.0];
+		for x in [min.x(), max.x()] {
+			for y in [min.y(), max.y()] {
+				for z in [min.z(), max.z()] {
+					targets.push(vec3(x, y, z))
+				}
+			}
+		}
+		!targets.into_iter().any(|target| line_of_sight(voxels, camera_pos, target, &cell))
+	}
+}
+
+// Does the line from `from` to `to` reach `target` without passing through solid voxels first?
+// Voxels are visited in order along the line (Amanatides & Woo).
+fn line_of_sight(voxels: &Voxels, from: vec3, to: vec3, target: &Cuboid) -> bool {
+	let delta = to - from;
+	let len = delta.len();
+	if len == 0.0 {
+		return true;
+	}
+	let dir = delta / len;
+
+	let mut pos = from.floor();
+	let mut step = ivec3(0, 0, 0);
+	let mut t_max = vec3(f32::INFINITY, f32::INFINITY, f32::INFINITY); // distance along the line to the next voxel boundary, per axis
+	let mut t_delta = vec3(f32::INFINITY, f32::INFINITY, f32::INFINITY); // distance along the line between voxel boundaries, per axis
+	for axis in 0..3 {
+		if dir[axis] > 0.0 {
+			step[axis] = 1;
+			t_max[axis] = (pos[axis] as f32 + 1.0 - from[axis]) / dir[axis];
+			t_delta[axis] = 1.0 / dir[axis];
+		} else if dir[axis] < 0.0 {
+			step[axis] = -1;
+			t_max[axis] = (from[axis] - pos[axis] as f32) / -dir[axis];
+			t_delta[axis] = -1.0 / dir[axis];
+		}
+	}
+
+	// the camera's own voxel does not count (e.g. flying through walls in the editor).
+	loop {
+		let axis = if t_max.x() < t_max.y() && t_max.x() < t_max.z() {
+			0
+		} else if t_max.y() < t_max.z() {
+			1
+		} else {
+			2
+		};
+		if t_max[axis] > len {
+			return true;
+		}
+		pos[axis] += step[axis];
+		t_max[axis] += t_delta[axis];
+
+		if target.contains(pos) {
+			return true;
+		}
+		if voxels.at(pos).is_solid() {
+			return false;
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn hidden_behind_wall() {
+		let c = Voxels::ICELL_SIZE;
+		let mut voxels = Voxels::new();
+		// a wall filling the whole plane x = 2c..2c+1 (between the camera and cells further along x).
+		voxels.set_range(&Cuboid::new(ivec3(2 * c, -c, -c), ivec3(2 * c + 1, 2 * c, 2 * c)), VoxelType(5));
+
+		let occlusion = Occlusion::default();
+		let camera = vec3(10.0, 10.0, 10.0);
+		assert!(!occlusion.is_hidden(&voxels, camera, ivec3(c, 0, 0)));
+		assert!(occlusion.is_hidden(&voxels, camera, ivec3(3 * c, 0, 0)));
+		// the cell containing the wall itself is visible (we see the wall).
+		assert!(!occlusion.is_hidden(&voxels, camera, ivec3(2 * c, 0, 0)));
+
+		// a window in the wall.
+		voxels.set_range(&Cuboid::new(ivec3(2 * c, 0, 0), ivec3(2 * c + 1, c, c)), VoxelType::EMPTY);
+		occlusion.invalidate();
+		assert!(!occlusion.is_hidden(&voxels, camera, ivec3(3 * c, 0, 0)));
+
+		// water does not hide anything.
+		voxels.set_range(&Cuboid::new(ivec3(2 * c, 0, 0), ivec3(2 * c + 1, c, c)), VoxelType::WATER);
+		occlusion.invalidate();
+		assert!(!occlusion.is_hidden(&voxels, camera, ivec3(3 * c, 0, 0)));
+	}
+}
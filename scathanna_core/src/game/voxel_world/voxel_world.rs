@@ -103,12 +103,12 @@ impl VoxelWorld {
 
 	/// Draw voxel world as seen from camera.
 	pub fn draw(&mut self, engine: &Engine, camera: &Camera) {
-		let visible_range = Self::visible_range(camera); // TODO
+		let visible_range = Self::visible_range(camera, engine.view_distance());
 
 		self.receive_models(engine);
 
 		self.request_models_if_dirty(&visible_range);
-		self.draw_current_models(engine, &visible_range);
+		self.draw_current_models(engine, camera, &visible_range);
 	}
 
 	/// TODO
@@ -135,13 +135,22 @@ impl VoxelWorld {
 			.models
 			.into_iter()
 			.map(|(v, meshbuffer)| {
-				Model::new(
-					engine.build_vao(&meshbuffer),
-					Material::Lightmap {
-						texture: voxel_texture(engine, v),
-						lightmap: lm_tex.clone(),
-					},
-				)
+				let vao = engine.build_vao(&meshbuffer);
+				let texture = voxel_texture(engine, v);
+				let lightmap = lm_tex.clone();
+				match v.is_transparent() {
+					// water: seen from both above and below the surface.
+					true => Model::new(
+						vao,
+						Material::TranslucentLightmap {
+							texture,
+							lightmap,
+							alpha: WATER_ALPHA,
+						},
+					)
+					.double_sided(),
+					false => Model::new(vao, Material::Lightmap { texture, lightmap }),
+				}
 			})
 			.collect()
 	}
@@ -173,44 +182,56 @@ impl VoxelWorld {
 	}
 
 	/// Draw using the models that we currently have, possibly outdated.
-	fn draw_current_models(&self, engine: &Engine, visible_range: &Cuboid) {
+	fn draw_current_models(&self, engine: &Engine, camera: &Camera, visible_range: &Cuboid) {
 		engine.set_line_width(1.5);
-		for pos in visible_range.iter_by(Voxels::CELL_SIZE) {
-			if let Some(models) = self.models.get(&pos) {
-				for model in models {
-					engine.draw_model(model);
+		let frustum = engine.frustum(camera);
+		let mut cells = visible_range.iter_by(Voxels::CELL_SIZE).filter(|&pos| frustum.intersects(&Self::cell_bounds(pos))).collect::<Vec<_>>();
+
+		// translucent models (water) last, so that what's behind them has been drawn.
+		// Back to front, so that nearer water is blended over farther water.
+		for translucent in [false, true] {
+			if translucent {
+				let dist2 = |pos: ivec3| (Self::cell_bounds(pos).center() - camera.position).len2();
+				cells.sort_by(|&a, &b| dist2(b).partial_cmp(&dist2(a)).unwrap());
+			}
+			engine.set_translucent(translucent);
+			for &pos in &cells {
+				if let Some(models) = self.models.get(&pos) {
+					for model in models.iter().filter(|m| m.material.is_translucent() == translucent) {
+						engine.draw_model(model);
+					}
 				}
 			}
 		}
+		engine.set_translucent(false);
 	}
 
 	fn status(&self, cell_pos: ivec3) -> Status {
 		*self.status.get(&cell_pos).unwrap_or(&DirtyVAO)
 	}
 
-	pub fn visible_range(camera: &Camera) -> Cuboid {
-		let cam_ipos = camera.position.map(|v| v as i32); // TODO: * pitch
-		let r = 512; // TODO
+	/// Cells within `view_distance` from the camera, in any direction.
+	/// (Further narrowed down by the camera's Frustum before drawing).
+	pub fn visible_range(camera: &Camera, view_distance: f32) -> Cuboid {
+		let cam_ipos = camera.position.floor();
+		let r = view_distance.ceil() as i32;
 		let min = (cam_ipos - ivec3(r, r, r)).map(Voxels::align_down);
 		let max = (cam_ipos + ivec3(r, r, r)).map(Voxels::align_up);
 		Cuboid::new(min, max)
 	}
+
+	/// Bounding box of the cell at `cell_pos`, for culling.
+	pub fn cell_bounds(cell_pos: ivec3) -> BoundingBox<f32> {
+		BoundingBox::new(cell_pos.to_f32(), (cell_pos + ivec3(1, 1, 1) * Voxels::ICELL_SIZE).to_f32())
+	}
 }
 
-/// Texture for a voxel type (sand, lava, ...)
+// Opacity of water surfaces.
+const WATER_ALPHA: f32 = 0.6;
+
+/// Texture for a voxel type (sand, lava, ...), as defined by the Palette.
 fn voxel_texture(engine: &Engine, voxel: VoxelType) -> Rc<Texture> {
-	match voxel.0 {
-		0 => panic!("requesting texture for empty voxel"),
-		1 => engine.texture("snow", WHITE),             //
-		2 => engine.texture("lava", RED),               //
-		3 => engine.texture("plasma", GREEN),           //
-		4 => engine.texture("whitestone2", LIGHT_GREY), //
-		5 => engine.texture("greystone", DARK_GREY),    //
-		6 => engine.texture("sand", YELLOW),            //
-		7 => engine.texture("whiterstone", GREY),       //
-		8 => engine.texture("stars", MAGENTA),          //
-		9 => engine.texture("sponge", GREY),            //
-		10 => engine.texture("light", YELLOW),          //
-		_ => engine.texture("xy", GREY),
-	}
+	assert!(!voxel.is_empty(), "requesting texture for empty voxel");
+	let def = voxel.def();
+	engine.texture(def.texture(), def.color)
 }
@@ -4,7 +4,7 @@ use super::internal::*;
 /// Used to index VoxelStore Ranges.
 /// TODO: private members.
 
-#[derive(PartialEq, Eq, Debug)]
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct Cuboid {
 	pub min: ivec3,
 	pub max: ivec3,
@@ -5,6 +5,7 @@ pub use super::cubetype::*;
 pub use super::cuboid::*;
 pub use super::direction::*;
 pub use super::node::*;
+pub use super::palette::*;
 pub use super::rectangle::*;
 pub use super::voxels::*;
 pub use super::voxeltype::*;
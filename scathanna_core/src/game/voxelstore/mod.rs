@@ -5,6 +5,7 @@ mod cubetype;
 mod cuboid;
 mod direction;
 mod node;
+mod palette;
 mod rectangle;
 mod voxels;
 mod voxeltype;
@@ -14,6 +15,7 @@ pub use cubetype::*;
 pub use cuboid::*;
 pub use direction::*;
 pub use node::*;
+pub use palette::*;
 pub use rectangle::*;
 pub use voxels::*;
 pub use voxeltype::*;
@@ -104,8 +104,11 @@ impl Node {
 	}
 
 	pub fn intersect(&self, self_range: Cube, ray: &Ray64) -> Option<(VoxelType, f64)> {
-		if self == &Node::Uniform(VoxelType(0)) {
-			return None;
+		// rays pass through empty space and water.
+		if let Node::Uniform(v) = self {
+			if !v.is_solid() {
+				return None;
+			}
 		}
 
 		let size = self_range.size() as i32;
@@ -0,0 +1,198 @@
+use super::internal::*;
+use std::ops::Index;
+use std::sync::OnceLock;
+
+/// Material properties of each voxel type, loaded from `assets/palette.json`
+/// so that new blocks can be added without recompiling.
+///
+/// Read by rendering, lightmap baking, physics and sound alike.
+/// Voxel types absent from the file look like a plain grey block.
+/// Server and clients must use the same file (checked on join, see `JoinMsg::assets_hash`).
+pub struct Palette {
+	defs: Vec<VoxelDef>, // indexed by VoxelType, always 256 entries.
+	hash: u64,           // of the file contents
+}
+
+/// A single voxel type, as defined in `assets/palette.json`.
+#[derive(Deserialize, Clone, Debug)]
+pub struct VoxelDef {
+	/// VoxelType number as stored in maps (1-255, 0 is always empty).
+	pub id: u8,
+
+	/// E.g. "sand". Also the default texture name.
+	pub name: String,
+
+	/// Texture (defaults to `name`).
+	#[serde(default)]
+	pub texture: Option<String>,
+
+	/// Color used when the texture is missing.
+	#[serde(default = "grey")]
+	pub color: vec3,
+
+	/// Glows: faces are drawn at full brightness instead of being lit by the sun,
+	/// and light up their surroundings with this color.
+	#[serde(default)]
+	pub emissive: Option<vec3>,
+
+	/// Grip when walking on top: 1 (default) stops and turns instantly, lower is slippery.
+	#[serde(default = "one")]
+	pub friction: f32,
+
+	/// Fraction of the vertical speed kept when landing on top (0: no bounce).
+	#[serde(default)]
+	pub bounciness: f32,
+
+	/// Health lost when standing on top, repeated every second
+	/// (100 kills instantly, like lava).
+	#[serde(default)]
+	pub damage: i32,
+
+	/// Footstep sounds, picked at random (defaults to regular footsteps).
+	#[serde(default)]
+	pub footsteps: Vec<String>,
+
+	/// Blocks players and bullets. Non-solid voxels (water) can be swum through
+	/// and do not hide the faces of voxels behind them.
+	#[serde(default = "yes")]
+	pub solid: bool,
+}
+
+const PALETTE_FILE: &str = "assets/palette.json";
+
+static PALETTE: OnceLock<Palette> = OnceLock::new();
+
+impl Palette {
+	/// Load the palette, if not yet loaded.
+	/// Call on startup to report a missing or invalid file gracefully,
+	/// instead of panicking on first use.
+	pub fn init() -> Result<()> {
+		if PALETTE.get().is_none() {
+			let _ = PALETTE.set(Self::load()?);
+		}
+		Ok(())
+	}
+
+	/// The palette (loaded on first use if `init` was not called).
+	pub fn get() -> &'static Self {
+		PALETTE.get_or_init(|| Self::load().unwrap_or_else(|err| panic!("{}", err)))
+	}
+
+	fn load() -> Result<Self> {
+		let file = asset_path(PALETTE_FILE);
+		let (defs, hash) = load_json(&file)?;
+		let palette = Self::from_defs(defs).map_err(|err| anyhow!("Error in {}: {}", file.to_string_lossy(), err))?;
+		Ok(Self { hash, ..palette })
+	}
+
+	fn from_defs(defs: Vec<VoxelDef>) -> Result<Self> {
+		let mut table: Vec<Option<VoxelDef>> = vec![None; 256];
+		for def in defs {
+			if def.id == VoxelType::EMPTY.0 {
+				return Err(anyhow!("{}: id 0 is reserved for empty space", def.name));
+			}
+			if def.friction.is_nan() || def.friction < 0.0 {
+				return Err(anyhow!("{}: friction must be >= 0, got {}", def.name, def.friction));
+			}
+			if def.bounciness.is_nan() || !(0.0..=1.0).contains(&def.bounciness) {
+				return Err(anyhow!("{}: bounciness must be between 0 and 1, got {}", def.name, def.bounciness));
+			}
+			if def.damage < 0 {
+				return Err(anyhow!("{}: damage must be >= 0, got {}", def.name, def.damage));
+			}
+			let slot = &mut table[def.id as usize];
+			if let Some(other) = slot {
+				return Err(anyhow!("duplicate voxel id {}: {} and {}", def.id, other.name, def.name));
+			}
+			*slot = Some(def);
+		}
+		let defs = table.into_iter().enumerate().map(|(id, def)| def.unwrap_or_else(|| VoxelDef::fallback(id as u8))).collect();
+		Ok(Self { defs, hash: 0 })
+	}
+
+	/// Hash of the file the palette was loaded from,
+	/// to tell if server and client use the same one.
+	pub fn hash(&self) -> u64 {
+		self.hash
+	}
+}
+
+impl Index<VoxelType> for Palette {
+	type Output = VoxelDef;
+
+	fn index(&self, voxel: VoxelType) -> &VoxelDef {
+		&self.defs[voxel.0 as usize]
+	}
+}
+
+impl VoxelDef {
+	// Undefined voxel types (and empty space, which is never drawn).
+	fn fallback(id: u8) -> Self {
+		Self {
+			id,
+			name: format!("voxel{}", id),
+			texture: Some("xy".into()),
+			color: GREY,
+			emissive: None,
+			friction: 1.0,
+			bounciness: 0.0,
+			damage: 0,
+			footsteps: Vec::new(),
+			solid: id != VoxelType::EMPTY.0,
+		}
+	}
+
+	pub fn texture(&self) -> &str {
+		self.texture.as_deref().unwrap_or(&self.name)
+	}
+}
+
+fn grey() -> vec3 {
+	GREY
+}
+
+fn one() -> f32 {
+	1.0
+}
+
+fn yes() -> bool {
+	true
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn parse_defaults() {
+		let defs: Vec<VoxelDef> = serde_json::from_str(r#"[{"id": 3, "name": "ice", "friction": 0.1}]"#).unwrap();
+		let palette = Palette::from_defs(defs).unwrap();
+		let ice = &palette[VoxelType(3)];
+		assert_eq!(ice.texture(), "ice");
+		assert_eq!(ice.color, GREY);
+		assert_eq!(ice.emissive, None);
+		assert_eq!(ice.friction, 0.1);
+		assert_eq!(ice.bounciness, 0.0);
+		assert_eq!(ice.damage, 0);
+		assert!(ice.footsteps.is_empty());
+		assert!(ice.solid);
+
+		let undefined = &palette[VoxelType(4)];
+		assert_eq!(undefined.texture(), "xy");
+		assert!(undefined.solid);
+		assert!(!palette[VoxelType::EMPTY].solid);
+	}
+
+	#[test]
+	fn reject_invalid() {
+		for json in [
+			r#"[{"id": 0, "name": "nothing"}]"#,
+			r#"[{"id": 1, "name": "a"}, {"id": 1, "name": "b"}]"#,
+			r#"[{"id": 1, "name": "trampoline", "bounciness": 2}]"#,
+			r#"[{"id": 1, "name": "glue", "friction": -1}]"#,
+		] {
+			let defs: Vec<VoxelDef> = serde_json::from_str(json).unwrap();
+			assert!(Palette::from_defs(defs).is_err(), "{}", json);
+		}
+	}
+}
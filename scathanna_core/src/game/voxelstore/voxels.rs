@@ -148,7 +148,8 @@ impl Voxels {
 
 		let neigbour_cube = cube.translate(dir.ivec() * cube.size() as i32);
 		match self.query_cube(neigbour_cube) {
-			CubeType::Uniform(VoxelType::EMPTY) => {
+			// faces are seen through empty space and through water (but not water through water).
+			CubeType::Uniform(neighbour) if neighbour.is_empty() || (neighbour.is_transparent() && neighbour != voxel) => {
 				let face = Rectangle {
 					position: cube.position() + dir.unoriented_vec() * dir.side_index() as i32 * (cube.size() as i32),
 					size: uvec2(cube.size(), cube.size()),
@@ -309,15 +310,16 @@ impl Voxels {
 		Self::deserialize(std::io::Cursor::new(map_data))
 	}
 
+	/// Does `bounds` overlap any solid voxel? (Merely touching does not count).
 	pub fn bumps(&self, bounds: &BoundingBox<f32>) -> bool {
 		let imin = bounds.min.map(f32::floor).floor();
-		let imax = bounds.max.map(f32::ceil).floor();
+		let imax = bounds.max.map(f32::ceil).floor() - ivec3(1, 1, 1);
 
 		for iz in imin.z()..=imax.z() {
 			for iy in imin.y()..=imax.y() {
 				for ix in imin.x()..=imax.x() {
 					let pos = ivec3(ix, iy, iz);
-					if !self.at(pos).is_empty() {
+					if self.at(pos).is_solid() {
 						return true;
 					}
 				}
@@ -417,6 +419,29 @@ mod test {
 	}
 	*/
 
+	#[test]
+	fn water() {
+		let mut store = Voxels::new();
+		// sand floor, with a pool 4 deep on top.
+		store.set_range(&Cuboid::new(ivec3(0, 0, 0), ivec3(16, 4, 16)), VoxelType(6));
+		store.set_range(&Cuboid::new(ivec3(0, 4, 0), ivec3(16, 8, 16)), VoxelType::WATER);
+
+		// can swim and shoot through water, but not through sand.
+		assert!(!store.bumps(&BoundingBox::new(vec3(4.0, 4.5, 4.0), vec3(6.0, 7.5, 6.0))));
+		assert!(store.bumps(&BoundingBox::new(vec3(4.0, 3.5, 4.0), vec3(6.0, 7.5, 6.0))));
+		let down = Ray64::new(dvec3(4.5, 10.0, 4.5), dvec3(0.0, -1.0, 0.0));
+		assert_eq!(store.intersect(&down).map(|(v, _)| v), Some(VoxelType(6)));
+
+		// the sand floor is seen through the water, the water surface is seen from the air.
+		let faces = store.collect_faces(ivec3(0, 0, 0));
+		let up = |v: VoxelType, y: i32| faces.iter().any(|(r, voxel)| *voxel == v && r.direction == Direction::Y && r.position.y() == y);
+		assert!(up(VoxelType(6), 4));
+		assert!(up(VoxelType::WATER, 8));
+		// no faces between water and water, or water facing sand.
+		assert!(!faces.iter().any(|(r, voxel)| *voxel == VoxelType::WATER && r.direction == Direction::MinusY));
+		assert!(!faces.iter().any(|(r, voxel)| *voxel == VoxelType::WATER && r.direction == Direction::Y && r.position.y() != 8));
+	}
+
 	#[test]
 	fn nonempty_cubes() {
 		let mut store = Voxels::new();
@@ -8,8 +8,24 @@ impl VoxelType {
 		self.0 == Self::EMPTY.0
 	}
 
+	/// Blocks players and bullets? (Not empty, not water. See `Palette`).
+	pub fn is_solid(&self) -> bool {
+		!self.is_empty() && Palette::get()[*self].solid
+	}
+
+	/// Does not hide the faces of voxels behind it (water).
+	pub fn is_transparent(&self) -> bool {
+		!self.is_empty() && !Palette::get()[*self].solid
+	}
+
+	/// Material properties (texture, friction, ...).
+	pub fn def(&self) -> &'static VoxelDef {
+		&Palette::get()[*self]
+	}
+
 	pub const EMPTY: Self = Self(0);
 	pub const LAVA: Self = Self(2);
+	pub const WATER: Self = Self(11);
 }
 
 impl fmt::Display for VoxelType {
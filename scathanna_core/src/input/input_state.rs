@@ -69,6 +69,13 @@ impl InputState {
 		self.released[key as usize]
 	}
 
+	/// Turn the absolute viewing angles to `(yaw, pitch)`
+	/// (e.g. to face a given direction after respawning).
+	pub fn set_mouse(&mut self, yaw: f32, pitch: f32) {
+		self.mouse_yaw = wrap_angle(yaw);
+		self.mouse_pitch = clamp(pitch, -PI / 2.0, PI / 2.0);
+	}
+
 	/// Absolute viewing angle wrt. the -Z axis (positive = CCW),
 	/// accumulated by all mouse movements ever (i.e.: not reset on clear()).
 	pub fn mouse_yaw(&self) -> f32 {
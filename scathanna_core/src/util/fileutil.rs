@@ -1,6 +1,8 @@
 use super::internal::*;
 
+use serde::de::DeserializeOwned;
 use std::fs::File;
+use std::hash::Hasher;
 use std::io::BufReader;
 use std::io::BufWriter;
 use std::io::Read;
@@ -51,6 +53,30 @@ pub fn abs_path(file: &Path) -> PathBuf {
 	}
 }
 
+/// Path of a file in `assets/`, e.g. "assets/palette.json", found via `abs_path`.
+#[cfg(not(test))]
+pub fn asset_path(file: &str) -> PathBuf {
+	abs_path(Path::new(file))
+}
+
+// Tests do not run in the directory containing `assets/`,
+// and many of them touch voxels or powerups.
+#[cfg(test)]
+pub fn asset_path(file: &str) -> PathBuf {
+	Path::new(env!("CARGO_MANIFEST_DIR")).join("..").join(file)
+}
+
+/// Parse a JSON file, and hash its contents
+/// (to tell if server and client use the same file).
+pub fn load_json<T: DeserializeOwned>(file: &Path) -> Result<(T, u64)> {
+	let mut bytes = Vec::new();
+	open(file)?.read_to_end(&mut bytes)?;
+	let value = serde_json::from_slice(&bytes).map_err(|err| anyhow!("Error in {}: {}", file.to_string_lossy(), err))?;
+	let mut hasher = fnv::FnvHasher::default();
+	hasher.write(&bytes);
+	Ok((value, hasher.finish()))
+}
+
 /// Spawn a thread that reads lines from stdin,
 /// pipes them through a channel.
 /// Used for non-blocking stdin reads.